@@ -0,0 +1,256 @@
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::core::{
+  color::Color,
+  image::Image,
+  track::Track,
+  point3::Point3,
+  vec3::Vec3,
+  ray::Ray,
+};
+use crate::utils::utils::random_double_in_range;
+
+use super::{Camera, CameraBuilder, CameraError, PerspectiveCamera, Region, render_region};
+
+// Intervals the shutter of a frame is split into, rays being traced from cameras interpolated
+// between the ones set up at their ends
+const SHUTTER_STEPS: usize = 16;
+
+/**
+ * Perspective camera whose position, target, field of view and focus distance follow keyframe
+ * tracks, over the other parameters of a `CameraBuilder`. Parameters without a track keep the
+ * value of the builder; without a focus track the camera focuses on its target as it moves.
+ **/
+#[derive(Clone, Debug)]
+pub struct AnimatedCamera {
+  base: CameraBuilder,
+  look_from: Option<Track<Point3>>,
+  look_at: Option<Track<Point3>>,
+  vfov: Option<Track<f64>>,
+  focus_dist: Option<Track<f64>>,
+}
+
+impl AnimatedCamera {
+  pub fn new(base: CameraBuilder) -> Self {
+    AnimatedCamera { base, look_from: None, look_at: None, vfov: None, focus_dist: None }
+  }
+
+  /**
+   * Animated counterpart of `PerspectiveCamera::new_with_lens`, with the position, target and
+   * field of view given as tracks.
+   **/
+  pub fn new_with_lens(
+    look_from: Track<Point3>,
+    look_at: Track<Point3>,
+    vup: Vec3,
+    vfov: Track<f64>,
+    aspect_ratio: f64,
+    aperture: f64,
+  ) -> Self {
+    Self::new(CameraBuilder::new().vup(vup).aspect(aspect_ratio).aperture(aperture))
+      .look_from(look_from)
+      .look_at(look_at)
+      .vfov(vfov)
+  }
+
+  pub fn look_from(mut self, track: Track<Point3>) -> Self {
+    self.look_from = Some(track);
+    self
+  }
+
+  pub fn look_at(mut self, track: Track<Point3>) -> Self {
+    self.look_at = Some(track);
+    self
+  }
+
+  pub fn vfov(mut self, track: Track<f64>) -> Self {
+    self.vfov = Some(track);
+    self
+  }
+
+  pub fn focus_dist(mut self, track: Track<f64>) -> Self {
+    self.focus_dist = Some(track);
+    self
+  }
+
+  /**
+   * Parameters of the camera at `time`, with the shutter closed on that instant.
+   **/
+  pub fn builder_at(&self, time: f64) -> CameraBuilder {
+    let mut builder = self.base.clone().shutter(time, time);
+    if let Some(track) = &self.look_from {
+      builder = builder.look_from(track.sample(time));
+    }
+    if let Some(track) = &self.look_at {
+      builder = builder.look_at(track.sample(time));
+    }
+    if let Some(track) = &self.vfov {
+      builder = builder.vfov(track.sample(time));
+    }
+    if let Some(track) = &self.focus_dist {
+      builder = builder.focus_dist(track.sample(time));
+    }
+    builder
+  }
+
+  pub fn camera_at(&self, time: f64) -> Result<PerspectiveCamera, CameraError> {
+    self.builder_at(time).build()
+  }
+
+  /**
+   * Camera for a frame with the shutter open from `open` to `close`: each ray is traced from the
+   * camera as it is at a random time of the interval, which blurs the motion of the camera and of
+   * the objects alike. The cameras are set up at steps of the interval, any invalid one failing
+   * the frame, and interpolated in between.
+   **/
+  pub fn frame(&self, open: f64, close: f64) -> Result<FrameCamera, CameraError> {
+    if !(open.is_finite() && close.is_finite() && open <= close) {
+      return Err(CameraError::Shutter(open, close));
+    }
+    let steps = if close > open { SHUTTER_STEPS } else { 0 };
+    let cameras = (0..=steps)
+      .map(|k| self.camera_at(if k == steps { close } else { open + (close - open) * k as f64 / steps as f64 }))
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(FrameCamera { open, close, cameras })
+  }
+}
+
+/**
+ * An `AnimatedCamera` during the exposure of a frame.
+ **/
+#[derive(Clone, Debug)]
+pub struct FrameCamera {
+  open: f64,
+  close: f64,
+  // At the ends of the steps of the shutter interval
+  cameras: Vec<PerspectiveCamera>,
+}
+
+impl FrameCamera {
+  fn camera(&self) -> PerspectiveCamera {
+    let steps = self.cameras.len() - 1;
+    if steps == 0 {
+      return self.cameras[0].clone();
+    }
+    let time = random_double_in_range(self.open, self.close);
+    let x = (time - self.open) / (self.close - self.open) * steps as f64;
+    let k = (x as usize).min(steps - 1);
+    self.cameras[k].between(&self.cameras[k + 1], x - k as f64, time)
+  }
+}
+
+impl Camera for FrameCamera {
+  fn generate_ray(&self, film: (f64, f64), sample: (f64, f64)) -> Ray {
+    self.camera().generate_ray(film, sample)
+  }
+
+  fn generate_weighted_ray(&self, film: (f64, f64), sample: (f64, f64)) -> (Ray, f64) {
+    self.camera().generate_weighted_ray(film, sample)
+  }
+}
+
+/**
+ * Render settings of a sequence of frames at `fps` frames per second. Frame n (from 1, frame 0
+ * being before the start) opens its shutter at (n - 1) / fps for `shutter` of the frame duration, half of it like the 180 degree
+ * shutter of film cameras by default.
+ **/
+#[derive(Clone, Debug)]
+pub struct Sequence {
+  pub width: u32,
+  pub height: u32,
+  pub samples_per_pixel: u32,
+  pub fps: f64,
+  pub shutter: f64,
+  pub first_frame: u32,
+  pub last_frame: u32,
+}
+
+impl Sequence {
+  pub fn new(width: u32, height: u32, fps: f64, frames: u32) -> Self {
+    Sequence { width, height, samples_per_pixel: 100, fps, shutter: 0.5, first_frame: 1, last_frame: frames }
+  }
+
+  /**
+   * Shutter interval of frame `n`.
+   **/
+  pub fn frame_time(&self, n: u32) -> (f64, f64) {
+    let open = (n as f64 - 1.0) / self.fps;
+    (open, open + self.shutter / self.fps)
+  }
+
+  /**
+   * Render the frames to `frame_0001.png`... in `directory`, created if needed, with the radiance
+   * along camera rays given by `ray_color`. Returns the paths of the frames.
+   **/
+  pub fn render<F>(&self, camera: &AnimatedCamera, directory: &Path, ray_color: F) -> io::Result<Vec<PathBuf>>
+  where
+    F: Fn(&Ray) -> Color + Sync,
+  {
+    if self.first_frame == 0 {
+      return Err(Error::new(ErrorKind::InvalidInput, "frames are numbered from 1"));
+    }
+    fs::create_dir_all(directory)?;
+    let mut paths = Vec::new();
+    for n in self.first_frame..=self.last_frame {
+      let (open, close) = self.frame_time(n);
+      let frame = camera.frame(open, close)
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("frame {}: {}", n, e)))?;
+      let image = self.render_frame(n, &frame, &ray_color);
+      let path = directory.join(format!("frame_{:04}.png", n));
+      image.write_png(&path)?;
+      eprintln!("\rFrame {0} written to {1}", n, path.display());
+      paths.push(path);
+    }
+    Ok(paths)
+  }
+
+  /**
+   * Frame `n` through `camera`, its camera samples seeded by its number: rendering it again
+   * takes the same rays, and the sampling pattern changes from frame to frame.
+   **/
+  pub fn render_frame<C, F>(&self, n: u32, camera: &C, ray_color: &F) -> Image
+  where
+    C: Camera,
+    F: Fn(&Ray) -> Color + Sync,
+  {
+    let full = Region::full(self.width, self.height);
+    render_region(camera, self.width, self.height, &full, self.samples_per_pixel, n as u64, ray_color)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_frame() {
+    // Dolly along x, looking down -z
+    let camera = AnimatedCamera::new(CameraBuilder::new().aspect(1.0))
+      .look_from(Track::new(0.0, Point3::new(0.0, 0.0, 0.0)).key(1.0, Point3::new(1.0, 0.0, 0.0)))
+      .look_at(Track::new(0.0, Point3::new(0.0, 0.0, -1.0)).key(1.0, Point3::new(1.0, 0.0, -1.0)))
+      .vfov(Track::new(0.0, 90.0).key(1.0, 30.0));
+    let frame = camera.frame(0.25, 0.5).unwrap();
+    for _ in 0..20 {
+      let r = frame.generate_ray((0.5, 0.5), (0.5, 0.5));
+      assert!(r.origin.x >= 0.25 && r.origin.x <= 0.5);
+      assert!((r.time - r.origin.x).abs() < 1e-12);
+    }
+    let still = camera.frame(0.5, 0.5).unwrap().generate_ray((1.0, 0.5), (0.5, 0.5));
+    assert!((still.direction.unit().x - (30.0f64).to_radians().sin()).abs() < 1e-12);
+
+    assert_eq!(camera.frame(0.5, 0.25).unwrap_err(), CameraError::Shutter(0.5, 0.25));
+    // The field of view goes out of range during the second frame
+    let zoom = camera.clone().vfov(Track::new(0.0, 90.0).key(1.0, 270.0));
+    assert!(zoom.frame(0.0, 0.25).is_ok());
+    assert!(zoom.frame(0.25, 0.75).is_err());
+
+    let sequence = Sequence::new(4, 4, 24.0, 48);
+    assert_eq!(sequence.frame_time(1), (0.0, 0.5 / 24.0));
+    assert_eq!(sequence.frame_time(25), (1.0, 1.0 + 0.5 / 24.0));
+    assert_eq!(sequence.frame_time(0).0, -1.0 / 24.0);
+    let from_zero = Sequence { first_frame: 0, ..sequence };
+    assert!(from_zero.render(&camera, Path::new("target/no_frames"), |_: &Ray| Color::new(0.0, 0.0, 0.0)).is_err());
+  }
+}
@@ -0,0 +1,203 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::core::{PI, image::GrayImage};
+
+use super::sample_disk;
+
+/**
+ * Shape of the opening of a thin lens, which out of focus highlights take.
+ * Points are sampled in the unit disk (the mask fills the square around it), scaled by the lens
+ * radius. Masks are shared, so cameras using them stay cheap to clone.
+ **/
+#[derive(Clone, Debug, Default)]
+pub enum Aperture {
+  #[default]
+  Disk,
+  // Regular polygon of `blades` sides, the first corner at `rotation` radians from the u axis
+  Polygon { blades: u32, rotation: f64 },
+  Mask(Arc<ApertureMask>),
+}
+
+impl Aperture {
+  /**
+   * Polygon made by `blades` diaphragm blades, rotated by `rotation` degrees.
+   **/
+  pub fn polygon(blades: u32, rotation: f64) -> Self {
+    Aperture::Polygon { blades, rotation: rotation.to_radians() }
+  }
+
+  pub fn mask(mask: ApertureMask) -> Self {
+    Aperture::Mask(Arc::new(mask))
+  }
+
+  /**
+   * Point of the aperture from a point of [0, 1)^2, uniformly over its area (weighted by the
+   * transmission of a mask).
+   **/
+  pub fn sample(&self, sample: (f64, f64)) -> (f64, f64) {
+    match self {
+      Aperture::Disk => sample_disk(sample),
+      Aperture::Polygon { blades, rotation } => {
+        // Pick one of the triangles between the center and a side, reusing the rest of the sample
+        let n = *blades as f64;
+        let k = (sample.0 * n).floor().min(n - 1.0);
+        let s = sample.0 * n - k;
+        let (a0, a1) = (rotation + 2.0 * PI * k / n, rotation + 2.0 * PI * (k + 1.0) / n);
+        let su = s.sqrt();
+        let (b0, b1) = (su * (1.0 - sample.1), su * sample.1);
+        (b0 * a0.cos() + b1 * a1.cos(), b0 * a0.sin() + b1 * a1.sin())
+      },
+      Aperture::Mask(mask) => mask.sample(sample),
+    }
+  }
+}
+
+/**
+ * Grayscale image of the transmission of the aperture over the square [-1, 1]^2 around the lens,
+ * x varying fastest and rows from the top. Sampled through the cumulative distributions of its
+ * rows and of the pixels in each row.
+ * Refer to: https://www.pbr-book.org/3ed-2018/Monte_Carlo_Integration/2D_Sampling_with_Multidimensional_Transformations
+ **/
+#[derive(Clone, Debug)]
+pub struct ApertureMask {
+  nx: usize,
+  ny: usize,
+  // Cumulative transmission over the rows, normalized to end at 1
+  rows: Vec<f64>,
+  // Normalized cumulative transmission within each row, nx + 1 values per row
+  columns: Vec<f64>,
+}
+
+impl ApertureMask {
+  pub fn new(nx: usize, ny: usize, values: Vec<f64>) -> Result<Self> {
+    if nx == 0 || ny == 0 || values.len() != nx * ny {
+      return Err(Error::new(
+        ErrorKind::InvalidData,
+        format!("expected {}x{} transmission values, got {}", nx, ny, values.len()),
+      ));
+    }
+    if values.iter().any(|v| !(v.is_finite() && *v >= 0.0)) {
+      return Err(Error::new(ErrorKind::InvalidData, "transmission must be finite and not negative"));
+    }
+
+    let mut rows = vec![0.0; ny + 1];
+    let mut columns = vec![0.0; (nx + 1) * ny];
+    for j in 0..ny {
+      let cdf = &mut columns[j * (nx + 1)..(j + 1) * (nx + 1)];
+      for i in 0..nx {
+        cdf[i + 1] = cdf[i] + values[j * nx + i];
+      }
+      let total = cdf[nx];
+      rows[j + 1] = rows[j] + total;
+      if total > 0.0 {
+        cdf.iter_mut().for_each(|c| *c /= total);
+      }
+    }
+    let total = rows[ny];
+    if total <= 0.0 {
+      return Err(Error::new(ErrorKind::InvalidData, "the aperture mask lets no light through"));
+    }
+    rows.iter_mut().for_each(|r| *r /= total);
+    Ok(ApertureMask { nx, ny, rows, columns })
+  }
+
+  /**
+   * Fill the mask by evaluating `transmission` at the pixel centers (x, y) of [-1, 1]^2, y up.
+   **/
+  pub fn from_fn<F: Fn(f64, f64) -> f64>(nx: usize, ny: usize, transmission: F) -> Result<Self> {
+    let mut values = Vec::with_capacity(nx * ny);
+    for j in 0..ny {
+      for i in 0..nx {
+        let x = 2.0 * (i as f64 + 0.5) / nx as f64 - 1.0;
+        let y = 1.0 - 2.0 * (j as f64 + 0.5) / ny as f64;
+        values.push(transmission(x, y));
+      }
+    }
+    Self::new(nx, ny, values)
+  }
+
+  /**
+   * Load a `.pgm` or `.png` image, white lets light through.
+   **/
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+    let image = GrayImage::load(path)?;
+    Self::new(image.width(), image.height(), image.values().to_vec())
+  }
+
+  pub fn size(&self) -> (usize, usize) {
+    (self.nx, self.ny)
+  }
+
+  fn sample(&self, sample: (f64, f64)) -> (f64, f64) {
+    let (j, fy) = sample_cdf(&self.rows, sample.1);
+    let (i, fx) = sample_cdf(&self.columns[j * (self.nx + 1)..(j + 1) * (self.nx + 1)], sample.0);
+    let x = 2.0 * (i as f64 + fx) / self.nx as f64 - 1.0;
+    let y = 1.0 - 2.0 * (j as f64 + fy) / self.ny as f64;
+    (x, y)
+  }
+}
+
+/**
+ * Bin of a normalized cumulative distribution where `u` falls and the offset of `u` in it.
+ **/
+fn sample_cdf(cdf: &[f64], u: f64) -> (usize, f64) {
+  // Last entry not above u, skipping empty bins
+  let index = cdf.partition_point(|c| *c <= u).clamp(1, cdf.len() - 1) - 1;
+  let width = cdf[index + 1] - cdf[index];
+  let offset = if width > 0.0 { ((u - cdf[index]) / width).min(1.0) } else { 0.5 };
+  (index, offset)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn grid(n: usize) -> impl Iterator<Item = (f64, f64)> {
+    (0..n * n).map(move |k| (((k % n) as f64 + 0.5) / n as f64, ((k / n) as f64 + 0.5) / n as f64))
+  }
+
+  #[test]
+  fn test_polygon() {
+    let hexagon = Aperture::polygon(6, 90.0);
+    let apothem = (PI / 6.0).cos();
+    let mut farthest: f64 = 0.0;
+    for sample in grid(32) {
+      let (x, y) = hexagon.sample(sample);
+      let r = (x * x + y * y).sqrt();
+      farthest = farthest.max(r);
+      // Inside each side, the sides face 30 degrees off the corners
+      for k in 0..6 {
+        let a = PI / 2.0 + PI / 6.0 + k as f64 * PI / 3.0;
+        assert!(x * a.cos() + y * a.sin() <= apothem + 1e-12);
+      }
+    }
+    assert!(farthest > 0.95);
+    // The first corner points up
+    let (x, y) = hexagon.sample((1.0 / 6.0 - 1e-12, 0.0));
+    assert!(x.abs() < 1e-9 && (y - 1.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_mask() {
+    // Upper right quadrant three times as bright as the lower left one, the rest is opaque
+    let mask = ApertureMask::new(2, 2, vec![0.0, 3.0, 1.0, 0.0]).unwrap();
+    let aperture = Aperture::mask(mask);
+    let (mut upper, mut lower) = (0, 0);
+    for sample in grid(40) {
+      let (x, y) = aperture.sample(sample);
+      if x > 0.0 && y > 0.0 {
+        upper += 1;
+      } else {
+        assert!(x < 0.0 && y < 0.0);
+        lower += 1;
+      }
+    }
+    assert_eq!(upper, 3 * lower);
+    assert!(ApertureMask::new(2, 2, vec![0.0; 4]).is_err());
+    assert!(ApertureMask::new(2, 2, vec![1.0, -1.0, 1.0, 1.0]).is_err());
+    let mask = ApertureMask::from_fn(8, 4, |x, y| if x * x + y * y < 1.0 { 1.0 } else { 0.0 }).unwrap();
+    assert_eq!(mask.size(), (8, 4));
+  }
+}
@@ -0,0 +1,121 @@
+use crate::core::{
+  PI,
+  vec3::Vec3,
+};
+use crate::geometry::hit::Hittable;
+
+use super::{Camera, CameraBuilder, CameraError};
+
+/**
+ * Where to focus: the nearest surface seen through a point of the film, probed by a single ray or
+ * a small cone of rays around it so thin or missed subjects still count.
+ * `film` is in [0, 1]^2 from the lower left corner, `spread` the radius of the cone on the film
+ * (as a fraction of its height).
+ **/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoFocus {
+  pub film: (f64, f64),
+  pub spread: f64,
+  pub rays: usize,
+}
+
+impl Default for AutoFocus {
+  /**
+   * A single ray through the center of the frame.
+   **/
+  fn default() -> Self {
+    AutoFocus::new((0.5, 0.5))
+  }
+}
+
+impl AutoFocus {
+  pub fn new(film: (f64, f64)) -> Self {
+    AutoFocus { film, spread: 0.0, rays: 1 }
+  }
+
+  pub fn cone(mut self, spread: f64, rays: usize) -> Self {
+    self.spread = spread;
+    self.rays = rays;
+    self
+  }
+
+  /**
+   * Distance along the view direction to the nearest surface hit by the probe rays, from the
+   * center of the lens of the camera `builder` describes.
+   **/
+  pub fn distance(&self, builder: &CameraBuilder, world: &dyn Hittable) -> Result<f64, CameraError> {
+    let mut pinhole = builder.clone().focus_dist(1.0);
+    pinhole.aperture = None;
+    pinhole.exposure = None;
+    let pinhole = pinhole.build()?;
+    let view = pinhole.view_direction();
+    let mut nearest: Option<f64> = None;
+    for k in 0..self.rays.max(1) {
+      // Sunflower spiral: evenly spread over the disk for any number of rays
+      let r = self.spread * ((k as f64 + 0.5) / self.rays.max(1) as f64).sqrt();
+      let angle = k as f64 * PI * (3.0 - 5.0f64.sqrt());
+      let film = (self.film.0 + r * angle.cos() / builder.aspect, self.film.1 + r * angle.sin());
+      let ray = pinhole.generate_ray(film, (0.5, 0.5));
+      if let Some(record) = world.hit(&ray, 0.001, f64::INFINITY) {
+        let depth = Vec3::dot(&(record.point - ray.origin), &view);
+        if depth > 0.0 && nearest.is_none_or(|d| depth < d) {
+          nearest = Some(depth);
+        }
+      }
+    }
+    nearest.ok_or(CameraError::NoFocusTarget)
+  }
+}
+
+impl CameraBuilder {
+  /**
+   * Focus on what the camera sees through `focus` in `world`. Fails when no probe ray hits
+   * anything, or when the other parameters are invalid.
+   **/
+  pub fn autofocus(self, world: &dyn Hittable, focus: &AutoFocus) -> Result<Self, CameraError> {
+    let distance = focus.distance(&self, world)?;
+    Ok(self.focus_dist(distance))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::{color::Color, point3::Point3};
+  use crate::geometry::{hit::HittableList, sphere::Sphere};
+  use crate::materials::lambertian::Lambertian;
+
+  #[test]
+  fn test_autofocus() {
+    let material = || Lambertian { albedo: Color::new(0.5, 0.5, 0.5) };
+    let mut world = HittableList::new();
+    world.add(Sphere::new(Point3::new(0.0, 0.0, -5.0), 1.0, material()));
+    world.add(Sphere::new(Point3::new(1.5, 0.0, -3.0), 0.5, material()));
+    let builder = CameraBuilder::new()
+      .look_from(Point3::new(0.0, 0.0, 0.0))
+      .look_at(Point3::new(0.0, 0.0, -1.0))
+      .aspect(1.0)
+      .vfov(90.0)
+      .aperture(0.5);
+
+    // Front of the sphere in the middle, not its center
+    let center = AutoFocus::default().distance(&builder, &world).unwrap();
+    assert!((center - 4.0).abs() < 1e-9);
+    // Through the center of the small sphere on the right, measured along the view direction
+    let right = AutoFocus::new((0.75, 0.5)).distance(&builder, &world).unwrap();
+    assert!((right - (3.0 - 0.5 / 1.25f64.sqrt())).abs() < 1e-9);
+    // A cone wide enough to catch the small sphere focuses on it
+    let cone = AutoFocus::new((0.6, 0.5)).cone(0.2, 32).distance(&builder, &world).unwrap();
+    assert!(cone < 3.0);
+
+    let camera = builder.clone().autofocus(&world, &AutoFocus::default()).unwrap().build().unwrap();
+    for sample in [(0.0, 0.5), (1.0, 1.0)].iter() {
+      let r = camera.generate_ray((0.5, 0.5), *sample);
+      assert!((r.at(4.0 / -r.direction.z) - Point3::new(0.0, 0.0, -4.0)).length() < 1e-9);
+    }
+    assert_eq!(
+      AutoFocus::new((0.5, 1.0)).distance(&builder, &world).unwrap_err(),
+      CameraError::NoFocusTarget,
+    );
+  }
+}
@@ -0,0 +1,354 @@
+use std::fmt;
+
+use crate::core::{
+  point3::Point3,
+  vec3::Vec3,
+};
+
+use super::{Aperture, Exposure, PerspectiveCamera};
+
+/**
+ * Why a camera could not be built.
+ **/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraError {
+  // Vertical field of view, in degrees, must be in (0, 180)
+  FieldOfView(f64),
+  AspectRatio(f64),
+  Aperture(f64),
+  // A polygonal aperture needs at least 3 blades
+  Blades(u32),
+  // Cat's eye vignetting strength, in [0, 1]
+  CatEye(f64),
+  FocusDistance(f64),
+  // Shutter opening and closing times
+  Shutter(f64, f64),
+  // A point or vector has an infinite or NaN coordinate
+  NotFinite(&'static str),
+  // The camera looks at its own position
+  NoViewDirection,
+  // The up vector is null or along the view direction
+  UpAlongView,
+  // No paraxial ray gets through the lens elements
+  LensBlocked,
+  // Distance between the eyes of a stereo camera
+  Interocular(f64),
+  // No probe ray of the autofocus hit anything
+  NoFocusTarget,
+  FNumber(f64),
+  // Exposure time, in seconds
+  ShutterTime(f64),
+  Iso(f64),
+  FilmHeight(f64),
+  // The f-number of the exposure already sets the aperture
+  ApertureAndExposure,
+}
+
+impl fmt::Display for CameraError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      CameraError::FieldOfView(vfov) => write!(f, "vertical field of view {} is not in (0, 180) degrees", vfov),
+      CameraError::AspectRatio(aspect) => write!(f, "aspect ratio {} is not positive", aspect),
+      CameraError::Aperture(aperture) => write!(f, "aperture {} is negative", aperture),
+      CameraError::Blades(blades) => write!(f, "an aperture of {} blades is not a polygon", blades),
+      CameraError::CatEye(strength) => write!(f, "cat's eye strength {} is not in [0, 1]", strength),
+      CameraError::FocusDistance(distance) => write!(f, "focus distance {} is not positive", distance),
+      CameraError::Shutter(open, close) => write!(f, "shutter closes at {} before opening at {}", close, open),
+      CameraError::NotFinite(what) => write!(f, "{} is not finite", what),
+      CameraError::NoViewDirection => write!(f, "look_from and look_at are the same point"),
+      CameraError::UpAlongView => write!(f, "vup is null or parallel to the view direction"),
+      CameraError::LensBlocked => write!(f, "no light gets through the lens"),
+      CameraError::Interocular(distance) => write!(f, "interocular distance {} is negative", distance),
+      CameraError::NoFocusTarget => write!(f, "autofocus found nothing to focus on"),
+      CameraError::FNumber(n) => write!(f, "f-number {} is not positive", n),
+      CameraError::ShutterTime(time) => write!(f, "shutter time {} is not positive", time),
+      CameraError::Iso(iso) => write!(f, "ISO {} is not positive", iso),
+      CameraError::FilmHeight(height) => write!(f, "film height {} is not positive", height),
+      CameraError::ApertureAndExposure => write!(f, "aperture set along with an exposure setting it"),
+    }
+  }
+}
+
+impl std::error::Error for CameraError {}
+
+/**
+ * Named parameters of a `PerspectiveCamera`, checked by `build`.
+ * Without `focus_dist` the camera focuses on `look_at`. The aperture is the diameter of the lens,
+ * 0 for a pinhole, and its shape that of a disk unless set. Rays get random times between the
+ * shutter opening and closing.
+ * With an exposure, film radiance is scaled by it and the aperture is the focal length over its
+ * f-number instead, so it cannot be set too. The focal length follows from the field of view and
+ * the film height (in scene units, 0.024 by default: a full frame camera in a scene in meters).
+ * The shutter times are in scene time, over which objects move, and only blur the motion: the
+ * shutter time of the exposure is in seconds and only sets the brightness, so a frame can be
+ * exposed for 1/100 s whatever the length of its motion blur.
+ **/
+#[derive(Clone, Debug)]
+pub struct CameraBuilder {
+  pub(super) look_from: Point3,
+  pub(super) look_at: Point3,
+  pub(super) vup: Vec3,
+  pub(super) vfov: f64,
+  pub(super) aspect: f64,
+  pub(super) aperture: Option<f64>,
+  pub(super) aperture_shape: Aperture,
+  pub(super) cat_eye: f64,
+  pub(super) focus_dist: Option<f64>,
+  pub(super) shutter: (f64, f64),
+  pub(super) exposure: Option<Exposure>,
+  pub(super) film_height: f64,
+}
+
+impl Default for CameraBuilder {
+  fn default() -> Self {
+    CameraBuilder {
+      look_from: Point3::new(0.0, 0.0, 0.0),
+      look_at: Point3::new(0.0, 0.0, -1.0),
+      vup: Vec3::new(0.0, 1.0, 0.0),
+      vfov: 90.0,
+      aspect: 16.0 / 9.0,
+      aperture: None,
+      aperture_shape: Aperture::Disk,
+      cat_eye: 0.0,
+      focus_dist: None,
+      shutter: (0.0, 0.0),
+      exposure: None,
+      film_height: 0.024,
+    }
+  }
+}
+
+impl CameraBuilder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn look_from(mut self, look_from: Point3) -> Self {
+    self.look_from = look_from;
+    self
+  }
+
+  pub fn look_at(mut self, look_at: Point3) -> Self {
+    self.look_at = look_at;
+    self
+  }
+
+  pub fn vup(mut self, vup: Vec3) -> Self {
+    self.vup = vup;
+    self
+  }
+
+  /**
+   * Vertical field of view in degrees.
+   **/
+  pub fn vfov(mut self, vfov: f64) -> Self {
+    self.vfov = vfov;
+    self
+  }
+
+  pub fn aspect(mut self, aspect: f64) -> Self {
+    self.aspect = aspect;
+    self
+  }
+
+  pub fn aperture(mut self, aperture: f64) -> Self {
+    self.aperture = Some(aperture);
+    self
+  }
+
+  pub fn aperture_shape(mut self, shape: Aperture) -> Self {
+    self.aperture_shape = shape;
+    self
+  }
+
+  /**
+   * Cat's eye vignetting: the lens barrel clips the aperture more and more towards the edges of
+   * the frame, from 0 (none) to 1 where nothing gets through at the corners.
+   **/
+  pub fn cat_eye(mut self, strength: f64) -> Self {
+    self.cat_eye = strength;
+    self
+  }
+
+  pub fn focus_dist(mut self, focus_dist: f64) -> Self {
+    self.focus_dist = Some(focus_dist);
+    self
+  }
+
+  pub fn shutter(mut self, open: f64, close: f64) -> Self {
+    self.shutter = (open, close);
+    self
+  }
+
+  pub fn exposure(mut self, exposure: Exposure) -> Self {
+    self.exposure = Some(exposure);
+    self
+  }
+
+  pub fn film_height(mut self, film_height: f64) -> Self {
+    self.film_height = film_height;
+    self
+  }
+
+  /**
+   * Distance from the lens to the film for the field of view, in the units of the film height.
+   **/
+  pub fn focal_length(&self) -> f64 {
+    self.film_height / 2.0 / (self.vfov.to_radians() / 2.0).tan()
+  }
+
+  /**
+   * Diameter of the lens: set directly, or from the f-number of the exposure.
+   **/
+  pub(super) fn lens_diameter(&self) -> f64 {
+    match &self.exposure {
+      Some(exposure) => self.focal_length() / exposure.f_number,
+      None => self.aperture.unwrap_or(0.0),
+    }
+  }
+
+  pub fn build(&self) -> Result<PerspectiveCamera, CameraError> {
+    let finite = |v: &Vec3| v.x.is_finite() && v.y.is_finite() && v.z.is_finite();
+    for (name, vector) in [("look_from", self.look_from), ("look_at", self.look_at), ("vup", self.vup)].iter() {
+      if !finite(vector) {
+        return Err(CameraError::NotFinite(name));
+      }
+    }
+    if !(self.vfov > 0.0 && self.vfov < 180.0) {
+      return Err(CameraError::FieldOfView(self.vfov));
+    }
+    if !(self.aspect > 0.0 && self.aspect.is_finite()) {
+      return Err(CameraError::AspectRatio(self.aspect));
+    }
+    if let Some(aperture) = self.aperture {
+      if !(aperture >= 0.0 && aperture.is_finite()) {
+        return Err(CameraError::Aperture(aperture));
+      }
+    }
+    if let Aperture::Polygon { blades, rotation } = self.aperture_shape {
+      if blades < 3 {
+        return Err(CameraError::Blades(blades));
+      }
+      if !rotation.is_finite() {
+        return Err(CameraError::NotFinite("aperture rotation"));
+      }
+    }
+    if !(self.cat_eye >= 0.0 && self.cat_eye <= 1.0) {
+      return Err(CameraError::CatEye(self.cat_eye));
+    }
+    if let Some(exposure) = &self.exposure {
+      if self.aperture.is_some() {
+        return Err(CameraError::ApertureAndExposure);
+      }
+      exposure.check()?;
+      if !(self.film_height > 0.0 && self.film_height.is_finite()) {
+        return Err(CameraError::FilmHeight(self.film_height));
+      }
+    }
+    let (open, close) = self.shutter;
+    if !(open.is_finite() && close.is_finite() && open <= close) {
+      return Err(CameraError::Shutter(open, close));
+    }
+
+    let view = self.look_at - self.look_from;
+    let distance = view.length();
+    if distance == 0.0 {
+      return Err(CameraError::NoViewDirection);
+    }
+    // Relative to the lengths, so tiny but valid vectors pass
+    let side = Vec3::cross(&self.vup, &view);
+    if side.length() <= 1e-9 * self.vup.length() * distance {
+      return Err(CameraError::UpAlongView);
+    }
+    let focus_dist = self.focus_dist.unwrap_or(distance);
+    if !(focus_dist > 0.0 && focus_dist.is_finite()) {
+      return Err(CameraError::FocusDistance(focus_dist));
+    }
+
+    Ok(PerspectiveCamera::from_builder(self, focus_dist))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::camera::Camera;
+
+  #[test]
+  fn test_validation() {
+    let builder = CameraBuilder::new()
+      .look_from(Point3::new(0.0, 0.0, 2.0))
+      .look_at(Point3::new(0.0, 0.0, -1.0));
+    assert!(builder.build().is_ok());
+    assert_eq!(builder.clone().vfov(180.0).build().unwrap_err(), CameraError::FieldOfView(180.0));
+    assert_eq!(builder.clone().aspect(0.0).build().unwrap_err(), CameraError::AspectRatio(0.0));
+    assert_eq!(builder.clone().aperture(-0.1).build().unwrap_err(), CameraError::Aperture(-0.1));
+    assert_eq!(builder.clone().focus_dist(0.0).build().unwrap_err(), CameraError::FocusDistance(0.0));
+    assert_eq!(builder.clone().shutter(1.0, 0.5).build().unwrap_err(), CameraError::Shutter(1.0, 0.5));
+    assert_eq!(builder.clone().look_at(Point3::new(0.0, 0.0, 2.0)).build().unwrap_err(), CameraError::NoViewDirection);
+    assert_eq!(builder.clone().vup(Vec3::new(0.0, 0.0, 3.0)).build().unwrap_err(), CameraError::UpAlongView);
+    assert_eq!(builder.clone().vup(Vec3::new(0.0, 0.0, 0.0)).build().unwrap_err(), CameraError::UpAlongView);
+    assert_eq!(builder.clone().aperture_shape(Aperture::polygon(2, 0.0)).build().unwrap_err(), CameraError::Blades(2));
+    assert_eq!(builder.clone().cat_eye(1.5).build().unwrap_err(), CameraError::CatEye(1.5));
+    assert_eq!(
+      builder.look_from(Point3::new(f64::NAN, 0.0, 0.0)).build().unwrap_err(),
+      CameraError::NotFinite("look_from"),
+    );
+  }
+
+  #[test]
+  fn test_lens_and_shutter() {
+    let look_from = Point3::new(1.0, 1.0, 1.0);
+    let camera = CameraBuilder::new()
+      .look_from(look_from)
+      .look_at(Point3::new(0.0, 0.0, 0.0))
+      .aperture(0.2)
+      .shutter(0.25, 0.5)
+      .build()
+      .unwrap();
+    // Focused on look_at by default
+    let film = (0.5, 0.5);
+    for sample in [(0.0, 0.5), (1.0, 1.0), (0.3, 0.9)].iter() {
+      let r = camera.generate_ray(film, *sample);
+      assert!(r.at(1.0).length() < 1e-12);
+      // The lens lies across the view direction
+      assert!(Vec3::dot(&(r.origin - look_from), &look_from).abs() < 1e-12);
+      assert!((r.origin - look_from).length() <= 0.1 + 1e-12);
+      assert!(r.time >= 0.25 && r.time <= 0.5);
+    }
+  }
+
+  #[test]
+  fn test_exposure() {
+    let look_from = Point3::new(0.0, 0.0, 0.0);
+    // A 24 mm high film behind a lens seeing 90 degrees is 12 mm away
+    let builder = CameraBuilder::new()
+      .look_from(look_from)
+      .look_at(Point3::new(0.0, 0.0, -1.0))
+      .vfov(90.0);
+    assert!((builder.focal_length() - 0.012).abs() < 1e-12);
+    let wide = builder.clone().exposure(Exposure::new(2.0, 0.01, 100.0)).build().unwrap();
+    let narrow = builder.clone().exposure(Exposure::new(4.0, 0.01, 100.0)).build().unwrap();
+    // Stopping down by two stops narrows the lens to half and darkens the film by 4
+    for (camera, radius) in [(&wide, 0.003), (&narrow, 0.0015)].iter() {
+      let (r, _) = camera.generate_weighted_ray((0.5, 0.5), (1.0, 0.5));
+      assert!(((r.origin - look_from).length() - radius).abs() < 1e-12);
+    }
+    let (_, weight) = wide.generate_weighted_ray((0.5, 0.5), (0.3, 0.3));
+    assert!((weight - 64.0).abs() < 1e-9);
+    assert!((weight / narrow.exposure() - 4.0).abs() < 1e-9);
+    assert_eq!(builder.build().unwrap().exposure(), 1.0);
+    assert_eq!(
+      builder.clone().exposure(Exposure::new(-2.0, 0.01, 100.0)).build().unwrap_err(),
+      CameraError::FNumber(-2.0),
+    );
+    assert_eq!(
+      builder.clone().film_height(0.0).exposure(Exposure::default()).build().unwrap_err(),
+      CameraError::FilmHeight(0.0),
+    );
+    assert_eq!(
+      builder.aperture(1.0).exposure(Exposure::default()).build().unwrap_err(),
+      CameraError::ApertureAndExposure,
+    );
+  }
+}
@@ -0,0 +1,59 @@
+use crate::core::{
+  PI,
+  point3::Point3,
+  vec3::Vec3,
+  ray::Ray,
+};
+
+use super::{Camera, look_at_frame};
+
+/**
+ * 360 degree panorama (as used by VR viewers and environment maps): longitude goes along the
+ * width from behind the camera on the left around to behind it on the right, latitude along the
+ * height from straight down to straight up. The center of the image looks at `lookat`.
+ * Images should be twice as wide as tall.
+ * Refer to: https://en.wikipedia.org/wiki/Equirectangular_projection
+ **/
+#[derive(Clone, Copy, Debug)]
+pub struct EquirectangularCamera {
+  origin: Point3,
+  u: Vec3,
+  v: Vec3,
+  w: Vec3,
+}
+
+impl EquirectangularCamera {
+  pub fn new(lookfrom: Point3, lookat: Point3, vup: Vec3) -> Self {
+    let (u, v, w) = look_at_frame(lookfrom, lookat, vup);
+    EquirectangularCamera { origin: lookfrom, u, v, w }
+  }
+}
+
+impl Camera for EquirectangularCamera {
+  fn generate_ray(&self, film: (f64, f64), _sample: (f64, f64)) -> Ray {
+    let longitude = (film.0 - 0.5) * 2.0 * PI;
+    let latitude = (film.1 - 0.5) * PI;
+    let direction = latitude.cos() * (longitude.sin() * self.u - longitude.cos() * self.w)
+      + latitude.sin() * self.v;
+    Ray::new(self.origin, direction)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_panorama() {
+    let camera = EquirectangularCamera::new(
+      Point3::new(0.0, 0.0, 0.0),
+      Point3::new(0.0, 0.0, -1.0),
+      Vec3::new(0.0, 1.0, 0.0),
+    );
+    let direction = |s: f64, t: f64| camera.generate_ray((s, t), (0.5, 0.5)).direction;
+    assert!((direction(0.5, 0.5) - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-12);
+    assert!((direction(0.75, 0.5) - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-12);
+    assert!((direction(0.0, 0.5) - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-12);
+    assert!((direction(0.3, 1.0) - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-12);
+  }
+}
@@ -0,0 +1,94 @@
+use super::CameraError;
+
+// Exposure value at ISO 100 giving a scale of 1: f/16 at 1/100 s ("sunny 16"), so radiances
+// around 1, like the sky of the samples, come out as a sunlit scene
+const REFERENCE_EV100: f64 = 14.643856189774725;
+
+/**
+ * Photographic exposure: the f-number (which also sets the aperture of the lens), the time the
+ * shutter stays open in seconds, the film sensitivity and a compensation in stops. Film radiance
+ * is scaled by the light these let in relative to f/16, 1/100 s and ISO 100, so opening up by a
+ * stop doubles the brightness.
+ * The shutter time is apart from the shutter interval of the camera, in scene time, which only
+ * spreads the rays for motion blur.
+ * Refer to: https://en.wikipedia.org/wiki/Exposure_value
+ **/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Exposure {
+  pub f_number: f64,
+  pub shutter_time: f64,
+  pub iso: f64,
+  pub compensation: f64,
+}
+
+impl Default for Exposure {
+  fn default() -> Self {
+    Exposure::new(16.0, 0.01, 100.0)
+  }
+}
+
+impl Exposure {
+  pub fn new(f_number: f64, shutter_time: f64, iso: f64) -> Self {
+    Exposure { f_number, shutter_time, iso, compensation: 0.0 }
+  }
+
+  /**
+   * Brighter by `stops` (darker when negative).
+   **/
+  pub fn compensation(mut self, stops: f64) -> Self {
+    self.compensation = stops;
+    self
+  }
+
+  /**
+   * Exposure value of the settings brought back to ISO 100.
+   **/
+  pub fn ev100(&self) -> f64 {
+    (self.f_number * self.f_number / self.shutter_time * 100.0 / self.iso).log2()
+  }
+
+  /**
+   * Factor applied to the radiance reaching the film.
+   **/
+  pub fn scale(&self) -> f64 {
+    (REFERENCE_EV100 - self.ev100() + self.compensation).exp2()
+  }
+
+  pub(super) fn check(&self) -> Result<(), CameraError> {
+    if !(self.f_number > 0.0 && self.f_number.is_finite()) {
+      return Err(CameraError::FNumber(self.f_number));
+    }
+    if !(self.shutter_time > 0.0 && self.shutter_time.is_finite()) {
+      return Err(CameraError::ShutterTime(self.shutter_time));
+    }
+    if !(self.iso > 0.0 && self.iso.is_finite()) {
+      return Err(CameraError::Iso(self.iso));
+    }
+    if !self.compensation.is_finite() {
+      return Err(CameraError::NotFinite("exposure compensation"));
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_scale() {
+    assert!((Exposure::default().scale() - 1.0).abs() < 1e-12);
+    assert!((Exposure::default().ev100() - REFERENCE_EV100).abs() < 1e-12);
+    // Equivalent exposures
+    assert!((Exposure::new(4.0, 1.0 / 1600.0, 100.0).scale() - 1.0).abs() < 1e-12);
+    assert!((Exposure::new(16.0, 0.01 / 4.0, 400.0).scale() - 1.0).abs() < 1e-12);
+    // One stop each
+    assert!((Exposure::new(16.0, 0.02, 100.0).scale() - 2.0).abs() < 1e-12);
+    assert!((Exposure::new(16.0 * 2f64.sqrt(), 0.01, 100.0).scale() - 0.5).abs() < 1e-12);
+    assert!((Exposure::new(16.0, 0.01, 200.0).scale() - 2.0).abs() < 1e-12);
+    assert!((Exposure::default().compensation(-1.0).scale() - 0.5).abs() < 1e-12);
+    assert_eq!(Exposure::new(0.0, 0.01, 100.0).check(), Err(CameraError::FNumber(0.0)));
+    assert_eq!(Exposure::new(2.0, -1.0, 100.0).check(), Err(CameraError::ShutterTime(-1.0)));
+    assert_eq!(Exposure::new(2.0, 0.01, 0.0).check(), Err(CameraError::Iso(0.0)));
+  }
+}
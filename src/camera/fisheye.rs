@@ -0,0 +1,76 @@
+use crate::core::{
+  PI,
+  point3::Point3,
+  vec3::Vec3,
+  ray::Ray,
+  degrees_to_radians,
+};
+
+use super::{Camera, look_at_frame};
+
+/**
+ * Equidistant fisheye: the angle to the view direction grows linearly with the distance to the
+ * center of the image, reaching `fov` / 2 at the top and bottom edges (the image circle fits the
+ * height). Beyond it the mapping goes on up to straight backwards, filling the corners.
+ * Refer to: https://en.wikipedia.org/wiki/Fisheye_lens#Mapping_function
+ **/
+#[derive(Clone, Copy, Debug)]
+pub struct FisheyeCamera {
+  origin: Point3,
+  u: Vec3,
+  v: Vec3,
+  w: Vec3,
+  // Half field of view in radians
+  half_fov: f64,
+  aspect_ratio: f64,
+}
+
+impl FisheyeCamera {
+  pub fn new(lookfrom: Point3, lookat: Point3, vup: Vec3, fov: f64, aspect_ratio: f64) -> Self {
+    let (u, v, w) = look_at_frame(lookfrom, lookat, vup);
+    FisheyeCamera {
+      origin: lookfrom,
+      u,
+      v,
+      w,
+      half_fov: degrees_to_radians(fov) / 2.0,
+      aspect_ratio,
+    }
+  }
+}
+
+impl Camera for FisheyeCamera {
+  fn generate_ray(&self, film: (f64, f64), _sample: (f64, f64)) -> Ray {
+    let x = (2.0 * film.0 - 1.0) * self.aspect_ratio;
+    let y = 2.0 * film.1 - 1.0;
+    let theta = ((x * x + y * y).sqrt() * self.half_fov).min(PI);
+    let phi = y.atan2(x);
+    let direction = theta.sin() * (phi.cos() * self.u + phi.sin() * self.v) - theta.cos() * self.w;
+    Ray::new(self.origin, direction)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_equidistant() {
+    let camera = FisheyeCamera::new(
+      Point3::new(0.0, 0.0, 0.0),
+      Point3::new(0.0, 0.0, -1.0),
+      Vec3::new(0.0, 1.0, 0.0),
+      180.0,
+      2.0,
+    );
+    let forward = Vec3::new(0.0, 0.0, -1.0);
+    let center = camera.generate_ray((0.5, 0.5), (0.5, 0.5));
+    assert!((center.direction - forward).length() < 1e-12);
+    // Top edge at 90 degrees, half way at 45
+    let top = camera.generate_ray((0.5, 1.0), (0.5, 0.5));
+    assert!((top.direction - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-12);
+    let half = camera.generate_ray((0.5 + 0.25 / 2.0, 0.5), (0.5, 0.5));
+    assert!((Vec3::dot(&half.direction, &forward) - (PI / 4.0).cos()).abs() < 1e-12);
+    assert!(half.direction.x > 0.0);
+  }
+}
@@ -0,0 +1,108 @@
+pub mod animation;
+pub mod aperture;
+pub mod autofocus;
+pub mod builder;
+pub mod perspective;
+pub mod orthographic;
+pub mod fisheye;
+pub mod equirectangular;
+pub mod exposure;
+pub mod realistic;
+pub mod render;
+pub mod stereo;
+
+pub use animation::{AnimatedCamera, FrameCamera, Sequence};
+pub use crate::core::track::{Interpolation, Track};
+pub use aperture::{Aperture, ApertureMask};
+pub use autofocus::AutoFocus;
+pub use builder::{CameraBuilder, CameraError};
+pub use perspective::PerspectiveCamera;
+pub use orthographic::OrthographicCamera;
+pub use fisheye::FisheyeCamera;
+pub use equirectangular::EquirectangularCamera;
+pub use exposure::Exposure;
+pub use realistic::{Lens, LensElement, RealisticCamera};
+pub use render::{Region, render_image, render_into, render_region};
+pub use stereo::{Eye, OdsCamera, StereoCamera, StereoLayout};
+
+use crate::core::{
+  PI,
+  point3::Point3,
+  vec3::Vec3,
+  ray::Ray,
+};
+use crate::utils::utils::random_double;
+
+/**
+ * Maps points of the film to rays leaving the camera.
+ * `film` is in [0, 1]^2, from the lower left corner of the image. `sample` is a point of
+ * [0, 1)^2 used for the lens, so renderers can stratify it; cameras without a lens ignore it.
+ **/
+pub trait Camera: Send + Sync {
+  fn generate_ray(&self, film: (f64, f64), sample: (f64, f64)) -> Ray;
+
+  /**
+   * Ray with the weight of the radiance it brings back, for cameras whose optics let more or
+   * less light through depending on the film point (0 when the ray is blocked).
+   * Only this weight carries the exposure of a `PerspectiveCamera` (and of the cameras made of
+   * it): `generate_ray` leaves it out, and the other cameras have none.
+   **/
+  fn generate_weighted_ray(&self, film: (f64, f64), sample: (f64, f64)) -> (Ray, f64) {
+    (self.generate_ray(film, sample), 1.0)
+  }
+
+  /**
+   * Ray through the film point (s, t) with a random lens sample, and the weight to scale the
+   * radiance it brings back by (see `generate_weighted_ray`).
+   **/
+  fn get_ray(&self, s: f64, t: f64) -> (Ray, f64) {
+    self.generate_weighted_ray((s, t), (random_double(), random_double()))
+  }
+}
+
+/**
+ * Orthonormal frame of a camera at `lookfrom` looking at `lookat`: u to the right, v up
+ * and w backwards (the camera looks along -w).
+ **/
+pub(crate) fn look_at_frame(lookfrom: Point3, lookat: Point3, vup: Vec3) -> (Vec3, Vec3, Vec3) {
+  let w = (lookfrom - lookat).unit();
+  let u = Vec3::cross(&vup, &w).unit();
+  let v = Vec3::cross(&w, &u);
+  (u, v, w)
+}
+
+/**
+ * Point of the unit disk from a point of the unit square, by the concentric mapping which keeps
+ * neighboring samples close.
+ * Refer to: https://www.pbr-book.org/3ed-2018/Monte_Carlo_Integration/2D_Sampling_with_Multidimensional_Transformations
+ **/
+pub fn sample_disk(sample: (f64, f64)) -> (f64, f64) {
+  let (a, b) = (2.0 * sample.0 - 1.0, 2.0 * sample.1 - 1.0);
+  if a == 0.0 && b == 0.0 {
+    return (0.0, 0.0);
+  }
+  let (r, theta) = if a.abs() > b.abs() {
+    (a, PI / 4.0 * (b / a))
+  } else {
+    (b, PI / 2.0 - PI / 4.0 * (a / b))
+  };
+  (r * theta.cos(), r * theta.sin())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_sample_disk() {
+    assert_eq!(sample_disk((0.5, 0.5)), (0.0, 0.0));
+    let (x, y) = sample_disk((1.0, 0.5));
+    assert!((x - 1.0).abs() < 1e-12 && y.abs() < 1e-12);
+    for i in 0..10 {
+      for j in 0..10 {
+        let (x, y) = sample_disk((i as f64 / 9.0, j as f64 / 9.0));
+        assert!(x * x + y * y <= 1.0 + 1e-12);
+      }
+    }
+  }
+}
@@ -0,0 +1,62 @@
+use crate::core::{
+  point3::Point3,
+  vec3::Vec3,
+  ray::Ray,
+};
+
+use super::{Camera, look_at_frame};
+
+/**
+ * Parallel projection: all rays go along the view direction from a film `view_height` tall,
+ * centered on `lookfrom`. Sizes don't change with the distance, as in technical drawings.
+ **/
+#[derive(Clone, Copy, Debug)]
+pub struct OrthographicCamera {
+  ll: Point3,
+  horizontal: Vec3,
+  vertical: Vec3,
+  direction: Vec3,
+}
+
+impl OrthographicCamera {
+  pub fn new(lookfrom: Point3, lookat: Point3, vup: Vec3, view_height: f64, aspect_ratio: f64) -> Self {
+    let (u, v, w) = look_at_frame(lookfrom, lookat, vup);
+    let horizontal = aspect_ratio * view_height * u;
+    let vertical = view_height * v;
+    OrthographicCamera {
+      ll: lookfrom - horizontal / 2 - vertical / 2,
+      horizontal,
+      vertical,
+      direction: -w,
+    }
+  }
+}
+
+impl Camera for OrthographicCamera {
+  fn generate_ray(&self, film: (f64, f64), _sample: (f64, f64)) -> Ray {
+    let (s, t) = film;
+    Ray::new(self.ll + s * self.horizontal + t * self.vertical, self.direction)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parallel_rays() {
+    let camera = OrthographicCamera::new(
+      Point3::new(0.0, 0.0, 2.0),
+      Point3::new(0.0, 0.0, -1.0),
+      Vec3::new(0.0, 1.0, 0.0),
+      2.0,
+      1.5,
+    );
+    let corner = camera.generate_ray((0.0, 0.0), (0.5, 0.5));
+    assert_eq!(corner.origin, Point3::new(-1.5, -1.0, 2.0));
+    let center = camera.generate_ray((0.5, 0.5), (0.5, 0.5));
+    assert_eq!(center.origin, Point3::new(0.0, 0.0, 2.0));
+    assert_eq!(corner.direction, center.direction);
+    assert_eq!(center.direction, Vec3::new(0.0, 0.0, -1.0));
+  }
+}
@@ -0,0 +1,350 @@
+use crate::core::{
+  point3::Point3,
+  vec3::Vec3,
+  ray::Ray,
+  degrees_to_radians,
+};
+use crate::utils::utils::{random_double, random_double_in_range};
+
+use super::{Aperture, Camera, CameraBuilder, look_at_frame};
+
+// Tries at sampling the part of the aperture left by cat's eye vignetting before using its center
+const CAT_EYE_TRIES: usize = 16;
+
+/**
+ * Pinhole camera, or thin lens camera focused at `focus_dist` when built with an aperture.
+ * The constructors panic on invalid parameters, use `CameraBuilder` to handle them.
+ **/
+#[derive(Clone, Debug)]
+pub struct PerspectiveCamera {
+  origin: Point3,
+  ll: Point3,
+  horizontal: Vec3,
+  vertical: Vec3,
+  u: Vec3,
+  v: Vec3,
+  w: Vec3,
+  lens_radius: f64,
+  aperture: Aperture,
+  cat_eye: f64,
+  aspect: f64,
+  time0: f64,
+  time1: f64,
+  exposure: f64,
+}
+
+impl Default for PerspectiveCamera {
+  fn default() -> PerspectiveCamera {
+    Self::new()
+  }
+}
+
+impl PerspectiveCamera {
+  pub fn builder() -> CameraBuilder {
+    CameraBuilder::new()
+  }
+
+  /**
+   * At the origin looking along -z, with a 90 degree vertical field of view and a 16:9 aspect ratio.
+   **/
+  pub fn new() -> Self {
+    Self::expect(CameraBuilder::new())
+  }
+
+  pub fn new_param(vfov: f64, aspect_ratio: f64) -> Self {
+    Self::expect(CameraBuilder::new().vfov(vfov).aspect(aspect_ratio))
+  }
+
+  pub fn new_free_camera(
+    lookfrom: Point3,
+    lookat: Point3,
+    vup: Vec3,
+    vfov: f64,
+    aspect_ratio: f64,
+  ) -> Self {
+    Self::expect(CameraBuilder::new()
+      .look_from(lookfrom)
+      .look_at(lookat)
+      .vup(vup)
+      .vfov(vfov)
+      .aspect(aspect_ratio))
+  }
+
+  pub fn new_with_lens(
+    lookfrom: Point3,
+    lookat: Point3,
+    vup: Vec3,
+    vfov: f64,
+    aspect_ratio: f64,
+    aperture: f64,
+    focus_dist: f64,
+  ) -> Self {
+    Self::expect(CameraBuilder::new()
+      .look_from(lookfrom)
+      .look_at(lookat)
+      .vup(vup)
+      .vfov(vfov)
+      .aspect(aspect_ratio)
+      .aperture(aperture)
+      .focus_dist(focus_dist))
+  }
+
+  fn expect(builder: CameraBuilder) -> Self {
+    builder.build().unwrap_or_else(|e| panic!("invalid camera: {}", e))
+  }
+
+  /**
+   * Camera from parameters already checked by the builder.
+   **/
+  pub(super) fn from_builder(builder: &CameraBuilder, focus_dist: f64) -> Self {
+    let theta = degrees_to_radians(builder.vfov);
+    let h = (theta / 2.0).tan();
+    let viewport_height = 2.0 * h;
+    let viewport_width = builder.aspect * viewport_height;
+
+    let (u, v, w) = look_at_frame(builder.look_from, builder.look_at, builder.vup);
+
+    let origin = builder.look_from;
+    let horizontal = focus_dist * viewport_width * u;
+    let vertical = focus_dist * viewport_height * v;
+    let ll = origin - horizontal / 2 - vertical / 2 - focus_dist * w;
+    let lens_radius = builder.lens_diameter() / 2.0;
+    let aperture = builder.aperture_shape.clone();
+    let (cat_eye, aspect) = (builder.cat_eye, builder.aspect);
+    let (time0, time1) = builder.shutter;
+    let exposure = builder.exposure.map_or(1.0, |e| e.scale());
+
+    Self { origin, ll, horizontal, vertical, u, v, w, lens_radius, aperture, cat_eye, aspect, time0, time1, exposure }
+  }
+
+  /**
+   * Camera a fraction `f` of the way to `other`, with the shutter closed at `time`. Both being
+   * valid cameras with the same lens, so is the result: the frame is interpolated then made
+   * orthonormal again, and the viewport rebuilt on it from the interpolated sizes.
+   **/
+  pub(super) fn between(&self, other: &Self, f: f64, time: f64) -> Self {
+    let lerp = |a: f64, b: f64| a + f * (b - a);
+    let origin = self.origin + f * (other.origin - self.origin);
+    let w = (self.w + f * (other.w - self.w)).unit();
+    let u = self.u + f * (other.u - self.u);
+    let u = (u - Vec3::dot(&u, &w) * w).unit();
+    let v = Vec3::cross(&w, &u);
+    let horizontal = lerp(self.horizontal.length(), other.horizontal.length()) * u;
+    let vertical = lerp(self.vertical.length(), other.vertical.length()) * v;
+    let focus_dist = lerp(self.focus_dist(), other.focus_dist());
+    Self {
+      origin,
+      ll: origin - horizontal / 2 - vertical / 2 - focus_dist * w,
+      horizontal,
+      vertical,
+      u,
+      v,
+      w,
+      lens_radius: lerp(self.lens_radius, other.lens_radius),
+      aperture: self.aperture.clone(),
+      time0: time,
+      time1: time,
+      ..*self
+    }
+  }
+
+  // Distance from the lens to the plane in focus, where the viewport lies
+  fn focus_dist(&self) -> f64 {
+    Vec3::dot(&(self.origin - self.ll), &self.w)
+  }
+
+  /**
+   * Unit vector along which the camera looks.
+   **/
+  pub fn view_direction(&self) -> Vec3 {
+    -self.w
+  }
+
+  /**
+   * Factor the exposure applies to film radiance, 1 without one.
+   **/
+  pub fn exposure(&self) -> f64 {
+    self.exposure
+  }
+
+  /**
+   * Whether the lens barrel lets through light from the point (x, y) of the aperture (in lens radii)
+   * to `film`. Seen from off axis, the barrel is a disk as large as the lens shifted away from the
+   * center of the frame, by up to twice the lens radius in the corners at full strength.
+   * Refer to: https://en.wikipedia.org/wiki/Vignetting#Optical_vignetting
+   **/
+  fn through_barrel(&self, film: (f64, f64), (x, y): (f64, f64)) -> bool {
+    if self.cat_eye <= 0.0 {
+      return true;
+    }
+    let diagonal = (self.aspect * self.aspect + 1.0).sqrt();
+    let scale = 2.0 * self.cat_eye / diagonal;
+    let (cx, cy) = (scale * (2.0 * film.0 - 1.0) * self.aspect, scale * (2.0 * film.1 - 1.0));
+    (x - cx) * (x - cx) + (y - cy) * (y - cy) <= 1.0
+  }
+
+  fn ray_from_lens(&self, film: (f64, f64), eye: f64, (dx, dy): (f64, f64)) -> Ray {
+    let (s, t) = film;
+    let target = self.ll + s * self.horizontal + t * self.vertical;
+    let time = if self.time1 > self.time0 { random_double_in_range(self.time0, self.time1) } else { self.time0 };
+    let offset = self.u * eye + self.lens_radius * (self.u * dx + self.v * dy);
+    Ray::new_with_time(self.origin + offset, target - self.origin - offset, time)
+  }
+
+  /**
+   * Ray from the camera moved by `eye` along its right vector, through the same point of the
+   * focus plane: the film window shifts with the eye instead of turning, as stereo pairs need.
+   **/
+  pub(super) fn eye_ray(&self, eye: f64, film: (f64, f64), sample: (f64, f64)) -> Ray {
+    if self.lens_radius <= 0.0 {
+      return self.ray_from_lens(film, eye, (0.0, 0.0));
+    }
+    let mut point = self.aperture.sample(sample);
+    for _ in 0..CAT_EYE_TRIES {
+      if self.through_barrel(film, point) {
+        return self.ray_from_lens(film, eye, point);
+      }
+      point = self.aperture.sample((random_double(), random_double()));
+    }
+    self.ray_from_lens(film, eye, (0.0, 0.0))
+  }
+
+  pub(super) fn weighted_eye_ray(&self, eye: f64, film: (f64, f64), sample: (f64, f64)) -> (Ray, f64) {
+    if self.lens_radius <= 0.0 {
+      return (self.ray_from_lens(film, eye, (0.0, 0.0)), self.exposure);
+    }
+    let point = self.aperture.sample(sample);
+    let weight = if self.through_barrel(film, point) { self.exposure } else { 0.0 };
+    (self.ray_from_lens(film, eye, point), weight)
+  }
+}
+
+impl Camera for PerspectiveCamera {
+  /**
+   * Ray from a point of the aperture through the point of the focus plane seen at `film`,
+   * at a random time while the shutter is open.
+   * Samples the barrel blocks are replaced by random ones, so the bokeh keeps its cat's eye shape
+   * but not the darkening, which needs the weights of `generate_weighted_ray`.
+   **/
+  fn generate_ray(&self, film: (f64, f64), sample: (f64, f64)) -> Ray {
+    self.eye_ray(0.0, film, sample)
+  }
+
+  /**
+   * Weight 0 for the samples of the aperture the lens barrel blocks, the exposure otherwise.
+   **/
+  fn generate_weighted_ray(&self, film: (f64, f64), sample: (f64, f64)) -> (Ray, f64) {
+    self.weighted_eye_ray(0.0, film, sample)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_focus() {
+    let lookfrom = Point3::new(0.0, 0.0, 2.0);
+    let lookat = Point3::new(0.0, 0.0, -1.0);
+    let camera = PerspectiveCamera::new_with_lens(lookfrom, lookat, Vec3::new(0.0, 1.0, 0.0), 40.0, 2.0, 0.5, 3.0);
+    // Rays through a film point from anywhere on the lens meet on the focus plane
+    let film = (0.3, 0.8);
+    let focus = camera.generate_ray(film, (0.5, 0.5)).at(1.0);
+    for sample in [(0.0, 0.0), (0.9, 0.2), (0.4, 1.0)].iter() {
+      let r = camera.generate_ray(film, *sample);
+      assert!((r.at(1.0) - focus).length() < 1e-12);
+      assert!((r.origin - lookfrom).length() <= 0.25 + 1e-12);
+    }
+    // The center of the film looks at the target
+    let r = camera.generate_ray((0.5, 0.5), (0.5, 0.5));
+    assert!((r.direction.unit() - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-12);
+
+    // Without a lens all rays start at the eye
+    let pinhole = PerspectiveCamera::new_free_camera(lookfrom, lookat, Vec3::new(0.0, 1.0, 0.0), 40.0, 2.0);
+    assert_eq!(pinhole.generate_ray(film, (0.1, 0.7)).origin, lookfrom);
+  }
+
+  #[test]
+  fn test_default_frame() {
+    // The default camera has a proper frame and no lens
+    let camera = PerspectiveCamera::default();
+    assert_eq!(camera.u, Vec3::new(1.0, 0.0, 0.0));
+    assert_eq!(camera.v, Vec3::new(0.0, 1.0, 0.0));
+    assert_eq!(camera.w, Vec3::new(0.0, 0.0, 1.0));
+    assert_eq!(camera.lens_radius, 0.0);
+    let r = camera.generate_ray((1.0, 1.0), (0.9, 0.1));
+    assert_eq!(r.origin, Point3::new(0.0, 0.0, 0.0));
+    assert!((r.direction - Vec3::new(16.0 / 9.0, 1.0, -1.0)).length() < 1e-12);
+  }
+
+  #[test]
+  fn test_aperture_shape() {
+    let builder = PerspectiveCamera::builder()
+      .look_from(Point3::new(0.0, 0.0, 0.0))
+      .look_at(Point3::new(0.0, 0.0, -1.0))
+      .aspect(1.0)
+      .aperture(2.0)
+      .aperture_shape(Aperture::polygon(4, 45.0));
+    // Square aperture: lens offsets fill the square of half side cos(45)
+    let camera = builder.clone().build().unwrap();
+    let half_side = (crate::core::PI / 4.0).cos();
+    let mut corner: f64 = 0.0;
+    for i in 0..16 {
+      for j in 0..16 {
+        let o = camera.generate_ray((0.5, 0.5), (i as f64 / 16.0, j as f64 / 16.0)).origin;
+        assert!(o.x.abs() <= half_side + 1e-12 && o.y.abs() <= half_side + 1e-12);
+        corner = corner.max(o.x.abs().min(o.y.abs()));
+      }
+    }
+    assert!(corner > 0.6);
+
+    // Cat's eye: all the aperture at the center, less towards the corners, nothing at full strength
+    let mean_weight = |camera: &PerspectiveCamera, film: (f64, f64)| {
+      let n = 32;
+      let mut sum = 0.0;
+      for k in 0..n * n {
+        let sample = (((k % n) as f64 + 0.5) / n as f64, ((k / n) as f64 + 0.5) / n as f64);
+        sum += camera.generate_weighted_ray(film, sample).1;
+      }
+      sum / (n * n) as f64
+    };
+    let camera = builder.clone().cat_eye(0.5).build().unwrap();
+    assert_eq!(mean_weight(&camera, (0.5, 0.5)), 1.0);
+    let edge = mean_weight(&camera, (1.0, 0.5));
+    let corner = mean_weight(&camera, (1.0, 1.0));
+    assert!(corner > 0.0 && corner < edge && edge < 1.0);
+    assert_eq!(mean_weight(&builder.cat_eye(1.0).build().unwrap(), (0.0, 0.0)), 0.0);
+    // Rays keep to the part the barrel lets through
+    let r = camera.generate_ray((1.0, 1.0), (0.0, 0.0));
+    assert!(camera.through_barrel((1.0, 1.0), (r.origin.x, r.origin.y)));
+  }
+
+  #[test]
+  fn test_between() {
+    // Turning 90 degrees from -z to -x: halfway the camera looks between them, undistorted
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let origin = Point3::new(0.0, 0.0, 0.0);
+    let from = PerspectiveCamera::new_with_lens(origin, Point3::new(0.0, 0.0, -1.0), up, 60.0, 2.0, 0.1, 2.0);
+    let to = PerspectiveCamera::new_with_lens(origin, Point3::new(-1.0, 0.0, 0.0), up, 60.0, 2.0, 0.1, 2.0);
+    let half = from.between(&to, 0.5, 0.0);
+    let expected = PerspectiveCamera::new_with_lens(origin, Point3::new(-1.0, 0.0, -1.0), up, 60.0, 2.0, 0.1, 2.0);
+    for (a, b) in [
+      (half.u, expected.u), (half.v, expected.v), (half.w, expected.w),
+      (half.horizontal, expected.horizontal), (half.vertical, expected.vertical), (half.ll, expected.ll),
+    ].iter() {
+      assert!((*a - *b).length() < 1e-9);
+    }
+  }
+
+  #[test]
+  #[should_panic(expected = "invalid camera")]
+  fn test_up_along_view() {
+    PerspectiveCamera::new_free_camera(
+      Point3::new(0.0, 2.0, 0.0),
+      Point3::new(0.0, 0.0, 0.0),
+      Vec3::new(0.0, 1.0, 0.0),
+      40.0,
+      1.0,
+    );
+  }
+}
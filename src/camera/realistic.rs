@@ -0,0 +1,538 @@
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::core::{
+  point3::Point3,
+  vec3::Vec3,
+  ray::Ray,
+  polynomial::solve_quadratic,
+};
+
+use crate::utils::utils::random_double;
+
+use super::{Camera, CameraError, look_at_frame};
+
+// Film distances over which the exit pupil is bounded
+const PUPIL_BINS: usize = 64;
+// Square root of the number of rays traced to bound the exit pupil of a bin
+const PUPIL_SAMPLES: usize = 64;
+// Tries at sampling a ray the lens lets through before giving up on a film point
+const LENS_TRIES: usize = 64;
+
+/**
+ * Spherical interface between two media of a lens, in meters. A zero curvature radius is the
+ * aperture stop. `thickness` is the distance to the next interface along the axis (to the film
+ * for the last one) and `ior` the index of the medium up to it, 0 or 1 for air.
+ **/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LensElement {
+  pub curvature_radius: f64,
+  pub thickness: f64,
+  pub ior: f64,
+  pub aperture_radius: f64,
+}
+
+impl LensElement {
+  pub fn is_stop(&self) -> bool {
+    self.curvature_radius == 0.0
+  }
+
+  fn medium(&self) -> f64 {
+    if self.ior == 0.0 { 1.0 } else { self.ior }
+  }
+}
+
+/**
+ * Stack of lens elements from the scene side to the film.
+ **/
+#[derive(Clone, Debug)]
+pub struct Lens {
+  elements: Vec<LensElement>,
+}
+
+impl Lens {
+  pub fn new(elements: Vec<LensElement>) -> Result<Self> {
+    if elements.is_empty() {
+      return Err(Error::new(ErrorKind::InvalidData, "a lens needs at least one element"));
+    }
+    let valid = |e: &LensElement| {
+      e.curvature_radius.is_finite() && e.thickness.is_finite() && e.thickness >= 0.0
+        && e.ior.is_finite() && e.ior >= 0.0 && e.aperture_radius.is_finite() && e.aperture_radius > 0.0
+    };
+    if !elements.iter().all(valid) {
+      return Err(Error::new(ErrorKind::InvalidData, "invalid lens element"));
+    }
+    Ok(Lens { elements })
+  }
+
+  /**
+   * Lens prescription in the tabular format of pbrt: one element per line with its curvature
+   * radius, thickness, index of refraction and aperture diameter, in mm. Everything after a `#`
+   * on a line is a comment.
+   * Refer to: https://www.pbr-book.org/3ed-2018/Camera_Models/Realistic_Cameras
+   **/
+  pub fn parse(text: &str) -> Result<Self> {
+    let mut elements = Vec::new();
+    for line in text.lines() {
+      let line = line.split('#').next().unwrap_or("");
+      if line.trim().is_empty() {
+        continue;
+      }
+      let values = line.split_whitespace()
+        .map(|token| token.parse::<f64>())
+        .collect::<std::result::Result<Vec<f64>, _>>()
+        .map_err(|_| Error::new(ErrorKind::InvalidData, format!("invalid lens line: {}", line)))?;
+      if values.len() != 4 {
+        return Err(Error::new(ErrorKind::InvalidData, format!("expected 4 values on lens line: {}", line)));
+      }
+      elements.push(LensElement {
+        curvature_radius: values[0] * 0.001,
+        thickness: values[1] * 0.001,
+        ior: values[2],
+        aperture_radius: values[3] * 0.001 / 2.0,
+      });
+    }
+    Self::new(elements)
+  }
+
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+    Self::parse(&fs::read_to_string(path)?)
+  }
+
+  pub fn elements(&self) -> &[LensElement] {
+    &self.elements
+  }
+
+  /**
+   * Stop the aperture down to the given diameter (m), it can't be opened past its design size.
+   **/
+  pub fn with_aperture(mut self, diameter: f64) -> Self {
+    for element in self.elements.iter_mut().filter(|e| e.is_stop()) {
+      element.aperture_radius = element.aperture_radius.min(diameter / 2.0);
+    }
+    self
+  }
+
+  /**
+   * Effective focal length, from the thick lens approximation.
+   **/
+  pub fn focal_length(&self) -> Option<f64> {
+    let (principal, focal) = self.thick_lens()?;
+    Some(focal[0] - principal[0])
+  }
+
+  fn front_z(&self) -> f64 {
+    self.elements.iter().map(|e| e.thickness).sum()
+  }
+
+  fn rear(&self) -> &LensElement {
+    &self.elements[self.elements.len() - 1]
+  }
+
+  /**
+   * Follow a ray in camera space (film at z = 0, the lens towards +z) from the film out of the
+   * front element, None if an element or the stop blocks it.
+   **/
+  pub(crate) fn trace_from_film(&self, ray: &Ray) -> Option<Ray> {
+    // In lens space the scene is towards -z
+    let mut origin = Point3::new(ray.origin.x, ray.origin.y, -ray.origin.z);
+    let mut direction = Vec3::new(ray.direction.x, ray.direction.y, -ray.direction.z);
+    let mut z = 0.0;
+    for i in (0..self.elements.len()).rev() {
+      let element = &self.elements[i];
+      z -= element.thickness;
+      let outside = if i > 0 { self.elements[i - 1].medium() } else { 1.0 };
+      let (o, d) = self.interface(element, z, origin, direction, element.medium() / outside)?;
+      origin = o;
+      direction = d;
+    }
+    Some(Ray::new(Point3::new(origin.x, origin.y, -origin.z), Vec3::new(direction.x, direction.y, -direction.z)))
+  }
+
+  /**
+   * Follow a ray in camera space from the scene through the lens to the film side.
+   **/
+  pub(crate) fn trace_from_scene(&self, ray: &Ray) -> Option<Ray> {
+    let mut origin = Point3::new(ray.origin.x, ray.origin.y, -ray.origin.z);
+    let mut direction = Vec3::new(ray.direction.x, ray.direction.y, -ray.direction.z);
+    let mut z = -self.front_z();
+    for i in 0..self.elements.len() {
+      let element = &self.elements[i];
+      let before = if i > 0 { self.elements[i - 1].medium() } else { 1.0 };
+      let (o, d) = self.interface(element, z, origin, direction, before / element.medium())?;
+      origin = o;
+      direction = d;
+      z += element.thickness;
+    }
+    Some(Ray::new(Point3::new(origin.x, origin.y, -origin.z), Vec3::new(direction.x, direction.y, -direction.z)))
+  }
+
+  /**
+   * Cross an element at `z` in lens space: the stop only clips, spherical interfaces refract.
+   **/
+  fn interface(&self, element: &LensElement, z: f64, origin: Point3, direction: Vec3, eta: f64) -> Option<(Point3, Vec3)> {
+    if element.is_stop() {
+      let t = (z - origin.z) / direction.z;
+      let p = origin + t * direction;
+      if t < 0.0 || p.x * p.x + p.y * p.y > element.aperture_radius * element.aperture_radius {
+        return None;
+      }
+      return Some((p, direction));
+    }
+
+    // Of the two intersections with the sphere, the one on the side of the vertex
+    let radius = element.curvature_radius;
+    let oc = origin - Vec3::new(0.0, 0.0, z + radius);
+    let a = direction.length_square();
+    let b = 2.0 * Vec3::dot(&direction, &oc);
+    let c = oc.length_square() - radius * radius;
+    let (t0, t1) = solve_quadratic(a, b, c)?;
+    let t = if (direction.z > 0.0) ^ (radius < 0.0) { t0 } else { t1 };
+    if t < 0.0 {
+      return None;
+    }
+    let p = origin + t * direction;
+    if p.x * p.x + p.y * p.y > element.aperture_radius * element.aperture_radius {
+      return None;
+    }
+
+    let unit_direction = direction.unit();
+    let mut normal = (oc + t * direction).unit();
+    if Vec3::dot(&normal, &unit_direction) > 0.0 {
+      normal = -normal;
+    }
+    let cos_i = -Vec3::dot(&normal, &unit_direction);
+    if eta * eta * (1.0 - cos_i * cos_i) >= 1.0 {
+      // Total internal reflection, lost for the image
+      return None;
+    }
+    Some((p, Vec3::refract(unit_direction, normal, eta)))
+  }
+
+  /**
+   * Principal and focal planes (as distances in front of the film) for light from the scene and
+   * from the film, tracing paraxial rays both ways.
+   **/
+  fn thick_lens(&self) -> Option<([f64; 2], [f64; 2])> {
+    let x = 0.001 * self.rear().aperture_radius.min(self.elements[0].aperture_radius);
+    let from_scene = Ray::new(Point3::new(x, 0.0, self.front_z() + 1.0), Vec3::new(0.0, 0.0, -1.0));
+    let to_film = self.trace_from_scene(&from_scene)?;
+    let (p0, f0) = cardinal_points(&from_scene, &to_film);
+    let from_film = Ray::new(Point3::new(x, 0.0, self.rear().thickness - 1.0), Vec3::new(0.0, 0.0, 1.0));
+    let to_scene = self.trace_from_film(&from_film)?;
+    let (p1, f1) = cardinal_points(&from_film, &to_scene);
+    Some(([p0, p1], [f0, f1]))
+  }
+
+  /**
+   * Move the elements relative to the film so objects at `distance` (m) in front of it are sharp.
+   **/
+  pub fn focus(&mut self, distance: f64) -> std::result::Result<(), CameraError> {
+    let (principal, focal) = self.thick_lens().ok_or(CameraError::LensBlocked)?;
+    let f = focal[0] - principal[0];
+    let z = -distance;
+    let c = (principal[1] - z - principal[0]) * (principal[1] - z - 4.0 * f - principal[0]);
+    if !(c > 0.0 && c.is_finite()) {
+      return Err(CameraError::FocusDistance(distance));
+    }
+    let delta = 0.5 * (principal[1] - z + principal[0] - c.sqrt());
+    let last = self.elements.len() - 1;
+    let thickness = self.elements[last].thickness + delta;
+    if thickness <= 0.0 {
+      return Err(CameraError::FocusDistance(distance));
+    }
+    self.elements[last].thickness = thickness;
+    Ok(())
+  }
+}
+
+/**
+ * Where a ray parallel to the axis and the ray it becomes through the lens (camera space) meet
+ * (principal plane) and where the latter crosses the axis (focal point).
+ **/
+fn cardinal_points(r_in: &Ray, r_out: &Ray) -> (f64, f64) {
+  let t_focal = -r_out.origin.x / r_out.direction.x;
+  let t_principal = (r_in.origin.x - r_out.origin.x) / r_out.direction.x;
+  (-r_out.at(t_principal).z, -r_out.at(t_focal).z)
+}
+
+/**
+ * Axis-aligned rectangle of the plane of the rear element.
+ **/
+#[derive(Clone, Copy, Debug)]
+struct PupilBounds {
+  min: (f64, f64),
+  max: (f64, f64),
+}
+
+impl PupilBounds {
+  fn area(&self) -> f64 {
+    (self.max.0 - self.min.0) * (self.max.1 - self.min.1)
+  }
+
+  fn contains(&self, x: f64, y: f64) -> bool {
+    x >= self.min.0 && x <= self.max.0 && y >= self.min.1 && y <= self.max.1
+  }
+}
+
+/**
+ * Camera tracing rays through the elements of a real lens, so its depth of field, bokeh,
+ * vignetting and distortion come from the optics. `lookfrom` is the film, the lens is in front.
+ * The field of view follows from the focal length of the lens and the film diagonal (mm).
+ *
+ * Rays from a film point are sampled over a bound of its exit pupil (the region of the rear
+ * element through which light gets out), precomputed by film distance, so few are blocked.
+ * Refer to: https://www.pbr-book.org/3ed-2018/Camera_Models/Realistic_Cameras
+ **/
+#[derive(Clone, Debug)]
+pub struct RealisticCamera {
+  origin: Point3,
+  u: Vec3,
+  v: Vec3,
+  w: Vec3,
+  lens: Lens,
+  // Film size in m
+  film_width: f64,
+  film_height: f64,
+  exit_pupils: Vec<PupilBounds>,
+}
+
+impl RealisticCamera {
+  pub fn new(
+    lookfrom: Point3,
+    lookat: Point3,
+    vup: Vec3,
+    mut lens: Lens,
+    focus_dist: f64,
+    film_diagonal: f64,
+    aspect_ratio: f64,
+  ) -> std::result::Result<Self, CameraError> {
+    if !(aspect_ratio > 0.0 && aspect_ratio.is_finite()) {
+      return Err(CameraError::AspectRatio(aspect_ratio));
+    }
+    lens.focus(focus_dist)?;
+    let (u, v, w) = look_at_frame(lookfrom, lookat, vup);
+    let diagonal = film_diagonal * 0.001;
+    let film_height = diagonal / (1.0 + aspect_ratio * aspect_ratio).sqrt();
+    let mut camera = RealisticCamera {
+      origin: lookfrom,
+      u,
+      v,
+      w,
+      lens,
+      film_width: aspect_ratio * film_height,
+      film_height,
+      exit_pupils: Vec::new(),
+    };
+    camera.exit_pupils = (0..PUPIL_BINS)
+      .map(|i| {
+        let r = 0.5 * diagonal / PUPIL_BINS as f64;
+        camera.bound_exit_pupil(i as f64 * r, (i + 1) as f64 * r)
+      })
+      .collect();
+    Ok(camera)
+  }
+
+  pub fn lens(&self) -> &Lens {
+    &self.lens
+  }
+
+  /**
+   * Bound of the points of the rear element plane that film points at distances in [r0, r1] on
+   * the x axis see the scene through.
+   **/
+  fn bound_exit_pupil(&self, r0: f64, r1: f64) -> PupilBounds {
+    let rear = self.lens.rear();
+    let extent = 1.5 * rear.aperture_radius;
+    let mut bounds: Option<PupilBounds> = None;
+    let n = PUPIL_SAMPLES * PUPIL_SAMPLES;
+    for k in 0..n {
+      let film = Point3::new(r0 + (r1 - r0) * (k as f64 + 0.5) / n as f64, 0.0, 0.0);
+      let x = -extent + 2.0 * extent * ((k % PUPIL_SAMPLES) as f64 + 0.5) / PUPIL_SAMPLES as f64;
+      let y = -extent + 2.0 * extent * ((k / PUPIL_SAMPLES) as f64 + 0.5) / PUPIL_SAMPLES as f64;
+      if bounds.is_some_and(|b| b.contains(x, y)) {
+        continue;
+      }
+      let rear_point = Point3::new(x, y, rear.thickness);
+      if self.lens.trace_from_film(&Ray::new(film, rear_point - film)).is_some() {
+        bounds = Some(match bounds {
+          Some(b) => PupilBounds { min: (b.min.0.min(x), b.min.1.min(y)), max: (b.max.0.max(x), b.max.1.max(y)) },
+          None => PupilBounds { min: (x, y), max: (x, y) },
+        });
+      }
+    }
+    match bounds {
+      // Padded by the spacing of the samples
+      Some(b) => {
+        let pad = 2.0 * extent / PUPIL_SAMPLES as f64;
+        PupilBounds { min: (b.min.0 - pad, b.min.1 - pad), max: (b.max.0 + pad, b.max.1 + pad) }
+      },
+      None => PupilBounds { min: (-extent, -extent), max: (extent, extent) },
+    }
+  }
+
+  /**
+   * Point of the rear element plane for a film point, and the area of the bound it is drawn from.
+   **/
+  fn sample_exit_pupil(&self, film_x: f64, film_y: f64, sample: (f64, f64)) -> (Point3, f64) {
+    let r = (film_x * film_x + film_y * film_y).sqrt();
+    let half_diagonal = 0.5 * (self.film_width * self.film_width + self.film_height * self.film_height).sqrt();
+    let index = ((r / half_diagonal * PUPIL_BINS as f64) as usize).min(PUPIL_BINS - 1);
+    let bounds = self.exit_pupils[index];
+    let x = bounds.min.0 + (bounds.max.0 - bounds.min.0) * sample.0;
+    let y = bounds.min.1 + (bounds.max.1 - bounds.min.1) * sample.1;
+    // Bounds were found on the x axis, rotate them to the film point
+    let (sin, cos) = if r > 0.0 { (film_y / r, film_x / r) } else { (0.0, 1.0) };
+    (Point3::new(cos * x - sin * y, sin * x + cos * y, self.lens.rear().thickness), bounds.area())
+  }
+
+  fn to_world(&self, ray: &Ray) -> Ray {
+    let frame = |v: Vec3| v.x * self.u + v.y * self.v - v.z * self.w;
+    Ray::new(self.origin + frame(ray.origin), frame(ray.direction).unit())
+  }
+}
+
+impl Camera for RealisticCamera {
+  /**
+   * Samples the lens blocks are replaced by random ones until a ray gets through, which keeps
+   * the shape of the bokeh but not the vignetting, given by the weights of
+   * `generate_weighted_ray`. Film points outside the image circle, which no ray reaches, still
+   * give a blocked ray: render them through `generate_weighted_ray`.
+   **/
+  fn generate_ray(&self, film: (f64, f64), sample: (f64, f64)) -> Ray {
+    let (mut ray, mut weight) = self.generate_weighted_ray(film, sample);
+    for _ in 0..LENS_TRIES {
+      if weight > 0.0 {
+        break;
+      }
+      let (r, w) = self.generate_weighted_ray(film, (random_double(), random_double()));
+      ray = r;
+      weight = w;
+    }
+    ray
+  }
+
+  /**
+   * Weighted by the cos^4 falloff and the size of the exit pupil relative to the center of the
+   * film, zero for rays the lens blocks.
+   **/
+  fn generate_weighted_ray(&self, film: (f64, f64), sample: (f64, f64)) -> (Ray, f64) {
+    // The lens flips the image
+    let film_point = Point3::new(
+      -(film.0 - 0.5) * self.film_width,
+      -(film.1 - 0.5) * self.film_height,
+      0.0,
+    );
+    let (rear_point, area) = self.sample_exit_pupil(film_point.x, film_point.y, sample);
+    let film_ray = Ray::new(film_point, rear_point - film_point);
+    match self.lens.trace_from_film(&film_ray) {
+      Some(ray) => {
+        let cos_theta = film_ray.direction.unit().z;
+        let weight = cos_theta.powi(4) * area / self.exit_pupils[0].area();
+        (self.to_world(&ray), weight)
+      },
+      None => (self.to_world(&film_ray), 0.0),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn dgauss() -> Lens {
+    Lens::parse(include_str!("../../lenses/dgauss.50mm.dat")).unwrap()
+  }
+
+  fn camera(lens: Lens, focus_dist: f64) -> RealisticCamera {
+    RealisticCamera::new(
+      Point3::new(0.0, 0.0, 0.0),
+      Point3::new(0.0, 0.0, -1.0),
+      Vec3::new(0.0, 1.0, 0.0),
+      lens,
+      focus_dist,
+      35.0,
+      1.5,
+    ).unwrap()
+  }
+
+  #[test]
+  fn test_parse() {
+    let lens = dgauss();
+    assert_eq!(lens.elements().len(), 11);
+    assert!(lens.elements()[5].is_stop());
+    assert_eq!(lens.elements()[0].aperture_radius, 0.0126);
+    assert!((lens.focal_length().unwrap() - 0.05).abs() < 0.002);
+    assert!(Lens::parse("1 2 3\n").is_err());
+    assert!(Lens::parse("# nothing\n").is_err());
+    assert!(Lens::parse("1 2 x 4\n").is_err());
+  }
+
+  #[test]
+  fn test_focus() {
+    // Rays from the center of the film through a small aperture meet on the axis at the focus distance
+    let camera = camera(dgauss().with_aperture(0.005), 2.0);
+    let mut spread: f64 = 0.0;
+    let mut passed = 0;
+    for i in 0..20 {
+      let sample = ((i as f64 + 0.5) / 20.0, (i * 7 % 20) as f64 / 20.0 + 0.025);
+      let (r, weight) = camera.generate_weighted_ray((0.5, 0.5), sample);
+      if weight == 0.0 {
+        continue;
+      }
+      passed += 1;
+      // Film is at the origin, the scene towards -z
+      let t = (-2.0 - r.origin.z) / r.direction.z;
+      let p = r.at(t);
+      spread = spread.max((p.x * p.x + p.y * p.y).sqrt());
+    }
+    assert!(passed > 10);
+    assert!(spread < 0.002);
+    assert!(RealisticCamera::new(
+      Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 1.0, 0.0), dgauss(), 0.05, 35.0, 1.5,
+    ).is_err());
+  }
+
+  #[test]
+  fn test_vignetting() {
+    // Less light gets through towards the corners of the film
+    let camera = camera(dgauss(), 5.0);
+    let mean_weight = |film: (f64, f64)| {
+      let n = 40;
+      let mut sum = 0.0;
+      for i in 0..n {
+        for j in 0..n {
+          let sample = ((i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64);
+          sum += camera.generate_weighted_ray(film, sample).1;
+        }
+      }
+      sum / (n * n) as f64
+    };
+    let center = mean_weight((0.5, 0.5));
+    let corner = mean_weight((0.98, 0.98));
+    assert!(center > 0.5);
+    assert!(corner < 0.8 * center);
+    // The image is upright: the top of the film sees up
+    assert!(camera.generate_ray((0.5, 0.95), (0.5, 0.5)).direction.y > 0.0);
+    assert!(camera.generate_ray((0.95, 0.5), (0.5, 0.5)).direction.x > 0.0);
+  }
+
+  #[test]
+  fn test_blocked_samples() {
+    // Samples the lens blocks are drawn again: unweighted rays leave from the front element
+    let camera = camera(dgauss(), 5.0);
+    let mut blocked = 0;
+    for i in 0..20 {
+      for j in 0..20 {
+        let sample = ((i as f64 + 0.5) / 20.0, (j as f64 + 0.5) / 20.0);
+        if camera.generate_weighted_ray((0.98, 0.98), sample).1 > 0.0 {
+          continue;
+        }
+        blocked += 1;
+        assert!(camera.generate_ray((0.98, 0.98), sample).origin.z < 0.0);
+      }
+    }
+    assert!(blocked > 0);
+  }
+}
@@ -0,0 +1,220 @@
+use std::thread;
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::core::{
+  color::Color,
+  image::Image,
+  ray::Ray,
+};
+
+use super::Camera;
+
+/**
+ * Pixels [x0, x1) x [y0, y1) of a frame, rows from the bottom like `Image`.
+ **/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+  pub x0: u32,
+  pub y0: u32,
+  pub x1: u32,
+  pub y1: u32,
+}
+
+impl Region {
+  pub fn new(x0: u32, y0: u32, x1: u32, y1: u32) -> Self {
+    Region { x0, y0, x1, y1 }
+  }
+
+  /**
+   * The whole frame.
+   **/
+  pub fn full(width: u32, height: u32) -> Self {
+    Region::new(0, 0, width, height)
+  }
+
+  /**
+   * Pixels of a `width` x `height` frame covered by the crop window from `min` to `max` in film
+   * coordinates ([0, 1]^2 from the lower left corner), rounded up at both ends like pbrt so
+   * adjacent windows share no pixel.
+   * Refer to: https://pbrt.org/fileformat-v3#cameras
+   **/
+  pub fn crop(width: u32, height: u32, min: (f64, f64), max: (f64, f64)) -> Self {
+    let pixel = |f: f64, size: u32| (size as f64 * f.clamp(0.0, 1.0)).ceil() as u32;
+    Region::new(pixel(min.0, width), pixel(min.1, height), pixel(max.0, width), pixel(max.1, height))
+  }
+
+  pub fn width(&self) -> u32 {
+    self.x1.saturating_sub(self.x0)
+  }
+
+  pub fn height(&self) -> u32 {
+    self.y1.saturating_sub(self.y0)
+  }
+
+  // Part of the region inside a `width` x `height` frame
+  fn clipped(&self, width: u32, height: u32) -> Self {
+    Region::new(self.x0.min(width), self.y0.min(height), self.x1.min(width), self.y1.min(height))
+  }
+}
+
+// Spreads the bits of a seed so that nearby seeds give unrelated pixel seeds (splitmix64)
+// Refer to: https://prng.di.unimi.it/splitmix64.c
+fn scramble(seed: u64) -> u64 {
+  let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+  z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+  z ^ (z >> 31)
+}
+
+/**
+ * Image of `width` x `height` pixels through `camera`, averaging `samples_per_pixel` weighted
+ * rays per pixel with the radiance given by `ray_color`. Rows are shared among the available
+ * threads. Each call draws new random numbers, use `render_region` to repeat a render.
+ **/
+pub fn render_image<C, F>(camera: &C, width: u32, height: u32, samples_per_pixel: u32, ray_color: &F) -> Image
+where
+  C: Camera,
+  F: Fn(&Ray) -> Color + Sync,
+{
+  render_region(camera, width, height, &Region::full(width, height), samples_per_pixel, rand::random(), ray_color)
+}
+
+/**
+ * Only the pixels of `region` (clipped to the frame) of a `width` x `height` image, in an image
+ * of the size of the region.
+ * The film and lens samples of each pixel come from its own random numbers, seeded by `seed` and
+ * its position in the frame: with the same seed a pixel gets the same camera rays whatever
+ * region it is rendered in, while renders with other seeds (frames of an animation, eyes of a
+ * stereo pair, more samples over a preview) get independent noise. Patches line up exactly with
+ * full renders when `ray_color` depends on the ray only, path tracing adds its own noise.
+ **/
+pub fn render_region<C, F>(
+  camera: &C,
+  width: u32,
+  height: u32,
+  region: &Region,
+  samples_per_pixel: u32,
+  seed: u64,
+  ray_color: &F,
+) -> Image
+where
+  C: Camera,
+  F: Fn(&Ray) -> Color + Sync,
+{
+  let region = region.clipped(width, height);
+  let seed = scramble(seed);
+  let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1) as u32;
+  let row = |j: u32| -> Vec<Color> {
+    let scale = 1.0 / samples_per_pixel as f64;
+    (region.x0..region.x1)
+      .map(|i| {
+        let mut rng = StdRng::seed_from_u64(seed ^ (((j as u64) << 32) | i as u64));
+        let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+        for _s in 0..samples_per_pixel {
+          // Film coordinates of the whole frame, not of the region
+          let u = (i as f64 + rng.gen::<f64>()) / (width - 1) as f64;
+          let v = (j as f64 + rng.gen::<f64>()) / (height - 1) as f64;
+          let (r, weight) = camera.generate_weighted_ray((u, v), (rng.gen(), rng.gen()));
+          if weight > 0.0 {
+            pixel_color += weight * ray_color(&r);
+          }
+        }
+        scale * pixel_color
+      })
+      .collect()
+  };
+
+  let mut image = Image::new(region.width(), region.height());
+  thread::scope(|scope| {
+    let row = &row;
+    let handles: Vec<_> = (0..threads)
+      .map(|t| {
+        scope.spawn(move || {
+          (region.y0 + t..region.y1).step_by(threads as usize).map(|j| (j, row(j))).collect::<Vec<_>>()
+        })
+      })
+      .collect();
+    for handle in handles {
+      for (j, colors) in handle.join().expect("render thread panicked") {
+        for (i, color) in colors.into_iter().enumerate() {
+          image.set(i as u32, j - region.y0, color);
+        }
+      }
+    }
+  });
+  image
+}
+
+/**
+ * Render `region` again over `image`, a previous render of the whole frame, for instance with
+ * more samples on a detail. Use another seed than the previous render, unless repeating it.
+ **/
+pub fn render_into<C, F>(
+  camera: &C,
+  image: &mut Image,
+  region: &Region,
+  samples_per_pixel: u32,
+  seed: u64,
+  ray_color: &F,
+)
+where
+  C: Camera,
+  F: Fn(&Ray) -> Color + Sync,
+{
+  let (width, height) = (image.width(), image.height());
+  let region = region.clipped(width, height);
+  let patch = render_region(camera, width, height, &region, samples_per_pixel, seed, ray_color);
+  image.paste(&patch, region.x0, region.y0);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::camera::CameraBuilder;
+  use crate::core::point3::Point3;
+
+  #[test]
+  fn test_crop() {
+    assert_eq!(Region::crop(100, 50, (0.25, 0.0), (0.5, 0.5)), Region::new(25, 0, 50, 25));
+    assert_eq!(Region::crop(10, 10, (0.33, -1.0), (0.66, 2.0)), Region::new(4, 0, 7, 10));
+    assert_eq!(Region::new(5, 5, 2, 8).width(), 0);
+
+    let camera = CameraBuilder::new()
+      .look_from(Point3::new(0.0, 0.0, 0.0))
+      .look_at(Point3::new(0.0, 0.0, -1.0))
+      .aspect(2.0)
+      .aperture(0.1)
+      .build()
+      .unwrap();
+    // Depends on the film position and the lens
+    let ray_color = |r: &Ray| {
+      let d = r.direction.unit();
+      Color::new(d.x.abs(), r.origin.y.abs(), r.origin.x.abs())
+    };
+    let full = render_region(&camera, 12, 6, &Region::full(12, 6), 3, 7, &ray_color);
+    let region = Region::new(3, 2, 9, 5);
+    let patch = render_region(&camera, 12, 6, &region, 3, 7, &ray_color);
+    assert_eq!((patch.width(), patch.height()), (6, 3));
+    for j in 0..3 {
+      for i in 0..6 {
+        assert_eq!(patch.get(i, j), full.get(i + 3, j + 2));
+      }
+    }
+
+    // Resuming over a blank frame fills the region exactly, clipped to the frame
+    let mut resumed = Image::new(12, 6);
+    render_into(&camera, &mut resumed, &Region::new(8, 4, 20, 20), 3, 7, &ray_color);
+    assert_eq!(resumed.get(11, 5), full.get(11, 5));
+    assert_eq!(resumed.get(8, 4), full.get(8, 4));
+    assert_eq!(resumed.get(7, 4), Color::new(0.0, 0.0, 0.0));
+
+    // Other seeds, or no seed, give other noise
+    let other = render_region(&camera, 12, 6, &Region::full(12, 6), 3, 8, &ray_color);
+    let unseeded = render_image(&camera, 12, 6, 3, &ray_color);
+    for (i, j) in [(0, 0), (5, 3), (11, 5)].iter() {
+      assert_ne!(other.get(*i, *j), full.get(*i, *j));
+      assert_ne!(unseeded.get(*i, *j), full.get(*i, *j));
+    }
+  }
+}
@@ -0,0 +1,229 @@
+use crate::core::{
+  PI,
+  color::Color,
+  image::Image,
+  point3::Point3,
+  vec3::Vec3,
+  ray::Ray,
+};
+
+use super::{Camera, CameraBuilder, CameraError, PerspectiveCamera, look_at_frame, render_image};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Eye {
+  Left,
+  Right,
+}
+
+impl Eye {
+  // Side of the eye along the right vector of the rig
+  fn side(&self) -> f64 {
+    match self {
+      Eye::Left => -1.0,
+      Eye::Right => 1.0,
+    }
+  }
+}
+
+fn check_interocular(interocular: f64) -> Result<(), CameraError> {
+  if interocular >= 0.0 && interocular.is_finite() {
+    Ok(())
+  } else {
+    Err(CameraError::Interocular(interocular))
+  }
+}
+
+/**
+ * One eye of a stereo pair of perspective cameras `interocular` apart, looking the same way.
+ * Their film windows are shifted so they line up at the convergence distance (off-axis stereo):
+ * things there appear at the depth of the screen, nearer ones in front of it, without the
+ * vertical parallax turning the eyes in would bring. The eyes focus at the convergence distance.
+ * Refer to: http://paulbourke.net/stereographics/stereorender/
+ **/
+#[derive(Clone, Debug)]
+pub struct StereoCamera {
+  camera: PerspectiveCamera,
+  eye: f64,
+}
+
+impl StereoCamera {
+  /**
+   * The eye of a rig centered on the `look_from` of `builder`.
+   **/
+  pub fn new(builder: &CameraBuilder, interocular: f64, convergence: f64, eye: Eye) -> Result<Self, CameraError> {
+    check_interocular(interocular)?;
+    let camera = builder.clone().focus_dist(convergence).build()?;
+    Ok(StereoCamera { camera, eye: eye.side() * interocular / 2.0 })
+  }
+
+  /**
+   * Left and right eyes.
+   **/
+  pub fn pair(builder: &CameraBuilder, interocular: f64, convergence: f64) -> Result<(Self, Self), CameraError> {
+    Ok((
+      Self::new(builder, interocular, convergence, Eye::Left)?,
+      Self::new(builder, interocular, convergence, Eye::Right)?,
+    ))
+  }
+}
+
+impl Camera for StereoCamera {
+  fn generate_ray(&self, film: (f64, f64), sample: (f64, f64)) -> Ray {
+    self.camera.eye_ray(self.eye, film, sample)
+  }
+
+  fn generate_weighted_ray(&self, film: (f64, f64), sample: (f64, f64)) -> (Ray, f64) {
+    self.camera.weighted_eye_ray(self.eye, film, sample)
+  }
+}
+
+/**
+ * One eye of an omni-directional stereo (ODS) panorama, laid out like `EquirectangularCamera`:
+ * each ray starts on the circle the eyes draw as the head turns, on the side of the eye for its
+ * direction, so any part of the panorama is seen in stereo when looked at. The circle shrinks
+ * towards the poles, where the eyes can no longer be told apart.
+ * Refer to: https://developers.google.com/vr/jump/rendering-ods-content.pdf
+ **/
+#[derive(Clone, Copy, Debug)]
+pub struct OdsCamera {
+  origin: Point3,
+  u: Vec3,
+  v: Vec3,
+  w: Vec3,
+  // Signed radius of the circle of the eye
+  eye: f64,
+}
+
+impl OdsCamera {
+  pub fn new(lookfrom: Point3, lookat: Point3, vup: Vec3, interocular: f64, eye: Eye) -> Result<Self, CameraError> {
+    check_interocular(interocular)?;
+    let (u, v, w) = look_at_frame(lookfrom, lookat, vup);
+    Ok(OdsCamera { origin: lookfrom, u, v, w, eye: eye.side() * interocular / 2.0 })
+  }
+
+  pub fn pair(lookfrom: Point3, lookat: Point3, vup: Vec3, interocular: f64) -> Result<(Self, Self), CameraError> {
+    Ok((
+      Self::new(lookfrom, lookat, vup, interocular, Eye::Left)?,
+      Self::new(lookfrom, lookat, vup, interocular, Eye::Right)?,
+    ))
+  }
+}
+
+impl Camera for OdsCamera {
+  fn generate_ray(&self, film: (f64, f64), _sample: (f64, f64)) -> Ray {
+    let longitude = (film.0 - 0.5) * 2.0 * PI;
+    let latitude = (film.1 - 0.5) * PI;
+    let (sin, cos) = longitude.sin_cos();
+    let forward = sin * self.u - cos * self.w;
+    let right = cos * self.u + sin * self.w;
+    let direction = latitude.cos() * forward + latitude.sin() * self.v;
+    Ray::new(self.origin + self.eye * latitude.cos() * right, direction)
+  }
+}
+
+/**
+ * How the two views of a stereo pair share one image.
+ **/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StereoLayout {
+  // Left eye on the left half
+  SideBySide,
+  // Left eye on the top half
+  TopBottom,
+}
+
+impl StereoLayout {
+  /**
+   * Single image holding the views of both eyes.
+   **/
+  pub fn compose(&self, left: &Image, right: &Image) -> Image {
+    let (width, height) = (left.width(), left.height());
+    let (mut image, right_at, left_at) = match self {
+      StereoLayout::SideBySide => (Image::new(2 * width, height), (width, 0), (0, 0)),
+      // Rows go up from the bottom
+      StereoLayout::TopBottom => (Image::new(width, 2 * height), (0, 0), (0, height)),
+    };
+    for j in 0..height {
+      for i in 0..width {
+        image.set(left_at.0 + i, left_at.1 + j, left.get(i, j));
+        image.set(right_at.0 + i, right_at.1 + j, right.get(i, j));
+      }
+    }
+    image
+  }
+
+  /**
+   * Render both eyes, each `width` x `height`, into a single image.
+   **/
+  pub fn render<C, F>(&self, eyes: (&C, &C), width: u32, height: u32, samples_per_pixel: u32, ray_color: &F) -> Image
+  where
+    C: Camera,
+    F: Fn(&Ray) -> Color + Sync,
+  {
+    let left = render_image(eyes.0, width, height, samples_per_pixel, ray_color);
+    let right = render_image(eyes.1, width, height, samples_per_pixel, ray_color);
+    self.compose(&left, &right)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_convergence() {
+    let builder = CameraBuilder::new()
+      .look_from(Point3::new(0.0, 0.0, 0.0))
+      .look_at(Point3::new(0.0, 0.0, -1.0))
+      .aspect(1.0);
+    let (left, right) = StereoCamera::pair(&builder, 0.064, 2.0).unwrap();
+    // The eyes see the same point of the convergence plane at the same film point
+    for film in [(0.5, 0.5), (0.1, 0.8), (0.9, 0.3)].iter() {
+      let (l, r) = (left.generate_ray(*film, (0.5, 0.5)), right.generate_ray(*film, (0.5, 0.5)));
+      assert!((l.origin - Point3::new(-0.032, 0.0, 0.0)).length() < 1e-12);
+      assert!((r.origin - Point3::new(0.032, 0.0, 0.0)).length() < 1e-12);
+      let (pl, pr) = (l.at(-2.0 / l.direction.z), r.at(-2.0 / r.direction.z));
+      assert!((pl - pr).length() < 1e-12);
+      // No vertical parallax
+      assert!((l.direction.y - r.direction.y).abs() < 1e-12);
+    }
+    assert_eq!(StereoCamera::new(&builder, -1.0, 2.0, Eye::Left).unwrap_err(), CameraError::Interocular(-1.0));
+    assert_eq!(StereoCamera::new(&builder, 0.064, 0.0, Eye::Left).unwrap_err(), CameraError::FocusDistance(0.0));
+  }
+
+  #[test]
+  fn test_ods() {
+    let (left, right) = OdsCamera::pair(
+      Point3::new(0.0, 1.0, 0.0),
+      Point3::new(0.0, 1.0, -1.0),
+      Vec3::new(0.0, 1.0, 0.0),
+      0.064,
+    ).unwrap();
+    // Looking ahead the eyes sit left and right, looking to the right they sit ahead and behind
+    let ahead = (left.generate_ray((0.5, 0.5), (0.5, 0.5)), right.generate_ray((0.5, 0.5), (0.5, 0.5)));
+    assert!((ahead.0.origin - Point3::new(-0.032, 1.0, 0.0)).length() < 1e-12);
+    assert!((ahead.1.origin - Point3::new(0.032, 1.0, 0.0)).length() < 1e-12);
+    assert!((ahead.0.direction - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-12);
+    let side = left.generate_ray((0.75, 0.5), (0.5, 0.5));
+    assert!((side.origin - Point3::new(0.0, 1.0, -0.032)).length() < 1e-12);
+    assert!((side.direction - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-12);
+    // The eyes meet at the poles
+    let up = left.generate_ray((0.3, 1.0), (0.5, 0.5));
+    assert!((up.origin - Point3::new(0.0, 1.0, 0.0)).length() < 1e-12);
+  }
+
+  #[test]
+  fn test_layout() {
+    let (mut left, mut right) = (Image::new(2, 1), Image::new(2, 1));
+    left.set(1, 0, Color::new(1.0, 0.0, 0.0));
+    right.set(0, 0, Color::new(0.0, 1.0, 0.0));
+    let sbs = StereoLayout::SideBySide.compose(&left, &right);
+    assert_eq!((sbs.width(), sbs.height()), (4, 1));
+    assert_eq!(sbs.get(1, 0), Color::new(1.0, 0.0, 0.0));
+    assert_eq!(sbs.get(2, 0), Color::new(0.0, 1.0, 0.0));
+    let tb = StereoLayout::TopBottom.compose(&left, &right);
+    assert_eq!((tb.width(), tb.height()), (2, 2));
+    assert_eq!(tb.get(1, 1), Color::new(1.0, 0.0, 0.0));
+    assert_eq!(tb.get(0, 0), Color::new(0.0, 1.0, 0.0));
+  }
+}
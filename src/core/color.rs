@@ -1,4 +1,4 @@
-use std::ops::{Add, AddAssign, Mul};
+use std::ops::{Add, AddAssign, Mul, Sub};
 use super::vec3::Vec3;
 use crate::utils::utils::{clamp, random_double, random_double_in_range};
 
@@ -39,6 +39,38 @@ impl Color {
 
   }
   
+  pub fn r(&self) -> f64 {
+    self.r
+  }
+
+  pub fn g(&self) -> f64 {
+    self.g
+  }
+
+  pub fn b(&self) -> f64 {
+    self.b
+  }
+
+  pub fn exp(&self) -> Color {
+    Color::new(self.r.exp(), self.g.exp(), self.b.exp())
+  }
+
+  pub fn average(&self) -> f64 {
+    (self.r + self.g + self.b) / 3.0
+  }
+
+  pub fn max_component(&self) -> f64 {
+    self.r.max(self.g).max(self.b)
+  }
+
+  /**
+   * Component-wise division, channels divided by zero become zero.
+   */
+  pub fn safe_div(&self, rhs: &Color) -> Color {
+    let div = |a: f64, b: f64| if b == 0.0 { 0.0 } else { a / b };
+    Color::new(div(self.r, rhs.r), div(self.g, rhs.g), div(self.b, rhs.b))
+  }
+
   /**
    * Static method to generate a random color
    */
@@ -84,6 +116,14 @@ impl Add<Vec3> for Color {
   }
 }
 
+impl Sub<Self> for Color {
+  type Output = Color;
+
+  fn sub(self, _rhs: Self) -> Self::Output {
+    Color::new(self.r - _rhs.r, self.g - _rhs.g, self.b - _rhs.b)
+  }
+}
+
 impl Mul<f64> for Color {
   type Output = Color;
 
@@ -103,6 +143,7 @@ impl Mul<Color> for f64 {
 /**
  * 
  */
+#[allow(clippy::empty_docs)]
 impl Mul<&Color> for &Color {
   type Output = Color;
 
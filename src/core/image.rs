@@ -1,4 +1,268 @@
-struct Image {
+use std::fs::{self, File};
+use std::io::{BufWriter, Error, ErrorKind, Result};
+use std::path::Path;
 
+use crate::utils::utils::clamp;
+use super::color::Color;
+
+/**
+ * Rendered pixels, rows from the bottom of the picture like the film coordinates of cameras.
+ **/
+#[derive(Clone, Debug)]
+pub struct Image {
+  width: u32,
+  height: u32,
+  pixels: Vec<Color>,
+}
+
+impl Image {
+  pub fn new(width: u32, height: u32) -> Self {
+    Self { width, height, pixels: vec![Color::new(0.0, 0.0, 0.0); (width * height) as usize] }
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn get(&self, i: u32, j: u32) -> Color {
+    self.pixels[(j * self.width + i) as usize]
+  }
+
+  pub fn set(&mut self, i: u32, j: u32, color: Color) {
+    self.pixels[(j * self.width + i) as usize] = color;
+  }
+
+  /**
+   * Copy `other` over the pixels from (i, j), as far as it fits.
+   **/
+  pub fn paste(&mut self, other: &Image, i: u32, j: u32) {
+    for y in 0..other.height.min(self.height.saturating_sub(j)) {
+      for x in 0..other.width.min(self.width.saturating_sub(i)) {
+        self.set(i + x, j + y, other.get(x, y));
+      }
+    }
+  }
+
+  /**
+   * 8-bit RGB samples from the top row, gamma corrected like `Color::write_color_gamma_corrected`.
+   **/
+  pub fn to_rgb8(&self) -> Vec<u8> {
+    let channel = |c: f64| (256.0 * clamp(c.sqrt(), 0.0, 0.999)) as u8;
+    (0..self.height).rev()
+      .flat_map(|j| (0..self.width).map(move |i| (i, j)))
+      .flat_map(|(i, j)| {
+        let color = self.get(i, j);
+        vec![channel(color.r()), channel(color.g()), channel(color.b())]
+      })
+      .collect()
+  }
+
+  pub fn write_png<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, self.width, self.height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(Error::other)?;
+    writer.write_image_data(&self.to_rgb8()).map_err(Error::other)
+  }
+}
+
+/**
+ * Gray levels in [0, 1] read from an image file, rows from the top like the file, x varies
+ * fastest in `values`. Height maps and aperture masks are loaded from these.
+ **/
+#[derive(Clone, Debug)]
+pub struct GrayImage {
+  width: usize,
+  height: usize,
+  values: Vec<f64>,
 }
 
+impl GrayImage {
+  pub fn new(width: usize, height: usize, values: Vec<f64>) -> Result<Self> {
+    if values.len() != width * height {
+      return Err(Error::new(
+        ErrorKind::InvalidData,
+        format!("expected {}x{} samples, got {}", width, height, values.len()),
+      ));
+    }
+    Ok(Self { width, height, values })
+  }
+
+  /**
+   * Grayscale PGM image, ASCII (P2) or binary (P5), scaled to [0, 1] by its maximum value.
+   * Refer to: https://netpbm.sourceforge.net/doc/pgm.html
+   **/
+  pub fn parse_pgm(bytes: &[u8]) -> Result<Self> {
+    let invalid = |what: &str| Error::new(ErrorKind::InvalidData, format!("invalid PGM {}", what));
+    let mut pos = 0;
+    let magic = pgm_token(bytes, &mut pos).ok_or_else(|| invalid("header"))?;
+    let number = |pos: &mut usize| -> Result<usize> {
+      pgm_token(bytes, pos)
+        .and_then(|token| token.parse::<usize>().ok())
+        .ok_or_else(|| invalid("header"))
+    };
+    let (width, height, max_value) = (number(&mut pos)?, number(&mut pos)?, number(&mut pos)?);
+    if max_value == 0 || max_value > 65535 {
+      return Err(invalid("maximum value"));
+    }
+    let scale = 1.0 / max_value as f64;
+
+    let values = match magic {
+      "P2" => {
+        let mut values = Vec::with_capacity(width * height);
+        while let Some(token) = pgm_token(bytes, &mut pos) {
+          values.push(token.parse::<u32>().map_err(|_| invalid("sample"))? as f64 * scale);
+        }
+        values
+      },
+      "P5" => {
+        // A single whitespace separates the header from the raster
+        let raster = bytes.get(pos + 1..).unwrap_or(&[]);
+        if max_value < 256 {
+          raster.iter().map(|b| *b as f64 * scale).collect()
+        } else {
+          raster.chunks_exact(2).map(|b| u16::from_be_bytes([b[0], b[1]]) as f64 * scale).collect()
+        }
+      },
+      _ => return Err(invalid("magic number")),
+    };
+    Self::new(width, height, values)
+  }
+
+  /**
+   * PNG image, using the gray level (or the average of the color channels), scaled to [0, 1].
+   **/
+  pub fn decode_png(bytes: &[u8]) -> Result<Self> {
+    let mut decoder = png::Decoder::new(bytes);
+    // Palettes and bit depths under 8 come out as 8-bit samples
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+
+    let (bytes_per_sample, max_value) = match info.bit_depth {
+      png::BitDepth::Sixteen => (2, 65535.0),
+      _ => (1, 255.0),
+    };
+    let samples = info.color_type.samples();
+    let channels = if samples >= 3 { 3 } else { 1 };
+    let sample = |pixel: &[u8], c: usize| -> f64 {
+      let b = &pixel[c * bytes_per_sample..];
+      if bytes_per_sample == 2 { u16::from_be_bytes([b[0], b[1]]) as f64 } else { b[0] as f64 }
+    };
+    let values = buffer[..info.buffer_size()]
+      .chunks_exact(samples * bytes_per_sample)
+      .map(|pixel| (0..channels).map(|c| sample(pixel, c)).sum::<f64>() / (channels as f64 * max_value))
+      .collect();
+    Self::new(info.width as usize, info.height as usize, values)
+  }
+
+  /**
+   * Load a `.pgm` or `.png` image.
+   **/
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+    let path = path.as_ref();
+    let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+    match extension.as_deref() {
+      Some("pgm") => Self::parse_pgm(&fs::read(path)?),
+      Some("png") => Self::decode_png(&fs::read(path)?),
+      _ => Err(Error::new(ErrorKind::InvalidInput, "expected a .pgm or .png image")),
+    }
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  pub fn get(&self, i: usize, j: usize) -> f64 {
+    self.values[j * self.width + i]
+  }
+
+  pub fn values(&self) -> &[f64] {
+    &self.values
+  }
+}
+
+/**
+ * Next whitespace separated token of a PGM header or ASCII raster, skipping `#` comments.
+ **/
+fn pgm_token<'a>(bytes: &'a [u8], pos: &mut usize) -> Option<&'a str> {
+  loop {
+    match bytes.get(*pos)? {
+      b'#' => {
+        while bytes.get(*pos).is_some_and(|b| *b != b'\n') {
+          *pos += 1;
+        }
+      },
+      b if b.is_ascii_whitespace() => *pos += 1,
+      _ => break,
+    }
+  }
+  let start = *pos;
+  while bytes.get(*pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+    *pos += 1;
+  }
+  std::str::from_utf8(&bytes[start..*pos]).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_rgb8() {
+    let mut image = Image::new(2, 2);
+    image.set(0, 1, Color::new(1.0, 0.25, 0.0));
+    image.set(1, 0, Color::new(4.0, 4.0, 4.0));
+    // Top row first, clamped and gamma corrected
+    assert_eq!(image.to_rgb8(), vec![255, 128, 0, 0, 0, 0, 0, 0, 0, 255, 255, 255]);
+  }
+
+  #[test]
+  fn test_paste() {
+    let mut image = Image::new(3, 2);
+    let mut patch = Image::new(2, 2);
+    patch.set(0, 0, Color::new(1.0, 0.0, 0.0));
+    patch.set(1, 1, Color::new(0.0, 1.0, 0.0));
+    // Clipped at the right edge
+    image.paste(&patch, 2, 0);
+    assert_eq!(image.get(2, 0), Color::new(1.0, 0.0, 0.0));
+    assert_eq!(image.get(1, 1), Color::new(0.0, 0.0, 0.0));
+    image.paste(&patch, 1, 0);
+    assert_eq!(image.get(2, 1), Color::new(0.0, 1.0, 0.0));
+  }
+
+  #[test]
+  fn test_parse_gray() {
+    let ascii = GrayImage::parse_pgm(b"P2\n# ramp\n3 2\n4\n0 1 2\n2 3 4\n").unwrap();
+    assert_eq!(ascii.get(1, 0), 0.25);
+    assert_eq!(ascii.get(2, 1), 1.0);
+
+    let binary = GrayImage::parse_pgm(b"P5 2 2 255\n\x00\x33\xcc\xff").unwrap();
+    assert_eq!(binary.get(1, 0), 0.2);
+    assert_eq!(binary.get(0, 1), 0.8);
+    assert!(GrayImage::parse_pgm(b"P5 2 2 255\n\x00\x33\xcc").is_err());
+    assert!(GrayImage::parse_pgm(b"P6 2 2 255\n").is_err());
+
+    let mut bytes = Vec::new();
+    {
+      let mut encoder = png::Encoder::new(&mut bytes, 2, 2);
+      encoder.set_color(png::ColorType::Grayscale);
+      encoder.set_depth(png::BitDepth::Sixteen);
+      let mut writer = encoder.write_header().unwrap();
+      writer.write_image_data(&[0, 0, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00]).unwrap();
+    }
+    let decoded = GrayImage::decode_png(&bytes).unwrap();
+    assert_eq!(decoded.get(1, 0), 1.0);
+    assert!((decoded.get(0, 1) - 0.5).abs() < 1e-4);
+  }
+}
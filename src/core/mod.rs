@@ -1,9 +1,15 @@
 pub mod color;
 pub mod image;
 pub mod point3;
+pub mod polynomial;
+pub mod quaternion;
 pub mod ray;
+pub mod spectrum;
+pub mod track;
+pub mod transform;
 pub mod vec3;
 
+#[allow(clippy::useless_attribute, clippy::legacy_numeric_constants)]
 pub use std::f64::{INFINITY, consts::PI};
 
 // --- Utilities ---
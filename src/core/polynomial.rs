@@ -0,0 +1,143 @@
+/**
+ * Real roots of a x^2 + b x + c, sorted, computed without cancellation.
+ * Refer to: https://en.wikipedia.org/wiki/Loss_of_significance#A_better_algorithm
+ **/
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+  if a == 0.0 {
+    if b == 0.0 {
+      return None;
+    }
+    let t = -c / b;
+    return Some((t, t));
+  }
+  let discriminant = b * b - 4.0 * a * c;
+  if discriminant < 0.0 {
+    return None;
+  }
+  let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+  if q == 0.0 {
+    // b and c are zero
+    return Some((0.0, 0.0));
+  }
+  let (t0, t1) = (q / a, c / q);
+  Some(if t0 < t1 { (t0, t1) } else { (t1, t0) })
+}
+
+/**
+ * Polynomial with real coefficients, from the constant term up.
+ **/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polynomial {
+  coeffs: Vec<f64>,
+}
+
+impl Polynomial {
+  pub fn new(coeffs: Vec<f64>) -> Self {
+    let mut coeffs = coeffs;
+    while coeffs.len() > 1 && *coeffs.last().unwrap() == 0.0 {
+      coeffs.pop();
+    }
+    Self { coeffs }
+  }
+
+  pub fn degree(&self) -> usize {
+    self.coeffs.len().saturating_sub(1)
+  }
+
+  pub fn eval(&self, x: f64) -> f64 {
+    self.coeffs.iter().rev().fold(0.0, |acc, c| acc * x + c)
+  }
+
+  pub fn derivative(&self) -> Self {
+    Self::new(self.coeffs.iter().enumerate().skip(1).map(|(i, c)| i as f64 * c).collect())
+  }
+
+  /**
+   * The real roots in [lo, hi], sorted.
+   * The roots of the derivative split the range into monotonic pieces, each holding at most
+   * one root which is then found by bisection. This can't miss roots as Newton iterations may,
+   * only double roots where the polynomial touches zero without crossing it can be missed.
+   **/
+  pub fn roots_in(&self, lo: f64, hi: f64) -> Vec<f64> {
+    match self.degree() {
+      0 => Vec::new(),
+      1 => {
+        let x = -self.coeffs[0] / self.coeffs[1];
+        if x >= lo && x <= hi { vec![x] } else { Vec::new() }
+      },
+      _ => {
+        let mut bounds = vec![lo];
+        bounds.extend(self.derivative().roots_in(lo, hi));
+        bounds.push(hi);
+        bounds.windows(2)
+          .filter_map(|pair| self.bisect(pair[0], pair[1]))
+          .fold(Vec::new(), |mut roots, x| {
+            // A root on the boundary of two pieces is found twice
+            if roots.last().is_none_or(|last: &f64| (x - last).abs() > 1e-12 * (1.0 + x.abs())) {
+              roots.push(x);
+            }
+            roots
+          })
+      },
+    }
+  }
+
+  fn bisect(&self, lo: f64, hi: f64) -> Option<f64> {
+    let (mut lo, mut hi) = (lo, hi);
+    let (f_lo, f_hi) = (self.eval(lo), self.eval(hi));
+    if f_lo == 0.0 {
+      return Some(lo);
+    }
+    if f_hi == 0.0 {
+      return Some(hi);
+    }
+    if f_lo.signum() == f_hi.signum() {
+      return None;
+    }
+    for _ in 0..100 {
+      let mid = 0.5 * (lo + hi);
+      if mid <= lo || mid >= hi {
+        break;
+      }
+      let f_mid = self.eval(mid);
+      if f_mid == 0.0 {
+        return Some(mid);
+      }
+      if f_mid.signum() == f_lo.signum() {
+        lo = mid;
+      } else {
+        hi = mid;
+      }
+    }
+    Some(0.5 * (lo + hi))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_quadratic() {
+    assert_eq!(solve_quadratic(1.0, -3.0, 2.0), Some((1.0, 2.0)));
+    assert_eq!(solve_quadratic(1.0, 0.0, 1.0), None);
+    assert_eq!(solve_quadratic(0.0, 2.0, -4.0), Some((2.0, 2.0)));
+    // Tiny root next to a large one
+    let (t0, t1) = solve_quadratic(1.0, -1e8, 1.0).unwrap();
+    assert!((t0 - 1e-8).abs() < 1e-20 && (t1 - 1e8).abs() < 1e-6);
+  }
+
+  #[test]
+  fn test_roots() {
+    // (x - 1)(x - 2)(x - 3)(x - 4)
+    let p = Polynomial::new(vec![24.0, -50.0, 35.0, -10.0, 1.0]);
+    assert_eq!(p.degree(), 4);
+    let roots = p.roots_in(-10.0, 10.0);
+    assert_eq!(roots.len(), 4);
+    for (root, expected) in roots.iter().zip([1.0, 2.0, 3.0, 4.0].iter()) {
+      assert!((root - expected).abs() < 1e-9);
+    }
+    assert_eq!(p.roots_in(1.5, 3.5).len(), 2);
+    assert!(Polynomial::new(vec![1.0, 0.0, 1.0]).roots_in(-10.0, 10.0).is_empty());
+  }
+}
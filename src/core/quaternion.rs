@@ -0,0 +1,157 @@
+use super::{
+  vec3::Vec3,
+  track::Track,
+  transform::Transform,
+  degrees_to_radians,
+  radians_to_degrees,
+};
+
+/**
+ * Unit quaternion w + (x, y, z) representing a rotation, interpolated along the shortest arc.
+ * Refer to: https://en.wikipedia.org/wiki/Quaternions_and_spatial_rotation
+ **/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+  pub w: f64,
+  pub v: Vec3,
+}
+
+impl Default for Quaternion {
+  fn default() -> Self {
+    Self::identity()
+  }
+}
+
+impl Quaternion {
+  pub fn identity() -> Self {
+    Quaternion { w: 1.0, v: Vec3::new(0.0, 0.0, 0.0) }
+  }
+
+  /**
+   * Rotation counter-clockwise around `axis` when looking down it, like `Transform::rotate`.
+   **/
+  pub fn from_axis_angle(axis: Vec3, degrees: f64) -> Self {
+    let (sin, cos) = (degrees_to_radians(degrees) / 2.0).sin_cos();
+    Quaternion { w: cos, v: sin * axis.unit() }
+  }
+
+  pub fn dot(&self, other: &Quaternion) -> f64 {
+    self.w * other.w + Vec3::dot(&self.v, &other.v)
+  }
+
+  pub fn normalized(&self) -> Self {
+    let length = self.dot(self).sqrt();
+    Quaternion { w: self.w / length, v: self.v / length }
+  }
+
+  /**
+   * Rotation applying `self` first and `next` afterwards.
+   **/
+  pub fn then(&self, next: &Quaternion) -> Self {
+    let (a, b) = (next, self);
+    Quaternion {
+      w: a.w * b.w - Vec3::dot(&a.v, &b.v),
+      v: a.w * b.v + b.w * a.v + Vec3::cross(&a.v, &b.v),
+    }
+  }
+
+  /**
+   * Axis and angle in degrees, in [0, 360).
+   **/
+  pub fn axis_angle(&self) -> (Vec3, f64) {
+    let sin = self.v.length();
+    if sin == 0.0 {
+      return (Vec3::new(1.0, 0.0, 0.0), 0.0);
+    }
+    (self.v / sin, radians_to_degrees(2.0 * sin.atan2(self.w)))
+  }
+
+  pub fn to_transform(&self) -> Transform {
+    let (axis, degrees) = self.axis_angle();
+    Transform::rotate(axis, degrees)
+  }
+
+  /**
+   * Constant speed interpolation from `a` (t = 0) to `b` (t = 1), the short way around.
+   * Refer to: https://en.wikipedia.org/wiki/Slerp
+   **/
+  pub fn slerp(a: &Quaternion, b: &Quaternion, t: f64) -> Self {
+    // q and -q are the same rotation, take the closer one
+    let mut cos = a.dot(b);
+    let b = if cos < 0.0 {
+      cos = -cos;
+      Quaternion { w: -b.w, v: -b.v }
+    } else {
+      *b
+    };
+    if cos > 0.9995 {
+      // Nearly parallel: the normalized linear blend is as good and stays stable
+      let blend = Quaternion { w: a.w + (b.w - a.w) * t, v: a.v + (b.v - a.v) * t };
+      return blend.normalized();
+    }
+    let theta = cos.acos();
+    let (wa, wb) = (((1.0 - t) * theta).sin() / theta.sin(), (t * theta).sin() / theta.sin());
+    Quaternion { w: wa * a.w + wb * b.w, v: wa * a.v + wb * b.v }
+  }
+
+  /**
+   * Angle in degrees of the rotation taking `self` to `other`.
+   **/
+  pub fn angle_to(&self, other: &Quaternion) -> f64 {
+    radians_to_degrees(2.0 * self.dot(other).abs().min(1.0).acos())
+  }
+}
+
+impl Track<Quaternion> {
+  /**
+   * Rotation at `time`, slerped between the keys whatever the interpolation of the track.
+   **/
+  pub fn slerp(&self, time: f64) -> Quaternion {
+    match self.segment(time) {
+      Some((i, s)) => Quaternion::slerp(&self.keys()[i].1, &self.keys()[i + 1].1, s),
+      None => self.held(time),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::point3::Point3;
+
+  fn assert_near(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-9, "{:?} != {:?}", a, b);
+  }
+
+  #[test]
+  fn test_rotation() {
+    let q = Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 2.0), 90.0);
+    let p = Point3::new(1.0, 0.0, 0.0);
+    assert_near(q.to_transform().point(p), Point3::new(0.0, 1.0, 0.0));
+    assert_near(q.to_transform().point(p), Transform::rotate(Vec3::new(0.0, 0.0, 1.0), 90.0).point(p));
+    let (axis, degrees) = q.axis_angle();
+    assert_near(axis, Vec3::new(0.0, 0.0, 1.0));
+    assert!((degrees - 90.0).abs() < 1e-9);
+
+    let turn = q.then(&Quaternion::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), 90.0));
+    assert_near(turn.to_transform().point(p), Point3::new(0.0, 0.0, 1.0));
+    assert_eq!(Quaternion::identity().axis_angle().1, 0.0);
+  }
+
+  #[test]
+  fn test_slerp() {
+    let a = Quaternion::identity();
+    let b = Quaternion::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 120.0);
+    let half = Quaternion::slerp(&a, &b, 0.5);
+    assert!((half.angle_to(&a) - 60.0).abs() < 1e-9);
+    assert!((half.dot(&half) - 1.0).abs() < 1e-12);
+    // The short way: 350 degrees is 10 degrees the other way
+    let c = Quaternion::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 350.0);
+    assert!((Quaternion::slerp(&a, &c, 0.5).angle_to(&a) - 5.0).abs() < 1e-9);
+
+    let track = Track::new(0.0, a).key(2.0, b);
+    assert_eq!(track.slerp(-1.0), a);
+    assert!((track.slerp(0.5).angle_to(&a) - 30.0).abs() < 1e-9);
+    assert_eq!(track.slerp(3.0), b);
+  }
+}
@@ -6,6 +6,8 @@ pub struct Ray {
   pub origin: Point3,
   pub direction: Vec3, 
   pub time: f64,
+  // Hero wavelength in nm when rendering spectrally, None for RGB rendering
+  pub wavelength: Option<f64>,
 }
 
 impl Ray {
@@ -14,6 +16,7 @@ impl Ray {
       origin,
       direction,
       time: 0.0,
+      wavelength: None,
     }
   }
 
@@ -22,9 +25,22 @@ impl Ray {
       origin,
       direction,
       time,
+      wavelength: None,
     }
   }
 
+  /**
+   * Ray leaving a point this one hit, at the same time and wavelength.
+   **/
+  pub fn scattered(&self, origin: Point3, direction: Vec3) -> Self {
+    Self {
+      origin,
+      direction,
+      ..*self
+    }
+  }
+
+  #[allow(clippy::needless_return)]
   pub fn at(&self, t: f64) -> Point3 {
     return self.origin + self.direction * t;
   }
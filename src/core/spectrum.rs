@@ -0,0 +1,301 @@
+use std::ops::{Add, AddAssign, Mul};
+use std::sync::OnceLock;
+
+use super::color::Color;
+use crate::utils::utils::clamp;
+
+/**
+ * Spectral rendering helpers.
+ * Each path carries a few wavelengths (nm): the first one, the hero wavelength, drives
+ * wavelength-dependent decisions such as the refraction angle in a dispersive dielectric.
+ * Refer to: https://cgg.mff.cuni.cz/~wilkie/Website/EGSR_14_files/WNDWH14HWSS.pdf
+ **/
+pub const LAMBDA_MIN: f64 = 380.0;
+pub const LAMBDA_MAX: f64 = 720.0;
+pub const N_SPECTRUM_SAMPLES: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SampledWavelengths {
+  pub lambda: [f64; N_SPECTRUM_SAMPLES],
+  pub pdf: [f64; N_SPECTRUM_SAMPLES],
+}
+
+impl SampledWavelengths {
+  /**
+   * Hero wavelength uniform over the visible range, the others evenly spaced after it (wrapping).
+   * `u` is a uniform random number in [0, 1).
+   **/
+  pub fn sample_visible(u: f64) -> Self {
+    let range = LAMBDA_MAX - LAMBDA_MIN;
+    let delta = range / N_SPECTRUM_SAMPLES as f64;
+    let mut lambda = [0.0; N_SPECTRUM_SAMPLES];
+    lambda[0] = LAMBDA_MIN + u * range;
+    for i in 1..N_SPECTRUM_SAMPLES {
+      lambda[i] = lambda[i - 1] + delta;
+      if lambda[i] > LAMBDA_MAX {
+        lambda[i] -= range;
+      }
+    }
+    Self { lambda, pdf: [1.0 / range; N_SPECTRUM_SAMPLES] }
+  }
+
+  pub fn hero(&self) -> f64 {
+    self.lambda[0]
+  }
+
+  /**
+   * Keep only the hero wavelength, after a wavelength-dependent event (e.g. dispersion)
+   * made the path invalid for the others.
+   **/
+  pub fn terminate_secondary(&mut self) {
+    if self.is_secondary_terminated() {
+      return;
+    }
+    for i in 1..N_SPECTRUM_SAMPLES {
+      self.pdf[i] = 0.0;
+    }
+    self.pdf[0] /= N_SPECTRUM_SAMPLES as f64;
+  }
+
+  pub fn is_secondary_terminated(&self) -> bool {
+    self.pdf[1..].iter().all(|pdf| *pdf == 0.0)
+  }
+}
+
+/**
+ * Spectral values at the sampled wavelengths.
+ **/
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SampledSpectrum {
+  pub values: [f64; N_SPECTRUM_SAMPLES],
+}
+
+impl SampledSpectrum {
+  pub fn new(value: f64) -> Self {
+    Self { values: [value; N_SPECTRUM_SAMPLES] }
+  }
+
+  /**
+   * Upsample an RGB color and evaluate it at the given wavelengths.
+   **/
+  pub fn from_rgb(color: Color, wavelengths: &SampledWavelengths) -> Self {
+    let mut values = [0.0; N_SPECTRUM_SAMPLES];
+    for (value, lambda) in values.iter_mut().zip(wavelengths.lambda.iter()) {
+      *value = rgb_to_spectrum(color, *lambda);
+    }
+    Self { values }
+  }
+
+  /**
+   * Monte Carlo estimate of the CIE XYZ response, converted to linear sRGB.
+   **/
+  pub fn to_color(&self, wavelengths: &SampledWavelengths) -> Color {
+    let constants = constants();
+    let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+    for i in 0..N_SPECTRUM_SAMPLES {
+      if wavelengths.pdf[i] == 0.0 {
+        continue;
+      }
+      let (xb, yb, zb) = cie_xyz(wavelengths.lambda[i]);
+      let v = self.values[i] / wavelengths.pdf[i];
+      x += v * xb;
+      y += v * yb;
+      z += v * zb;
+    }
+    let scale = 1.0 / (N_SPECTRUM_SAMPLES as f64 * constants.y_integral);
+    let rgb = xyz_to_linear_srgb(x * scale, y * scale, z * scale);
+    rgb * constants.white_balance
+  }
+}
+
+impl Add for SampledSpectrum {
+  type Output = SampledSpectrum;
+
+  fn add(self, _rhs: Self) -> Self::Output {
+    let mut values = self.values;
+    for (value, rhs) in values.iter_mut().zip(_rhs.values.iter()) {
+      *value += rhs;
+    }
+    Self { values }
+  }
+}
+
+impl AddAssign for SampledSpectrum {
+  fn add_assign(&mut self, _rhs: Self) {
+    *self = *self + _rhs;
+  }
+}
+
+impl Mul for SampledSpectrum {
+  type Output = SampledSpectrum;
+
+  fn mul(self, _rhs: Self) -> Self::Output {
+    let mut values = self.values;
+    for (value, rhs) in values.iter_mut().zip(_rhs.values.iter()) {
+      *value *= rhs;
+    }
+    Self { values }
+  }
+}
+
+impl Mul<f64> for SampledSpectrum {
+  type Output = SampledSpectrum;
+
+  fn mul(self, _rhs: f64) -> Self::Output {
+    self * SampledSpectrum::new(_rhs)
+  }
+}
+
+/**
+ * CIE 1931 color matching functions, multi-lobe fit of Wyman, Sloan and Shirley.
+ * Refer to: https://jcgt.org/published/0002/02/01/
+ **/
+pub fn cie_xyz(lambda: f64) -> (f64, f64, f64) {
+  let g = |mu: f64, sigma_low: f64, sigma_high: f64| {
+    let t = (lambda - mu) / if lambda < mu { sigma_low } else { sigma_high };
+    (-0.5 * t * t).exp()
+  };
+  let x = 1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2);
+  let y = 0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1);
+  let z = 1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8);
+  (x, y, z)
+}
+
+/**
+ * CIE XYZ to linear sRGB (D65 white point).
+ **/
+pub fn xyz_to_linear_srgb(x: f64, y: f64, z: f64) -> Color {
+  Color::new(
+    3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+    -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+    0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+  )
+}
+
+// Smits' basis spectra, 10 bins over the visible range.
+// Refer to: https://www.cs.utah.edu/~bes/papers/color/
+const SMITS_WHITE: [f64; 10] = [1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000];
+const SMITS_CYAN: [f64; 10] = [0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000];
+const SMITS_MAGENTA: [f64; 10] = [1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959];
+const SMITS_YELLOW: [f64; 10] = [0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840];
+const SMITS_RED: [f64; 10] = [0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149];
+const SMITS_GREEN: [f64; 10] = [0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025];
+const SMITS_BLUE: [f64; 10] = [1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496];
+
+fn smits_basis(basis: &[f64; 10], lambda: f64) -> f64 {
+  // Linear interpolation between the bin centers
+  let bin_width = (LAMBDA_MAX - LAMBDA_MIN) / 10.0;
+  let s = clamp((lambda - LAMBDA_MIN) / bin_width - 0.5, 0.0, 9.0);
+  let i = (s.floor() as usize).min(8);
+  let f = s - i as f64;
+  basis[i] * (1.0 - f) + basis[i + 1] * f
+}
+
+/**
+ * RGB to spectrum upsampling (Smits 1999), evaluated at a single wavelength.
+ **/
+pub fn rgb_to_spectrum(color: Color, lambda: f64) -> f64 {
+  let (r, g, b) = (color.r(), color.g(), color.b());
+  let basis = |spectrum: &[f64; 10]| smits_basis(spectrum, lambda);
+  let value = if r <= g && r <= b {
+    let mut v = r * basis(&SMITS_WHITE);
+    if g <= b {
+      v += (g - r) * basis(&SMITS_CYAN) + (b - g) * basis(&SMITS_BLUE);
+    } else {
+      v += (b - r) * basis(&SMITS_CYAN) + (g - b) * basis(&SMITS_GREEN);
+    }
+    v
+  } else if g <= r && g <= b {
+    let mut v = g * basis(&SMITS_WHITE);
+    if r <= b {
+      v += (r - g) * basis(&SMITS_MAGENTA) + (b - r) * basis(&SMITS_BLUE);
+    } else {
+      v += (b - g) * basis(&SMITS_MAGENTA) + (r - b) * basis(&SMITS_RED);
+    }
+    v
+  } else {
+    let mut v = b * basis(&SMITS_WHITE);
+    if r <= g {
+      v += (r - b) * basis(&SMITS_YELLOW) + (g - r) * basis(&SMITS_GREEN);
+    } else {
+      v += (g - b) * basis(&SMITS_YELLOW) + (r - g) * basis(&SMITS_RED);
+    }
+    v
+  };
+  value.max(0.0)
+}
+
+struct Constants {
+  // Integral of the Y matching function over the sampled range
+  y_integral: f64,
+  // Per-channel scale so that a flat spectrum of 1 comes out as white
+  white_balance: Color,
+}
+
+fn constants() -> &'static Constants {
+  static CONSTANTS: OnceLock<Constants> = OnceLock::new();
+  CONSTANTS.get_or_init(|| {
+    let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+    let mut lambda = LAMBDA_MIN;
+    while lambda <= LAMBDA_MAX {
+      let (xb, yb, zb) = cie_xyz(lambda);
+      x += xb;
+      y += yb;
+      z += zb;
+      lambda += 1.0;
+    }
+    let white = xyz_to_linear_srgb(x / y, 1.0, z / y);
+    Constants {
+      y_integral: y,
+      white_balance: Color::new(1.0 / white.r(), 1.0 / white.g(), 1.0 / white.b()),
+    }
+  })
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn assert_color_near(a: Color, b: Color, eps: f64) {
+    assert!(
+      (a.r() - b.r()).abs() < eps && (a.g() - b.g()).abs() < eps && (a.b() - b.b()).abs() < eps,
+      "{:?} != {:?}", a, b,
+    );
+  }
+
+  #[test]
+  fn test_sample_visible() {
+    let wavelengths = SampledWavelengths::sample_visible(0.9);
+    assert_eq!(wavelengths.hero(), LAMBDA_MIN + 0.9 * (LAMBDA_MAX - LAMBDA_MIN));
+    assert!(wavelengths.lambda.iter().all(|l| *l >= LAMBDA_MIN && *l <= LAMBDA_MAX));
+
+    let mut terminated = wavelengths;
+    terminated.terminate_secondary();
+    assert!(terminated.is_secondary_terminated());
+    assert_eq!(terminated.pdf[0], wavelengths.pdf[0] / N_SPECTRUM_SAMPLES as f64);
+  }
+
+  #[test]
+  fn test_upsampling() {
+    let white = Color::new(1.0, 1.0, 1.0);
+    assert!((rgb_to_spectrum(white, 550.0) - 1.0).abs() < 0.01);
+    let red = Color::new(1.0, 0.0, 0.0);
+    assert!(rgb_to_spectrum(red, 680.0) > 0.9);
+    assert!(rgb_to_spectrum(red, 450.0) < 0.1);
+  }
+
+  #[test]
+  fn test_round_trip() {
+    // Averaging many wavelength samples of an upsampled color gives the color back
+    let n = 2000;
+    for color in [Color::new(1.0, 1.0, 1.0), Color::new(0.8, 0.3, 0.1), Color::new(0.1, 0.2, 0.7)].iter() {
+      let mut sum = Color::new(0.0, 0.0, 0.0);
+      for i in 0..n {
+        let wavelengths = SampledWavelengths::sample_visible((i as f64 + 0.5) / n as f64);
+        sum += SampledSpectrum::from_rgb(*color, &wavelengths).to_color(&wavelengths);
+      }
+      assert_color_near(sum * (1.0 / n as f64), *color, 0.06);
+    }
+  }
+}
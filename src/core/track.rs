@@ -0,0 +1,126 @@
+use std::ops::{Add, Mul, Sub};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+  Linear,
+  // Cubic through the keys, tangents from the neighboring keys
+  CatmullRom,
+}
+
+/**
+ * Values of an animated parameter at key times, interpolated in between and held before the
+ * first key and after the last one.
+ **/
+#[derive(Clone, Debug)]
+pub struct Track<T> {
+  keys: Vec<(f64, T)>,
+  interpolation: Interpolation,
+}
+
+impl<T: Copy> Track<T> {
+  /**
+   * Linear track with a first key, add the others with `key`. Panics if `time` is not finite.
+   **/
+  pub fn new(time: f64, value: T) -> Self {
+    Track { keys: Vec::new(), interpolation: Interpolation::Linear }.key(time, value)
+  }
+
+  /**
+   * Add a key, replacing any at the same time. Panics if `time` is not finite.
+   **/
+  pub fn key(mut self, time: f64, value: T) -> Self {
+    assert!(time.is_finite(), "key time {} is not finite", time);
+    match self.keys.iter().position(|(t, _)| *t >= time) {
+      Some(index) if self.keys[index].0 == time => self.keys[index].1 = value,
+      Some(index) => self.keys.insert(index, (time, value)),
+      None => self.keys.push((time, value)),
+    }
+    self
+  }
+
+  pub fn interpolation(mut self, interpolation: Interpolation) -> Self {
+    self.interpolation = interpolation;
+    self
+  }
+
+  pub fn keys(&self) -> &[(f64, T)] {
+    &self.keys
+  }
+
+  /**
+   * Index of the key starting the segment around `time` and the fraction of the segment at
+   * `time`, None outside the keys.
+   **/
+  pub(crate) fn segment(&self, time: f64) -> Option<(usize, f64)> {
+    let keys = &self.keys;
+    if time <= keys[0].0 || time >= keys[keys.len() - 1].0 {
+      return None;
+    }
+    let i = keys.iter().position(|(t, _)| *t > time).unwrap() - 1;
+    Some((i, (time - keys[i].0) / (keys[i + 1].0 - keys[i].0)))
+  }
+
+  /**
+   * Value held before the first key or after the last one.
+   **/
+  pub(crate) fn held(&self, time: f64) -> T {
+    if time <= self.keys[0].0 { self.keys[0].1 } else { self.keys[self.keys.len() - 1].1 }
+  }
+}
+
+impl<T> Track<T>
+where
+  T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f64, Output = T>,
+{
+  pub fn sample(&self, time: f64) -> T {
+    let (i, s) = match self.segment(time) {
+      Some(segment) => segment,
+      None => return self.held(time),
+    };
+    let keys = &self.keys;
+    let last = keys.len() - 1;
+    let ((t0, p0), (t1, p1)) = (keys[i], keys[i + 1]);
+    let h = t1 - t0;
+    match self.interpolation {
+      Interpolation::Linear => p0 + (p1 - p0) * s,
+      Interpolation::CatmullRom => {
+        // Finite difference tangents, one sided at the ends, scaled to the segment
+        let tangent = |k: usize| {
+          let (a, b) = (k.saturating_sub(1), (k + 1).min(last));
+          (keys[b].1 - keys[a].1) * (h / (keys[b].0 - keys[a].0))
+        };
+        let (m0, m1) = (tangent(i), tangent(i + 1));
+        let (s2, s3) = (s * s, s * s * s);
+        p0 * (2.0 * s3 - 3.0 * s2 + 1.0) + m0 * (s3 - 2.0 * s2 + s) + p1 * (3.0 * s2 - 2.0 * s3) + m1 * (s3 - s2)
+      },
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::vec3::Vec3;
+
+  #[test]
+  fn test_track() {
+    let linear = Track::new(2.0, 10.0).key(0.0, 0.0).key(4.0, 0.0);
+    assert_eq!(linear.keys().len(), 3);
+    assert_eq!(linear.sample(-1.0), 0.0);
+    assert_eq!(linear.sample(1.0), 5.0);
+    assert_eq!(linear.sample(3.5), 2.5);
+    assert_eq!(linear.sample(9.0), 0.0);
+
+    // Catmull-Rom goes through the keys, smoothly, and reproduces straight lines
+    let smooth = linear.clone().interpolation(Interpolation::CatmullRom);
+    assert_eq!(smooth.sample(2.0), 10.0);
+    assert!(smooth.sample(1.0) > 5.0);
+    let (a, b) = (smooth.sample(2.0 - 1e-6), smooth.sample(2.0 + 1e-6));
+    assert!((a - b).abs() < 1e-5);
+    let line = Track::new(0.0, Vec3::new(0.0, 0.0, 0.0))
+      .key(1.0, Vec3::new(1.0, 2.0, 0.0))
+      .key(3.0, Vec3::new(3.0, 6.0, 0.0))
+      .interpolation(Interpolation::CatmullRom);
+    assert!((line.sample(2.2) - Vec3::new(2.2, 4.4, 0.0)).length() < 1e-12);
+  }
+}
@@ -0,0 +1,283 @@
+use super::{
+  vec3::Vec3,
+  point3::Point3,
+  ray::Ray,
+  degrees_to_radians,
+};
+
+type Matrix = [[f64; 4]; 4];
+
+const IDENTITY: Matrix = [
+  [1.0, 0.0, 0.0, 0.0],
+  [0.0, 1.0, 0.0, 0.0],
+  [0.0, 0.0, 1.0, 0.0],
+  [0.0, 0.0, 0.0, 1.0],
+];
+
+/**
+ * Affine transform of the 3D space, kept together with its inverse.
+ * Refer to: https://en.wikipedia.org/wiki/Transformation_matrix#Affine_transformations
+ **/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+  m: Matrix,
+  m_inv: Matrix,
+}
+
+impl Default for Transform {
+  fn default() -> Self {
+    Self::identity()
+  }
+}
+
+impl Transform {
+  pub const fn identity() -> Self {
+    Self { m: IDENTITY, m_inv: IDENTITY }
+  }
+
+  /**
+   * Build from a row-major matrix, None if it can't be inverted.
+   **/
+  pub fn from_matrix(m: [[f64; 4]; 4]) -> Option<Self> {
+    let m_inv = invert(&m)?;
+    Some(Self { m, m_inv })
+  }
+
+  pub fn translate(delta: Vec3) -> Self {
+    let mut m = IDENTITY;
+    let mut m_inv = IDENTITY;
+    m[0][3] = delta.x;
+    m[1][3] = delta.y;
+    m[2][3] = delta.z;
+    m_inv[0][3] = -delta.x;
+    m_inv[1][3] = -delta.y;
+    m_inv[2][3] = -delta.z;
+    Self { m, m_inv }
+  }
+
+  pub fn scale(s: Vec3) -> Self {
+    let mut m = IDENTITY;
+    let mut m_inv = IDENTITY;
+    m[0][0] = s.x;
+    m[1][1] = s.y;
+    m[2][2] = s.z;
+    m_inv[0][0] = 1.0 / s.x;
+    m_inv[1][1] = 1.0 / s.y;
+    m_inv[2][2] = 1.0 / s.z;
+    Self { m, m_inv }
+  }
+
+  /**
+   * Rotation around an arbitrary axis, counter-clockwise when looking down the axis.
+   * Refer to: https://en.wikipedia.org/wiki/Rotation_matrix#Rotation_matrix_from_axis_and_angle
+   **/
+  pub fn rotate(axis: Vec3, degrees: f64) -> Self {
+    let a = axis.unit();
+    let theta = degrees_to_radians(degrees);
+    let (sin_theta, cos_theta) = theta.sin_cos();
+
+    let mut m = IDENTITY;
+    m[0][0] = a.x * a.x + (1.0 - a.x * a.x) * cos_theta;
+    m[0][1] = a.x * a.y * (1.0 - cos_theta) - a.z * sin_theta;
+    m[0][2] = a.x * a.z * (1.0 - cos_theta) + a.y * sin_theta;
+    m[1][0] = a.x * a.y * (1.0 - cos_theta) + a.z * sin_theta;
+    m[1][1] = a.y * a.y + (1.0 - a.y * a.y) * cos_theta;
+    m[1][2] = a.y * a.z * (1.0 - cos_theta) - a.x * sin_theta;
+    m[2][0] = a.x * a.z * (1.0 - cos_theta) - a.y * sin_theta;
+    m[2][1] = a.y * a.z * (1.0 - cos_theta) + a.x * sin_theta;
+    m[2][2] = a.z * a.z + (1.0 - a.z * a.z) * cos_theta;
+    // The inverse of a rotation is its transpose
+    Self { m, m_inv: transpose(&m) }
+  }
+
+  /**
+   * Rigid placement of a local frame at `origin`, its y axis turned towards `axis`.
+   * Shapes built around the y axis are placed with it.
+   **/
+  pub fn from_axis(origin: Point3, axis: Vec3) -> Self {
+    let y = axis.unit();
+    let helper = if y.x.abs() > 0.9 { Vec3::new(0.0, 0.0, 1.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    let x = Vec3::cross(&y, &helper).unit();
+    let z = Vec3::cross(&x, &y);
+    let m = [
+      [x.x, y.x, z.x, origin.x],
+      [x.y, y.y, z.y, origin.y],
+      [x.z, y.z, z.z, origin.z],
+      [0.0, 0.0, 0.0, 1.0],
+    ];
+    // Orthonormal basis: the rotation part inverts by transposition
+    let mut m_inv = transpose(&m);
+    m_inv[3] = [0.0, 0.0, 0.0, 1.0];
+    for row in m_inv.iter_mut().take(3) {
+      row[3] = -(row[0] * origin.x + row[1] * origin.y + row[2] * origin.z);
+    }
+    Self { m, m_inv }
+  }
+
+  /**
+   * The transform applying `self` first and `next` afterwards.
+   **/
+  pub fn then(&self, next: &Transform) -> Self {
+    Self {
+      m: multiply(&next.m, &self.m),
+      m_inv: multiply(&self.m_inv, &next.m_inv),
+    }
+  }
+
+  pub fn inverse(&self) -> Self {
+    Self { m: self.m_inv, m_inv: self.m }
+  }
+
+  pub fn matrix(&self) -> &[[f64; 4]; 4] {
+    &self.m
+  }
+
+  pub fn point(&self, p: Point3) -> Point3 {
+    let m = &self.m;
+    Point3::new(
+      m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3],
+      m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3],
+      m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3],
+    )
+  }
+
+  pub fn vector(&self, v: Vec3) -> Vec3 {
+    let m = &self.m;
+    Vec3::new(
+      m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+      m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+      m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+    )
+  }
+
+  /**
+   * Normals are transformed by the inverse transpose to stay perpendicular to the surface.
+   * The result is not normalized.
+   **/
+  pub fn normal(&self, n: Vec3) -> Vec3 {
+    let m = &self.m_inv;
+    Vec3::new(
+      m[0][0] * n.x + m[1][0] * n.y + m[2][0] * n.z,
+      m[0][1] * n.x + m[1][1] * n.y + m[2][1] * n.z,
+      m[0][2] * n.x + m[1][2] * n.y + m[2][2] * n.z,
+    )
+  }
+
+  /**
+   * Transform a ray, keeping its parameterization: `t` along the result hits the transformed point.
+   **/
+  pub fn ray(&self, r: &Ray) -> Ray {
+    let mut transformed = Ray::new_with_time(self.point(r.origin), self.vector(r.direction), r.time);
+    transformed.wavelength = r.wavelength;
+    transformed
+  }
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+  let mut m = [[0.0; 4]; 4];
+  for (i, row) in m.iter_mut().enumerate() {
+    for (j, value) in row.iter_mut().enumerate() {
+      *value = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+    }
+  }
+  m
+}
+
+fn transpose(a: &Matrix) -> Matrix {
+  let mut m = [[0.0; 4]; 4];
+  for (i, row) in m.iter_mut().enumerate() {
+    for (j, value) in row.iter_mut().enumerate() {
+      *value = a[j][i];
+    }
+  }
+  m
+}
+
+/**
+ * Gauss-Jordan elimination with partial pivoting.
+ **/
+fn invert(a: &Matrix) -> Option<Matrix> {
+  let mut m = *a;
+  let mut inv = IDENTITY;
+  for col in 0..4 {
+    let pivot = (col..4)
+      .max_by(|&i, &j| m[i][col].abs().partial_cmp(&m[j][col].abs()).unwrap())?;
+    if m[pivot][col].abs() < 1e-12 {
+      return None;
+    }
+    m.swap(col, pivot);
+    inv.swap(col, pivot);
+
+    let scale = 1.0 / m[col][col];
+    for k in 0..4 {
+      m[col][k] *= scale;
+      inv[col][k] *= scale;
+    }
+    for row in 0..4 {
+      if row != col {
+        let factor = m[row][col];
+        for k in 0..4 {
+          m[row][k] -= factor * m[col][k];
+          inv[row][k] -= factor * inv[col][k];
+        }
+      }
+    }
+  }
+  Some(inv)
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn assert_near(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-9, "{} != {}", a, b);
+  }
+
+  #[test]
+  fn test_translate_scale() {
+    let t = Transform::scale(Vec3::new(2.0, 2.0, 2.0)).then(&Transform::translate(Vec3::new(1.0, 0.0, 0.0)));
+    assert_near(t.point(Point3::new(1.0, 1.0, 1.0)), Point3::new(3.0, 2.0, 2.0));
+    assert_near(t.vector(Vec3::new(1.0, 1.0, 1.0)), Vec3::new(2.0, 2.0, 2.0));
+    assert_near(t.inverse().point(Point3::new(3.0, 2.0, 2.0)), Point3::new(1.0, 1.0, 1.0));
+  }
+
+  #[test]
+  fn test_rotate() {
+    let t = Transform::rotate(Vec3::new(0.0, 1.0, 0.0), 90.0);
+    assert_near(t.point(Point3::new(1.0, 0.0, 0.0)), Point3::new(0.0, 0.0, -1.0));
+    assert_near(t.inverse().point(Point3::new(0.0, 0.0, -1.0)), Point3::new(1.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn test_from_matrix() {
+    let t = Transform::rotate(Vec3::new(1.0, 1.0, 0.0), 30.0)
+      .then(&Transform::translate(Vec3::new(1.0, 2.0, 3.0)));
+    let u = Transform::from_matrix(*t.matrix()).unwrap();
+    let p = Point3::new(0.5, -2.0, 7.0);
+    assert_near(u.inverse().point(t.point(p)), p);
+    assert!(Transform::from_matrix([[0.0; 4]; 4]).is_none());
+  }
+
+  #[test]
+  fn test_from_axis() {
+    let origin = Point3::new(1.0, 2.0, 3.0);
+    let axis = Vec3::new(1.0, 1.0, 0.0);
+    let t = Transform::from_axis(origin, axis);
+    assert_near(t.point(Point3::new(0.0, 0.0, 0.0)), origin);
+    assert_near(t.vector(Vec3::new(0.0, 2.0, 0.0)), axis.unit() * 2.0);
+    let p = Point3::new(-0.5, 4.0, 2.0);
+    assert_near(t.inverse().point(t.point(p)), p);
+    assert!((t.vector(p).length() - p.length()).abs() < 1e-12);
+  }
+
+  #[test]
+  fn test_normal() {
+    // Squash a 45 degree slope, the normal has to follow
+    let t = Transform::scale(Vec3::new(1.0, 0.5, 1.0));
+    let tangent = t.vector(Vec3::new(1.0, 1.0, 0.0));
+    let normal = t.normal(Vec3::new(1.0, -1.0, 0.0));
+    assert!(Vec3::dot(&tangent, &normal).abs() < 1e-12);
+  }
+}
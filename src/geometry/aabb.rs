@@ -0,0 +1,111 @@
+use crate::core::{
+  point3::Point3,
+  ray::Ray,
+  transform::Transform,
+};
+
+/**
+ * Axis-aligned bounding box.
+ **/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+  pub min: Point3,
+  pub max: Point3,
+}
+
+impl Aabb {
+  pub fn new(min: Point3, max: Point3) -> Self {
+    Aabb { min, max }
+  }
+
+  /**
+   * Smallest box holding both boxes.
+   **/
+  pub fn surrounding(a: &Aabb, b: &Aabb) -> Self {
+    Aabb {
+      min: Point3::new(a.min.x.min(b.min.x), a.min.y.min(b.min.y), a.min.z.min(b.min.z)),
+      max: Point3::new(a.max.x.max(b.max.x), a.max.y.max(b.max.y), a.max.z.max(b.max.z)),
+    }
+  }
+
+  /**
+   * Box holding all the points.
+   **/
+  pub fn from_points(points: &[Point3]) -> Self {
+    let first = Aabb::new(points[0], points[0]);
+    points.iter().skip(1).fold(first, |aabb, p| Aabb::surrounding(&aabb, &Aabb::new(*p, *p)))
+  }
+
+  /**
+   * Box holding this box once transformed.
+   **/
+  pub fn transformed(&self, transform: &Transform) -> Self {
+    let corners: Vec<Point3> = (0..8)
+      .map(|i| transform.point(Point3::new(
+        if i & 1 == 0 { self.min.x } else { self.max.x },
+        if i & 2 == 0 { self.min.y } else { self.max.y },
+        if i & 4 == 0 { self.min.z } else { self.max.z },
+      )))
+      .collect();
+    Aabb::from_points(&corners)
+  }
+
+  pub fn centroid(&self) -> Point3 {
+    (self.min + self.max) * 0.5
+  }
+
+  /**
+   * Slab test: if the ray goes through the box within [t_min, t_max].
+   **/
+  pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+    let min = [self.min.x, self.min.y, self.min.z];
+    let max = [self.max.x, self.max.y, self.max.z];
+    let origin = [ray.origin.x, ray.origin.y, ray.origin.z];
+    let direction = [ray.direction.x, ray.direction.y, ray.direction.z];
+    let (mut t0, mut t1) = (t_min, t_max);
+    for axis in 0..3 {
+      let inv_d = 1.0 / direction[axis];
+      let mut t_near = (min[axis] - origin[axis]) * inv_d;
+      let mut t_far = (max[axis] - origin[axis]) * inv_d;
+      if inv_d < 0.0 {
+        std::mem::swap(&mut t_near, &mut t_far);
+      }
+      // NaN when the ray lies in the plane of a face, the comparisons then keep the bounds
+      if t_near > t0 {
+        t0 = t_near;
+      }
+      if t_far < t1 {
+        t1 = t_far;
+      }
+      if t1 < t0 {
+        return false;
+      }
+    }
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::vec3::Vec3;
+
+  #[test]
+  fn test_hit() {
+    let aabb = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+    assert!(aabb.hit(&Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0)), 0.0, f64::INFINITY));
+    assert!(!aabb.hit(&Ray::new(Point3::new(-5.0, 2.0, 0.0), Vec3::new(1.0, 0.0, 0.0)), 0.0, f64::INFINITY));
+    assert!(!aabb.hit(&Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0)), 0.0, 3.0));
+    assert!(!aabb.hit(&Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0)), 0.0, f64::INFINITY));
+  }
+
+  #[test]
+  fn test_transformed() {
+    let aabb = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+    let moved = aabb.transformed(&Transform::translate(Vec3::new(1.0, 2.0, 3.0)));
+    assert_eq!(moved, Aabb::new(Point3::new(1.0, 2.0, 3.0), Point3::new(2.0, 3.0, 4.0)));
+    let turned = aabb.transformed(&Transform::rotate(Vec3::new(0.0, 0.0, 1.0), 90.0));
+    assert!((turned.min - Point3::new(-1.0, 0.0, 0.0)).length() < 1e-12);
+    assert!((turned.max - Point3::new(0.0, 1.0, 1.0)).length() < 1e-12);
+  }
+}
@@ -0,0 +1,113 @@
+use crate::core::{
+  ray::Ray,
+};
+use crate::textures::{Texture};
+use crate::utils::utils::{random_double};
+
+use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
+
+/**
+ * Cutout of any hittable with texture coordinates by an opacity texture (its gray level),
+ * e.g. leaves on flat cards or fences.
+ * Rejected intersections are skipped and the search goes on behind them, so the object can be
+ * put in a list or a hierarchy like any other: only accepted hits ever come out of `hit`.
+ **/
+pub struct AlphaMasked<H: Hittable, T: Texture> {
+  object: H,
+  mask: T,
+  threshold: f64,
+  // Accept hits with a probability equal to the opacity instead of comparing it to the threshold
+  stochastic: bool,
+}
+
+impl<H: Hittable, T: Texture> AlphaMasked<H, T> {
+  /**
+   * Hits are kept where the opacity is at least `threshold`.
+   **/
+  pub fn new(object: H, mask: T, threshold: f64) -> Self {
+    AlphaMasked { object, mask, threshold, stochastic: false }
+  }
+
+  /**
+   * Partial opacity lets a matching fraction of the rays through, which gives soft edges once averaged.
+   **/
+  pub fn new_stochastic(object: H, mask: T) -> Self {
+    AlphaMasked { object, mask, threshold: 0.0, stochastic: true }
+  }
+
+  fn is_opaque(&self, record: &HitRecord) -> bool {
+    let alpha = self.mask.value(record.u, record.v, &record.point).average();
+    if self.stochastic {
+      random_double() < alpha
+    } else {
+      alpha >= self.threshold
+    }
+  }
+}
+
+impl<H: Hittable, T: Texture> Hittable for AlphaMasked<H, T> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    let mut t_from = t_min;
+    while let Some(record) = self.object.hit(ray, t_from, t_max) {
+      if self.is_opaque(&record) {
+        return Some(record);
+      }
+      t_from = record.t;
+    }
+    None
+  }
+
+  fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+    self.object.bounding_box(time0, time1)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::{
+    color::Color,
+    point3::Point3,
+    vec3::Vec3,
+  };
+  use crate::geometry::{
+    sphere::Sphere,
+    hit::HittableList,
+  };
+  use crate::materials::DefaultMaterial;
+  use crate::textures::SolidColor;
+
+  // Opaque on the half space z < 0
+  struct HalfSpace;
+
+  impl Texture for HalfSpace {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+      if p.z < 0.0 { Color::new(1.0, 1.0, 1.0) } else { Color::new(0.0, 0.0, 0.0) }
+    }
+  }
+
+  #[test]
+  fn test_cutout() {
+    let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, DefaultMaterial::new());
+    let masked = AlphaMasked::new(sphere, HalfSpace, 0.5);
+    let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+
+    // The front side is cut away, the ray goes on to the back side
+    let record = masked.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 6.0).abs() < 1e-9);
+    assert!(!record.front_face);
+
+    // Skipped hits don't shadow what is behind in a list
+    let mut world = HittableList::new();
+    world.add(AlphaMasked::new(
+      Sphere::new(Point3::new(0.0, 0.0, 2.0), 0.5, DefaultMaterial::new()),
+      SolidColor::new_gray(0.2),
+      0.5,
+    ));
+    world.add(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, DefaultMaterial::new()));
+    let record = world.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 4.0).abs() < 1e-9);
+    assert_eq!(world.shadow_transmittance(&r, 0.001, 3.5, false), Color::new(1.0, 1.0, 1.0));
+  }
+}
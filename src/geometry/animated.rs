@@ -0,0 +1,173 @@
+use crate::core::{
+  color::Color,
+  point3::Point3,
+  quaternion::Quaternion,
+  ray::Ray,
+  track::Track,
+  transform::Transform,
+  vec3::Vec3,
+};
+
+use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
+
+// Times at which the placement is sampled to bound the motion over a time interval
+const BOUND_STEPS: usize = 64;
+
+/**
+ * Object moving over time: scaled, rotated and then translated by keyframed tracks evaluated at
+ * the time of each ray, so objects blur along their motion under a camera with an open shutter.
+ * Rotations are slerped between their keys. Scales must not have zero components.
+ **/
+pub struct Animated<H: Hittable> {
+  object: H,
+  translation: Track<Vec3>,
+  rotation: Track<Quaternion>,
+  scale: Track<Vec3>,
+}
+
+impl<H: Hittable> Animated<H> {
+  /**
+   * The object standing still where it is, set the tracks that move it.
+   **/
+  pub fn new(object: H) -> Self {
+    Animated {
+      object,
+      translation: Track::new(0.0, Vec3::new(0.0, 0.0, 0.0)),
+      rotation: Track::new(0.0, Quaternion::identity()),
+      scale: Track::new(0.0, Vec3::new(1.0, 1.0, 1.0)),
+    }
+  }
+
+  pub fn translation(mut self, track: Track<Vec3>) -> Self {
+    self.translation = track;
+    self
+  }
+
+  pub fn rotation(mut self, track: Track<Quaternion>) -> Self {
+    self.rotation = track;
+    self
+  }
+
+  pub fn scale(mut self, track: Track<Vec3>) -> Self {
+    self.scale = track;
+    self
+  }
+
+  /**
+   * Placement of the object at `time`.
+   **/
+  pub fn to_world(&self, time: f64) -> Transform {
+    Transform::scale(self.scale.sample(time))
+      .then(&self.rotation.slerp(time).to_transform())
+      .then(&Transform::translate(self.translation.sample(time)))
+  }
+}
+
+impl<H: Hittable> Hittable for Animated<H> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    let to_world = self.to_world(ray.time);
+    let local = to_world.inverse().ray(ray);
+    let mut record = self.object.hit(&local, t_min, t_max)?;
+
+    let outward_normal = to_world.normal(record.outward_normal()).unit();
+    record.point = ray.at(record.t);
+    record.set_face_normal(ray, outward_normal);
+    record.dpdu = to_world.vector(record.dpdu);
+    record.dpdv = to_world.vector(record.dpdv);
+    Some(record)
+  }
+
+  /**
+   * Box of the object placed at regular times over [time0, time1] and at the keys in between,
+   * grown by how far rotations sweep the corners off the chords between those times.
+   **/
+  fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+    let local = self.object.bounding_box(time0, time1)?;
+    let mut times: Vec<f64> = (0..=BOUND_STEPS)
+      .map(|k| time0 + (time1 - time0) * k as f64 / BOUND_STEPS as f64)
+      .collect();
+    let keys = self.translation.keys().iter().map(|(t, _)| *t)
+      .chain(self.rotation.keys().iter().map(|(t, _)| *t))
+      .chain(self.scale.keys().iter().map(|(t, _)| *t));
+    times.extend(keys.filter(|t| *t > time0 && *t < time1));
+    times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let mut bounds: Option<Aabb> = None;
+    let mut sweep: f64 = 0.0;
+    let mut radius: f64 = 0.0;
+    for (k, time) in times.iter().enumerate() {
+      let to_world = self.to_world(*time);
+      let placed = local.transformed(&to_world);
+      bounds = Some(match bounds {
+        Some(b) => Aabb::surrounding(&b, &placed),
+        None => placed,
+      });
+      let center = to_world.point(Point3::new(0.0, 0.0, 0.0));
+      radius = radius.max((placed.max - center).length()).max((placed.min - center).length());
+      if k > 0 {
+        let angle = self.rotation.slerp(times[k - 1]).angle_to(&self.rotation.slerp(*time));
+        sweep = sweep.max(angle);
+      }
+    }
+    // Sagitta of the arc a point at `radius` from the center draws between two samples
+    let pad = radius * (1.0 - (sweep.to_radians() / 2.0).cos());
+    let pad = Vec3::new(pad, pad, pad);
+    bounds.map(|b| Aabb::new(b.min - pad, b.max + pad))
+  }
+
+  fn shadow_transmittance(&self, ray: &Ray, t_min: f64, t_max: f64, skip_transmissive: bool) -> Color {
+    let local = self.to_world(ray.time).inverse().ray(ray);
+    self.object.shadow_transmittance(&local, t_min, t_max, skip_transmissive)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::geometry::sphere::Sphere;
+  use crate::materials::lambertian::Lambertian;
+
+  #[test]
+  fn test_hit_at_time() {
+    let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, Lambertian { albedo: Color::new(0.5, 0.5, 0.5) });
+    let moving = Animated::new(sphere)
+      .translation(Track::new(0.0, Vec3::new(0.0, 0.0, 0.0)).key(1.0, Vec3::new(4.0, 0.0, 0.0)))
+      .scale(Track::new(0.0, Vec3::new(1.0, 1.0, 1.0)).key(1.0, Vec3::new(1.0, 2.0, 1.0)));
+    let down = Vec3::new(0.0, -1.0, 0.0);
+    // Above the start at time 0, gone by time 1
+    let r = Ray::new_with_time(Point3::new(0.0, 5.0, 0.0), down, 0.0);
+    let record = moving.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 4.0).abs() < 1e-9);
+    assert!((record.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+    assert!(moving.hit(&Ray::new_with_time(Point3::new(0.0, 5.0, 0.0), down, 1.0), 0.001, f64::INFINITY).is_none());
+    // Halfway, stretched to 1.5 high
+    let r = Ray::new_with_time(Point3::new(2.0, 5.0, 0.0), down, 0.5);
+    let record = moving.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.point - Point3::new(2.0, 1.5, 0.0)).length() < 1e-9);
+  }
+
+  #[test]
+  fn test_motion_bounds() {
+    // A unit sphere off the axis of a half turn around y
+    let sphere = Sphere::new(Point3::new(3.0, 0.0, 0.0), 1.0, Lambertian { albedo: Color::new(0.5, 0.5, 0.5) });
+    let y = Vec3::new(0.0, 1.0, 0.0);
+    let spinning = Animated::new(sphere)
+      .rotation(Track::new(0.0, Quaternion::identity()).key(1.0, Quaternion::from_axis_angle(y, 180.0)));
+    let bounds = spinning.bounding_box(0.0, 1.0).unwrap();
+    // Holds the sphere along the whole half circle
+    for k in 0..=100 {
+      let angle = (180.0 * k as f64 / 100.0f64).to_radians();
+      let center = Point3::new(3.0 * angle.cos(), 0.0, -3.0 * angle.sin());
+      for corner in [Vec3::new(1.0, 1.0, 1.0), Vec3::new(-1.0, -1.0, -1.0)].iter() {
+        let p = center + *corner;
+        assert!(p.x >= bounds.min.x - 1e-9 && p.x <= bounds.max.x + 1e-9);
+        assert!(p.z >= bounds.min.z - 1e-9 && p.z <= bounds.max.z + 1e-9);
+      }
+    }
+    assert!(bounds.min.z > -4.5 && bounds.max.x < 4.5);
+    // Only the start for a shutter closed at 0
+    let still = spinning.bounding_box(0.0, 0.0).unwrap();
+    assert!((still.min - Point3::new(2.0, -1.0, -1.0)).length() < 1e-9);
+  }
+}
@@ -0,0 +1,141 @@
+use crate::core::{
+  color::Color,
+  ray::Ray,
+};
+
+use super::hit::{HitRecord, Hittable, HittableList};
+use super::aabb::Aabb;
+
+/**
+ * Bounding volume hierarchy: a binary tree of boxes, so a ray only tests the objects whose
+ * boxes it goes through. Objects are split in two halves along the longest axis of their centers.
+ * All the objects must be bounded.
+ * Refer to: https://en.wikipedia.org/wiki/Bounding_volume_hierarchy
+ **/
+pub struct BvhNode {
+  left: Box<dyn Hittable>,
+  right: Option<Box<dyn Hittable>>,
+  bbox: Aabb,
+}
+
+impl BvhNode {
+  pub fn new(list: HittableList, time0: f64, time1: f64) -> Self {
+    let objects: Vec<(Box<dyn Hittable>, Aabb)> = list.into_objects().into_iter()
+      .map(|object| {
+        let bbox = object.bounding_box(time0, time1).expect("BvhNode needs objects with a bounding box");
+        (object, bbox)
+      })
+      .collect();
+    assert!(!objects.is_empty(), "BvhNode needs at least one object");
+    Self::build(objects)
+  }
+
+  fn build(mut objects: Vec<(Box<dyn Hittable>, Aabb)>) -> Self {
+    let bbox = objects.iter().skip(1)
+      .fold(objects[0].1, |bbox, (_, other)| Aabb::surrounding(&bbox, other));
+
+    match objects.len() {
+      1 => {
+        let (left, _) = objects.pop().unwrap();
+        BvhNode { left, right: None, bbox }
+      },
+      2 => {
+        let (right, _) = objects.pop().unwrap();
+        let (left, _) = objects.pop().unwrap();
+        BvhNode { left, right: Some(right), bbox }
+      },
+      n => {
+        let centroids = objects.iter().map(|(_, bbox)| bbox.centroid()).collect::<Vec<_>>();
+        let extent = Aabb::from_points(&centroids);
+        let size = extent.max - extent.min;
+        let key = |bbox: &Aabb| {
+          let c = bbox.centroid();
+          if size.x >= size.y && size.x >= size.z {
+            c.x
+          } else if size.y >= size.z {
+            c.y
+          } else {
+            c.z
+          }
+        };
+        objects.sort_by(|(_, a), (_, b)| key(a).partial_cmp(&key(b)).unwrap());
+
+        let right_half = objects.split_off(n / 2);
+        BvhNode {
+          left: Box::new(Self::build(objects)),
+          right: Some(Box::new(Self::build(right_half))),
+          bbox,
+        }
+      },
+    }
+  }
+}
+
+impl Hittable for BvhNode {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    if !self.bbox.hit(ray, t_min, t_max) {
+      return None;
+    }
+    let left = self.left.hit(ray, t_min, t_max);
+    let closest_so_far = left.as_ref().map_or(t_max, |record| record.t);
+    let right = self.right.as_ref().and_then(|right| right.hit(ray, t_min, closest_so_far));
+    right.or(left)
+  }
+
+  fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+    Some(self.bbox)
+  }
+
+  fn shadow_transmittance(&self, ray: &Ray, t_min: f64, t_max: f64, skip_transmissive: bool) -> Color {
+    let white = Color::new(1.0, 1.0, 1.0);
+    if !self.bbox.hit(ray, t_min, t_max) {
+      return white;
+    }
+    let left = self.left.shadow_transmittance(ray, t_min, t_max, skip_transmissive);
+    let right = self.right.as_ref()
+      .map_or(white, |right| right.shadow_transmittance(ray, t_min, t_max, skip_transmissive));
+    left * right
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::{
+    point3::Point3,
+    vec3::Vec3,
+  };
+  use crate::geometry::sphere::Sphere;
+  use crate::materials::DefaultMaterial;
+  use crate::utils::utils::random_double_in_range;
+
+  fn random_spheres() -> HittableList {
+    let mut list = HittableList::new();
+    for _ in 0..100 {
+      let center = Point3::new(random_double_in_range(-10.0, 10.0), random_double_in_range(-10.0, 10.0), random_double_in_range(-10.0, 10.0));
+      list.add(Sphere::new(center, random_double_in_range(0.1, 1.0), DefaultMaterial::new()));
+    }
+    list
+  }
+
+  #[test]
+  fn test_same_hits() {
+    // Same closest hits as going through the plain list
+    let list = random_spheres();
+    let mut centers = Vec::new();
+    for _ in 0..200 {
+      centers.push(Point3::new(random_double_in_range(-15.0, 15.0), random_double_in_range(-15.0, 15.0), -20.0));
+    }
+    let mut expected = Vec::new();
+    for origin in centers.iter() {
+      let r = Ray::new(*origin, Vec3::new(0.0, 0.0, 1.0));
+      expected.push(list.hit(&r, 0.001, f64::INFINITY).map(|record| record.t));
+    }
+
+    let bvh = BvhNode::new(list, 0.0, 1.0);
+    for (origin, expected) in centers.iter().zip(expected.iter()) {
+      let r = Ray::new(*origin, Vec3::new(0.0, 0.0, 1.0));
+      assert_eq!(bvh.hit(&r, 0.001, f64::INFINITY).map(|record| record.t), *expected);
+    }
+  }
+}
@@ -0,0 +1,143 @@
+use crate::core::{
+  PI,
+  point3::Point3,
+  polynomial::solve_quadratic,
+  ray::Ray,
+  transform::Transform,
+  vec3::Vec3,
+};
+use crate::materials::{Material};
+
+use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
+use super::local::{LocalHit, azimuth, radial, closest};
+
+/**
+ * Points within `radius` of the segment between two points: a cylinder with hemispherical ends.
+ * u goes around the axis, v along the profile from one pole to the other, proportionally to the length.
+ **/
+pub struct Capsule<M: Material> {
+  to_world: Transform,
+  radius: f64,
+  height: f64,
+  material: M,
+}
+
+impl<M: Material> Capsule<M> {
+  pub fn new(a: Point3, b: Point3, radius: f64, material: M) -> Self {
+    Capsule {
+      to_world: Transform::from_axis(a, b - a),
+      radius,
+      height: (b - a).length(),
+      material,
+    }
+  }
+
+  /**
+   * v and its derivative for a point of the surface, from the distance `s` along the profile
+   * and the unit tangent of the profile there.
+   **/
+  fn profile(&self, s: f64, tangent: Vec3) -> (f64, Vec3) {
+    let length = PI * self.radius + self.height;
+    (s / length, tangent * length)
+  }
+
+  /**
+   * Hits on one of the hemispheres, centered at height `y`, on the side of `sign`.
+   **/
+  fn cap_hits(&self, ray: &Ray, y: f64, sign: f64, hits: &mut Vec<LocalHit>) {
+    let r = self.radius;
+    let center = Point3::new(0.0, y, 0.0);
+    let oc = ray.origin - center;
+    let a = ray.direction.length_square();
+    let b = 2.0 * Vec3::dot(&oc, &ray.direction);
+    let c = oc.length_square() - r * r;
+    if let Some((t0, t1)) = solve_quadratic(a, b, c) {
+      for t in [t0, t1].iter() {
+        let p = ray.at(*t);
+        let normal = (p - center) / r;
+        if normal.y * sign < 0.0 {
+          continue;
+        }
+        let (u, dpdu) = azimuth(&p);
+        // Angle from the equator of the hemisphere
+        let angle = (normal.y * sign).min(1.0).asin();
+        let (s, tangent) = if sign < 0.0 {
+          (r * (PI / 2.0 - angle), radial(&p) * angle.sin() + Vec3::new(0.0, angle.cos(), 0.0))
+        } else {
+          (r * PI / 2.0 + self.height + r * angle, radial(&p) * -angle.sin() + Vec3::new(0.0, angle.cos(), 0.0))
+        };
+        let (v, dpdv) = self.profile(s, tangent);
+        hits.push(LocalHit { t: *t, normal, u, v, dpdu, dpdv });
+      }
+    }
+  }
+}
+
+impl<M: Material> Hittable for Capsule<M> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    let local = self.to_world.inverse().ray(ray);
+    let (o, d) = (local.origin, local.direction);
+    let r = self.radius;
+    let mut hits = Vec::with_capacity(6);
+
+    let a = d.x * d.x + d.z * d.z;
+    let b = 2.0 * (o.x * d.x + o.z * d.z);
+    let c = o.x * o.x + o.z * o.z - r * r;
+    if let Some((t0, t1)) = solve_quadratic(a, b, c) {
+      for t in [t0, t1].iter() {
+        let p = local.at(*t);
+        if p.y >= 0.0 && p.y <= self.height {
+          let (u, dpdu) = azimuth(&p);
+          let (v, dpdv) = self.profile(r * PI / 2.0 + p.y, Vec3::new(0.0, 1.0, 0.0));
+          hits.push(LocalHit { t: *t, normal: Vec3::new(p.x, 0.0, p.z), u, v, dpdu, dpdv });
+        }
+      }
+    }
+    self.cap_hits(&local, 0.0, -1.0, &mut hits);
+    self.cap_hits(&local, self.height, 1.0, &mut hits);
+
+    closest(hits, t_min, t_max).map(|hit| hit.into_record(ray, &self.to_world, &self.material))
+  }
+
+  fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+    let r = self.radius;
+    let local = Aabb::new(Point3::new(-r, -r, -r), Point3::new(r, self.height + r, r));
+    Some(local.transformed(&self.to_world))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::materials::DefaultMaterial;
+
+  #[test]
+  fn test_hit() {
+    let capsule = Capsule::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 2.0, 0.0), 0.5, DefaultMaterial::new());
+
+    // Top pole
+    let r = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let record = capsule.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 2.5).abs() < 1e-9);
+    assert!((record.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+    assert!((record.v - 1.0).abs() < 1e-9);
+    let record = capsule.hit(&r, 2.501, f64::INFINITY).unwrap();
+    assert!((record.t - 5.5).abs() < 1e-9 && record.v.abs() < 1e-9);
+
+    // Side
+    let r = Ray::new(Point3::new(-5.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    let record = capsule.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 4.5).abs() < 1e-9);
+    assert!((record.normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-9);
+
+    // Bottom hemisphere
+    let r = Ray::new(Point3::new(-5.0, -0.3, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    let record = capsule.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 4.6).abs() < 1e-9);
+    assert!((record.normal - Vec3::new(-0.8, -0.6, 0.0)).length() < 1e-9);
+
+    // The frame follows the outward normal
+    assert!(Vec3::dot(&Vec3::cross(&record.dpdu, &record.dpdv), &record.normal) > 0.0);
+  }
+}
@@ -0,0 +1,106 @@
+use crate::core::{
+  point3::Point3,
+  polynomial::solve_quadratic,
+  ray::Ray,
+  transform::Transform,
+  vec3::Vec3,
+};
+use crate::materials::{Material};
+
+use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
+use super::local::{LocalHit, azimuth, radial, disk_hit, closest};
+
+/**
+ * Closed cone from the center of its base disk to its apex.
+ * u goes around the axis, v from the base to the apex on the side and from the rim to the
+ * center on the base.
+ **/
+pub struct Cone<M: Material> {
+  to_world: Transform,
+  radius: f64,
+  height: f64,
+  material: M,
+}
+
+impl<M: Material> Cone<M> {
+  pub fn new(base: Point3, apex: Point3, radius: f64, material: M) -> Self {
+    Cone {
+      to_world: Transform::from_axis(base, apex - base),
+      radius,
+      height: (apex - base).length(),
+      material,
+    }
+  }
+}
+
+impl<M: Material> Hittable for Cone<M> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    let local = self.to_world.inverse().ray(ray);
+    let (o, d) = (local.origin, local.direction);
+    let (r, h) = (self.radius, self.height);
+    let mut hits = Vec::with_capacity(3);
+
+    // Side: x^2 + z^2 = (k (h - y))^2 for 0 <= y <= h, with k the slope r / h
+    let k2 = (r / h) * (r / h);
+    let a = d.x * d.x + d.z * d.z - k2 * d.y * d.y;
+    let b = 2.0 * (o.x * d.x + o.z * d.z) + 2.0 * k2 * (h - o.y) * d.y;
+    let c = o.x * o.x + o.z * o.z - k2 * (h - o.y) * (h - o.y);
+    if let Some((t0, t1)) = solve_quadratic(a, b, c) {
+      for t in [t0, t1].iter() {
+        let p = local.at(*t);
+        if p.y >= 0.0 && p.y <= h {
+          let (u, dpdu) = azimuth(&p);
+          let outward = radial(&p);
+          hits.push(LocalHit {
+            t: *t,
+            normal: Vec3::new(outward.x, r / h, outward.z),
+            u,
+            v: p.y / h,
+            dpdu,
+            dpdv: Vec3::new(0.0, h, 0.0) - outward * r,
+          });
+        }
+      }
+    }
+    hits.extend(disk_hit(&local, 0.0, r, false));
+
+    closest(hits, t_min, t_max).map(|hit| hit.into_record(ray, &self.to_world, &self.material))
+  }
+
+  fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+    let r = self.radius;
+    let local = Aabb::new(Point3::new(-r, 0.0, -r), Point3::new(r, self.height, r));
+    Some(local.transformed(&self.to_world))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::materials::DefaultMaterial;
+
+  #[test]
+  fn test_hit() {
+    // Standing on the ground, 45 degrees slope
+    let cone = Cone::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 1.0, 0.0), 1.0, DefaultMaterial::new());
+
+    // Halfway up, the radius is 0.5
+    let r = Ray::new(Point3::new(5.0, 0.5, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+    let record = cone.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 4.5).abs() < 1e-9);
+    assert!((record.normal - Vec3::new(1.0, 1.0, 0.0).unit()).length() < 1e-9);
+    assert!((record.v - 0.5).abs() < 1e-9);
+
+    // Above the apex
+    assert!(cone.hit(&Ray::new(Point3::new(5.0, 1.5, 0.0), Vec3::new(-1.0, 0.0, 0.0)), 0.001, f64::INFINITY).is_none());
+
+    // From below, through the base
+    let r = Ray::new(Point3::new(0.2, -1.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    let record = cone.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 1.0).abs() < 1e-9);
+    assert!((record.normal - Vec3::new(0.0, -1.0, 0.0)).length() < 1e-9);
+    let record = cone.hit(&r, 1.001, f64::INFINITY).unwrap();
+    assert!((record.t - 1.8).abs() < 1e-9 && !record.front_face);
+  }
+}
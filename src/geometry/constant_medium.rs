@@ -0,0 +1,115 @@
+use crate::core::{
+  color::Color,
+  point3::Point3,
+  ray::Ray,
+  vec3::Vec3,
+};
+use crate::materials::isotropic::Isotropic;
+use crate::utils::utils::random_double;
+
+use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
+
+/**
+ * Fog, smoke or mist of constant density filling a closed boundary (e.g. a Sphere).
+ * Rays passing through it scatter at exponentially distributed distances.
+ **/
+pub struct ConstantMedium<H: Hittable> {
+  boundary: H,
+  density: f64,
+  phase_function: Isotropic,
+}
+
+impl<H: Hittable> ConstantMedium<H> {
+  pub fn new(boundary: H, density: f64, albedo: Color) -> Self {
+    ConstantMedium {
+      boundary,
+      density,
+      phase_function: Isotropic::new(albedo),
+    }
+  }
+
+  /**
+   * The span of the ray, clipped to [t_min, t_max], that lies inside the boundary.
+   **/
+  fn inside(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+    let enter = self.boundary.hit(ray, -f64::INFINITY, f64::INFINITY)?;
+    let exit = self.boundary.hit(ray, enter.t + 0.0001, f64::INFINITY)?;
+
+    let t_enter = enter.t.max(t_min).max(0.0);
+    let t_exit = exit.t.min(t_max);
+    if t_enter >= t_exit {
+      None
+    } else {
+      Some((t_enter, t_exit))
+    }
+  }
+}
+
+impl<H: Hittable> Hittable for ConstantMedium<H> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    let (t_enter, t_exit) = self.inside(ray, t_min, t_max)?;
+
+    let ray_length = ray.direction.length();
+    let distance_inside = (t_exit - t_enter) * ray_length;
+    let hit_distance = -(1.0 - random_double()).ln() / self.density;
+    if hit_distance > distance_inside {
+      return None;
+    }
+
+    let t = t_enter + hit_distance / ray_length;
+    let point: Point3 = ray.at(t);
+    // The normal is meaningless inside a medium, the phase function ignores it
+    Some(HitRecord::new(point, Vec3::new(1.0, 0.0, 0.0), t, &self.phase_function))
+  }
+
+  fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+    self.boundary.bounding_box(time0, time1)
+  }
+
+  fn shadow_transmittance(&self, ray: &Ray, t_min: f64, t_max: f64, _skip_transmissive: bool) -> Color {
+    match self.inside(ray, t_min, t_max) {
+      Some((t_enter, t_exit)) => {
+        let transmittance = (-self.density * (t_exit - t_enter) * ray.direction.length()).exp();
+        Color::new(transmittance, transmittance, transmittance)
+      },
+      None => Color::new(1.0, 1.0, 1.0),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::geometry::sphere::Sphere;
+  use crate::materials::DefaultMaterial;
+
+  #[test]
+  fn test_scatter_inside_boundary() {
+    let fog = ConstantMedium::new(
+      Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, DefaultMaterial::new()),
+      1000.0,
+      Color::new(1.0, 1.0, 1.0),
+    );
+    let r = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    let record = fog.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!(record.t > 4.0 && record.t < 6.0);
+
+    // Missing the boundary never scatters
+    let r = Ray::new(Point3::new(-5.0, 2.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    assert!(fog.hit(&r, 0.001, f64::INFINITY).is_none());
+  }
+
+  #[test]
+  fn test_shadow_transmittance() {
+    let fog = ConstantMedium::new(
+      Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, DefaultMaterial::new()),
+      0.5,
+      Color::new(1.0, 1.0, 1.0),
+    );
+    // Starting at the center, a unit of fog to cross
+    let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    let t = (-0.5_f64).exp();
+    assert_eq!(fog.shadow_transmittance(&r, 0.0, f64::INFINITY, false), Color::new(t, t, t));
+  }
+}
@@ -0,0 +1,182 @@
+use crate::core::{
+  ray::Ray,
+};
+
+use super::hit::{HitRecord, Hittable, Interval};
+use super::aabb::Aabb;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operation {
+  Union,
+  Intersection,
+  // Inside the first object but not the second one
+  Difference,
+}
+
+impl Operation {
+  fn contains(&self, in_a: bool, in_b: bool) -> bool {
+    match self {
+      Operation::Union => in_a || in_b,
+      Operation::Intersection => in_a && in_b,
+      Operation::Difference => in_a && !in_b,
+    }
+  }
+}
+
+/**
+ * Constructive solid geometry: boolean combination of two closed objects, e.g. lenses as the
+ * intersection of two spheres or a hollow ball as the difference of two.
+ * The spans of both objects along the ray are merged, each surface keeping its own material.
+ * Nodes can be combined again, as they give their spans too.
+ * Refer to: https://en.wikipedia.org/wiki/Constructive_solid_geometry
+ **/
+pub struct Csg<A: Hittable, B: Hittable> {
+  a: A,
+  b: B,
+  operation: Operation,
+}
+
+impl<A: Hittable, B: Hittable> Csg<A, B> {
+  pub fn new(a: A, b: B, operation: Operation) -> Self {
+    Csg { a, b, operation }
+  }
+
+  pub fn union(a: A, b: B) -> Self {
+    Self::new(a, b, Operation::Union)
+  }
+
+  pub fn intersection(a: A, b: B) -> Self {
+    Self::new(a, b, Operation::Intersection)
+  }
+
+  pub fn difference(a: A, b: B) -> Self {
+    Self::new(a, b, Operation::Difference)
+  }
+}
+
+impl<A: Hittable, B: Hittable> Hittable for Csg<A, B> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    self.intervals(ray).into_iter()
+      .flat_map(|interval| vec![interval.enter, interval.exit])
+      .find(|record| record.t > t_min && record.t < t_max)
+  }
+
+  fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+    match self.operation {
+      Operation::Union => {
+        let a = self.a.bounding_box(time0, time1)?;
+        let b = self.b.bounding_box(time0, time1)?;
+        Some(Aabb::surrounding(&a, &b))
+      },
+      // The result lies inside the first object
+      Operation::Intersection | Operation::Difference => self.a.bounding_box(time0, time1),
+    }
+  }
+
+  /**
+   * Sweep along the ray through the boundaries of both objects, keeping the ones where being
+   * inside the result changes.
+   **/
+  fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>> {
+    let mut events: Vec<(HitRecord, bool)> = Vec::new();
+    for interval in self.a.intervals(ray) {
+      events.push((interval.enter, true));
+      events.push((interval.exit, true));
+    }
+    for interval in self.b.intervals(ray) {
+      events.push((interval.enter, false));
+      events.push((interval.exit, false));
+    }
+    events.sort_by(|(r1, _), (r2, _)| r1.t.partial_cmp(&r2.t).unwrap());
+
+    let mut intervals = Vec::new();
+    let (mut in_a, mut in_b, mut inside) = (false, false, false);
+    let mut enter = None;
+    for (mut record, from_a) in events {
+      if from_a {
+        in_a = record.front_face;
+      } else {
+        in_b = record.front_face;
+      }
+      let now_inside = self.operation.contains(in_a, in_b);
+      if now_inside == inside {
+        continue;
+      }
+      inside = now_inside;
+      if !from_a && self.operation == Operation::Difference {
+        // The surface of the removed object faces the other way: leaving it enters the result.
+        // The normal already faces the ray.
+        record.front_face = !record.front_face;
+      }
+      if inside {
+        enter = Some(record);
+      } else if let Some(enter) = enter.take() {
+        intervals.push(Interval { enter, exit: record });
+      }
+    }
+    intervals
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::{
+    point3::Point3,
+    vec3::Vec3,
+  };
+  use crate::geometry::sphere::Sphere;
+  use crate::materials::DefaultMaterial;
+
+  fn ball(x: f64, radius: f64) -> Sphere<DefaultMaterial> {
+    Sphere::new(Point3::new(x, 0.0, 0.0), radius, DefaultMaterial::new())
+  }
+
+  fn spans(object: &dyn Hittable, ray: &Ray) -> Vec<(f64, f64)> {
+    object.intervals(ray).iter()
+      .map(|interval| (interval.enter.t, interval.exit.t))
+      .collect()
+  }
+
+  fn assert_spans(actual: Vec<(f64, f64)>, expected: Vec<(f64, f64)>) {
+    assert_eq!(actual.len(), expected.len(), "{:?} != {:?}", actual, expected);
+    for (a, e) in actual.iter().zip(expected.iter()) {
+      assert!((a.0 - e.0).abs() < 1e-9 && (a.1 - e.1).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+    }
+  }
+
+  #[test]
+  fn test_operations() {
+    // Along the x axis from x = -10: [-1, 1] and [0, 2]
+    let r = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    assert_spans(spans(&Csg::union(ball(0.0, 1.0), ball(1.0, 1.0)), &r), vec![(9.0, 12.0)]);
+    assert_spans(spans(&Csg::intersection(ball(0.0, 1.0), ball(1.0, 1.0)), &r), vec![(10.0, 11.0)]);
+    assert_spans(spans(&Csg::difference(ball(0.0, 1.0), ball(1.0, 1.0)), &r), vec![(9.0, 10.0)]);
+    assert_spans(spans(&Csg::difference(ball(0.0, 1.0), ball(5.0, 1.0)), &r), vec![(9.0, 11.0)]);
+    assert!(spans(&Csg::intersection(ball(0.0, 1.0), ball(5.0, 1.0)), &r).is_empty());
+  }
+
+  #[test]
+  fn test_hollow_ball() {
+    let shell = Csg::difference(ball(0.0, 1.0), ball(0.0, 0.9));
+    let r = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    assert_spans(spans(&shell, &r), vec![(9.0, 9.1), (10.9, 11.0)]);
+
+    // Hits go in and out of the shell, the inner surface facing the hollow
+    let expected = [(9.0, true), (9.1, false), (10.9, true), (11.0, false)];
+    let mut t = 0.0;
+    for (t_expected, front_face) in expected.iter() {
+      let record = shell.hit(&r, t, f64::INFINITY).unwrap();
+      assert!((record.t - t_expected).abs() < 1e-9);
+      assert_eq!(record.front_face, *front_face);
+      assert!(Vec3::dot(&record.normal, &r.direction) < 0.0);
+      t = record.t;
+    }
+    assert!(shell.hit(&r, t, f64::INFINITY).is_none());
+
+    // From inside the hollow
+    let inside = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    let record = shell.hit(&inside, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 0.9).abs() < 1e-9 && record.front_face);
+  }
+}
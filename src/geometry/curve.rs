@@ -0,0 +1,264 @@
+use crate::core::{
+  point3::Point3,
+  ray::Ray,
+  vec3::Vec3,
+};
+use crate::materials::{Material};
+use crate::utils::utils::clamp;
+
+use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
+
+// Deepest subdivision of a curve, 1024 segments
+const MAX_DEPTH: i32 = 10;
+
+/**
+ * How the width of a curve is seen: a flat ribbon always facing the ray (grass blades, or hair
+ * too thin to tell) or a round tube (thick hair, wires).
+ **/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CurveKind {
+  Ribbon,
+  Cylinder,
+}
+
+/**
+ * Cubic Bezier curve whose width goes linearly from `width[0]` to `width[1]`.
+ * u goes along the curve, v across it. `dpdu` is the tangent, as hair materials need.
+ *
+ * The curve is intersected in a frame where the ray is the z axis, by splitting it in halves
+ * (de Casteljau) until the pieces are straight enough, skipping those whose padded control
+ * points don't surround the ray.
+ * Refer to: https://www.pbr-book.org/3ed-2018/Shapes/Curves
+ **/
+pub struct Curve<M: Material> {
+  control_points: [Point3; 4],
+  width: [f64; 2],
+  kind: CurveKind,
+  material: M,
+}
+
+/**
+ * Hit on a piece of the curve, in the frame of the ray.
+ **/
+struct SegmentHit {
+  z: f64,
+  u: f64,
+  // Signed distance from the center line over the half width
+  offset: f64,
+}
+
+impl<M: Material> Curve<M> {
+  pub fn new(control_points: [Point3; 4], width0: f64, width1: f64, kind: CurveKind, material: M) -> Self {
+    Curve {
+      control_points,
+      width: [width0, width1],
+      kind,
+      material,
+    }
+  }
+
+  fn width_at(&self, u: f64) -> f64 {
+    self.width[0] + (self.width[1] - self.width[0]) * u
+  }
+
+  fn subdivide(&self, cp: &[Point3; 4], u0: f64, u1: f64, depth: i32, z_min: f64, z_max: f64) -> Option<SegmentHit> {
+    // The piece lies within its control points, padded by its half width
+    let half = 0.5 * self.width_at(u0).max(self.width_at(u1));
+    let bounds = Aabb::from_points(cp);
+    if bounds.min.x - half > 0.0 || bounds.max.x + half < 0.0
+      || bounds.min.y - half > 0.0 || bounds.max.y + half < 0.0
+      || bounds.min.z - half > z_max || bounds.max.z + half < z_min {
+      return None;
+    }
+
+    if depth > 0 {
+      let (first_half, second_half) = split(cp);
+      let u_mid = 0.5 * (u0 + u1);
+      let first = self.subdivide(&first_half, u0, u_mid, depth - 1, z_min, z_max);
+      let z_max = first.as_ref().map_or(z_max, |hit| hit.z);
+      return self.subdivide(&second_half, u_mid, u1, depth - 1, z_min, z_max).or(first);
+    }
+
+    // Straight enough: the ray must pass between the perpendiculars at both ends
+    let start_edge = (cp[1].y - cp[0].y) * -cp[0].y + cp[0].x * (cp[0].x - cp[1].x);
+    let end_edge = (cp[2].y - cp[3].y) * -cp[3].y + cp[3].x * (cp[3].x - cp[2].x);
+    if start_edge < 0.0 || end_edge < 0.0 {
+      return None;
+    }
+    let (sx, sy) = (cp[3].x - cp[0].x, cp[3].y - cp[0].y);
+    let length_square = sx * sx + sy * sy;
+    if length_square == 0.0 {
+      return None;
+    }
+    let w = clamp(-(cp[0].x * sx + cp[0].y * sy) / length_square, 0.0, 1.0);
+    let u = u0 + (u1 - u0) * w;
+    let radius = 0.5 * self.width_at(u);
+    let p = bezier(cp, w);
+    let distance_square = p.x * p.x + p.y * p.y;
+    if distance_square > radius * radius {
+      return None;
+    }
+
+    let z = match self.kind {
+      CurveKind::Ribbon => p.z,
+      // Front of the tube around the center line
+      CurveKind::Cylinder => p.z - (radius * radius - distance_square).sqrt(),
+    };
+    if z <= z_min || z >= z_max {
+      return None;
+    }
+    let tangent = bezier_derivative(cp, w);
+    let side = if tangent.x * p.y - tangent.y * p.x > 0.0 { 1.0 } else { -1.0 };
+    Some(SegmentHit { z, u, offset: side * distance_square.sqrt() / radius })
+  }
+}
+
+fn bezier(cp: &[Point3; 4], u: f64) -> Point3 {
+  let v = 1.0 - u;
+  v * v * v * cp[0] + 3.0 * v * v * u * cp[1] + 3.0 * v * u * u * cp[2] + u * u * u * cp[3]
+}
+
+fn bezier_derivative(cp: &[Point3; 4], u: f64) -> Vec3 {
+  let v = 1.0 - u;
+  3.0 * (v * v * (cp[1] - cp[0]) + 2.0 * v * u * (cp[2] - cp[1]) + u * u * (cp[3] - cp[2]))
+}
+
+/**
+ * The two halves of a Bezier curve, by de Casteljau's algorithm.
+ **/
+fn split(cp: &[Point3; 4]) -> ([Point3; 4], [Point3; 4]) {
+  let mid = |a: Point3, b: Point3| 0.5 * (a + b);
+  let (p01, p12, p23) = (mid(cp[0], cp[1]), mid(cp[1], cp[2]), mid(cp[2], cp[3]));
+  let (p012, p123) = (mid(p01, p12), mid(p12, p23));
+  let center = mid(p012, p123);
+  ([cp[0], p01, p012, center], [center, p123, p23, cp[3]])
+}
+
+impl<M: Material> Hittable for Curve<M> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    // Frame where the ray starts at the origin along z, z being the distance along the ray
+    let speed = ray.direction.length();
+    let w = ray.direction / speed;
+    let helper = if w.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    let x_axis = Vec3::cross(&helper, &w).unit();
+    let y_axis = Vec3::cross(&w, &x_axis);
+    let to_ray = |p: Point3| {
+      let d = p - ray.origin;
+      Point3::new(Vec3::dot(&d, &x_axis), Vec3::dot(&d, &y_axis), Vec3::dot(&d, &w))
+    };
+    let cp = [
+      to_ray(self.control_points[0]),
+      to_ray(self.control_points[1]),
+      to_ray(self.control_points[2]),
+      to_ray(self.control_points[3]),
+    ];
+
+    // Split until the pieces deviate from straight lines by less than a 20th of the width
+    let mut bend: f64 = 0.0;
+    for i in 0..2 {
+      let d = cp[i] - 2.0 * cp[i + 1] + cp[i + 2];
+      bend = bend.max((d.x * d.x + d.y * d.y).sqrt());
+    }
+    let epsilon = self.width[0].max(self.width[1]) / 20.0;
+    let depth = if bend > 0.0 && epsilon > 0.0 {
+      let levels = (std::f64::consts::SQRT_2 * 6.0 * bend / (8.0 * epsilon)).log2() / 2.0;
+      clamp(levels.round(), 0.0, MAX_DEPTH as f64) as i32
+    } else {
+      0
+    };
+
+    let hit = self.subdivide(&cp, 0.0, 1.0, depth, t_min * speed, t_max * speed)?;
+    let t = hit.z / speed;
+    let point = ray.at(t);
+    let tangent = bezier_derivative(&self.control_points, hit.u);
+    let axis = tangent.unit();
+    let across = |v: Vec3| v - Vec3::dot(&v, &axis) * axis;
+    let outward_normal = match self.kind {
+      CurveKind::Ribbon => across(-w),
+      CurveKind::Cylinder => across(point - bezier(&self.control_points, hit.u)),
+    };
+    let outward_normal = if outward_normal.length_square() > 0.0 { outward_normal.unit() } else { -w };
+
+    let mut record = HitRecord::new(point, outward_normal, t, &self.material);
+    record.set_face_normal(ray, outward_normal);
+    record.u = hit.u;
+    record.v = 0.5 + 0.5 * hit.offset;
+    record.dpdu = tangent;
+    record.dpdv = Vec3::cross(&outward_normal, &axis) * self.width_at(hit.u);
+    Some(record)
+  }
+
+  fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+    let aabb = Aabb::from_points(&self.control_points);
+    let half = 0.5 * self.width[0].max(self.width[1]);
+    let padding = Vec3::new(half, half, half);
+    Some(Aabb::new(aabb.min - padding, aabb.max + padding))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::materials::DefaultMaterial;
+
+  fn straight(kind: CurveKind) -> Curve<DefaultMaterial> {
+    let cp = [
+      Point3::new(0.0, 0.0, 0.0),
+      Point3::new(1.0 / 3.0, 0.0, 0.0),
+      Point3::new(2.0 / 3.0, 0.0, 0.0),
+      Point3::new(1.0, 0.0, 0.0),
+    ];
+    Curve::new(cp, 0.2, 0.0, kind, DefaultMaterial::new())
+  }
+
+  #[test]
+  fn test_hit() {
+    let r = Ray::new(Point3::new(0.5, 1.0, 0.03), Vec3::new(0.0, -1.0, 0.0));
+    let (ribbon, tube) = (straight(CurveKind::Ribbon), straight(CurveKind::Cylinder));
+    let record = ribbon.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 1.0).abs() < 1e-9);
+    assert!((record.u - 0.5).abs() < 1e-9);
+    assert!((record.v - 0.5).abs() > 0.1);
+    assert!(record.front_face);
+    assert!((record.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+    assert!((record.dpdu.unit() - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+
+    // The tube bulges towards the ray, its normal leans away from the center line
+    let record = tube.hit(&r, 0.001, f64::INFINITY).unwrap();
+    let expected = (0.05_f64 * 0.05 - 0.03 * 0.03).sqrt();
+    assert!((record.t - (1.0 - expected)).abs() < 1e-9);
+    assert!((record.normal - Vec3::new(0.0, expected, 0.03).unit()).length() < 1e-9);
+
+    // Narrower than the offset near its thin end, and beyond its ends
+    let r = Ray::new(Point3::new(0.9, 1.0, 0.03), Vec3::new(0.0, -1.0, 0.0));
+    assert!(ribbon.hit(&r, 0.001, f64::INFINITY).is_none());
+    let r = Ray::new(Point3::new(-0.05, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    assert!(ribbon.hit(&r, 0.001, f64::INFINITY).is_none());
+  }
+
+  #[test]
+  fn test_bent_curve() {
+    // An arch in the xy plane, hit from the front on its top
+    let cp = [
+      Point3::new(-1.0, 0.0, 0.0),
+      Point3::new(-1.0, 1.0, 0.0),
+      Point3::new(1.0, 1.0, 0.0),
+      Point3::new(1.0, 0.0, 0.0),
+    ];
+    let curve = Curve::new(cp, 0.05, 0.05, CurveKind::Ribbon, DefaultMaterial::new());
+    let r = Ray::new(Point3::new(0.0, 0.75, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let record = curve.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 5.0).abs() < 1e-9);
+    assert!((record.u - 0.5).abs() < 1e-3);
+    // Under the arch
+    let r = Ray::new(Point3::new(0.0, 0.5, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(curve.hit(&r, 0.001, f64::INFINITY).is_none());
+    // On a leg, at the height where the curve is
+    let u: f64 = 0.2;
+    let p = bezier(&cp, u);
+    let r = Ray::new(Point3::new(p.x + 0.02, p.y, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let record = curve.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.u - u).abs() < 0.02);
+  }
+}
@@ -0,0 +1,110 @@
+use crate::core::{
+  point3::Point3,
+  polynomial::solve_quadratic,
+  ray::Ray,
+  transform::Transform,
+  vec3::Vec3,
+};
+use crate::materials::{Material};
+
+use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
+use super::local::{LocalHit, azimuth, disk_hit, closest};
+
+/**
+ * Closed cylinder between the centers of its two caps.
+ * u goes around the axis, v along it on the side and from the rim to the center on the caps.
+ **/
+pub struct Cylinder<M: Material> {
+  to_world: Transform,
+  radius: f64,
+  height: f64,
+  material: M,
+}
+
+impl<M: Material> Cylinder<M> {
+  pub fn new(base: Point3, top: Point3, radius: f64, material: M) -> Self {
+    Cylinder {
+      to_world: Transform::from_axis(base, top - base),
+      radius,
+      height: (top - base).length(),
+      material,
+    }
+  }
+}
+
+impl<M: Material> Hittable for Cylinder<M> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    let local = self.to_world.inverse().ray(ray);
+    let (o, d) = (local.origin, local.direction);
+    let mut hits = Vec::with_capacity(4);
+
+    // Side: x^2 + z^2 = r^2 for 0 <= y <= h
+    let a = d.x * d.x + d.z * d.z;
+    let b = 2.0 * (o.x * d.x + o.z * d.z);
+    let c = o.x * o.x + o.z * o.z - self.radius * self.radius;
+    if let Some((t0, t1)) = solve_quadratic(a, b, c) {
+      for t in [t0, t1].iter() {
+        let p = local.at(*t);
+        if p.y >= 0.0 && p.y <= self.height {
+          let (u, dpdu) = azimuth(&p);
+          hits.push(LocalHit {
+            t: *t,
+            normal: Vec3::new(p.x, 0.0, p.z),
+            u,
+            v: p.y / self.height,
+            dpdu,
+            dpdv: Vec3::new(0.0, self.height, 0.0),
+          });
+        }
+      }
+    }
+    hits.extend(disk_hit(&local, 0.0, self.radius, false));
+    hits.extend(disk_hit(&local, self.height, self.radius, true));
+
+    closest(hits, t_min, t_max).map(|hit| hit.into_record(ray, &self.to_world, &self.material))
+  }
+
+  fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+    let r = self.radius;
+    let local = Aabb::new(Point3::new(-r, 0.0, -r), Point3::new(r, self.height, r));
+    Some(local.transformed(&self.to_world))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::materials::DefaultMaterial;
+
+  #[test]
+  fn test_hit() {
+    // Along the x axis, from x = 0 to x = 4
+    let cylinder = Cylinder::new(Point3::new(0.0, 0.0, 0.0), Point3::new(4.0, 0.0, 0.0), 1.0, DefaultMaterial::new());
+
+    // Through the side
+    let r = Ray::new(Point3::new(1.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let record = cylinder.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 4.0).abs() < 1e-9);
+    assert!((record.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+    assert!((record.v - 0.25).abs() < 1e-9);
+
+    // Through the cap
+    let r = Ray::new(Point3::new(10.0, 0.5, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+    let record = cylinder.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 6.0).abs() < 1e-9);
+    assert!((record.normal - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+    assert!(record.front_face);
+
+    // From inside, out through the other cap
+    let record = cylinder.hit(&r, 6.001, f64::INFINITY).unwrap();
+    assert!((record.t - 10.0).abs() < 1e-9 && !record.front_face);
+
+    // Passing beside it
+    assert!(cylinder.hit(&Ray::new(Point3::new(5.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0)), 0.001, f64::INFINITY).is_none());
+
+    let aabb = cylinder.bounding_box(0.0, 1.0).unwrap();
+    assert!((aabb.min - Point3::new(0.0, -1.0, -1.0)).length() < 1e-9);
+    assert!((aabb.max - Point3::new(4.0, 1.0, 1.0)).length() < 1e-9);
+  }
+}
@@ -0,0 +1,367 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::core::{
+  image::GrayImage,
+  point3::Point3,
+  ray::Ray,
+  vec3::Vec3,
+  transform::Transform,
+};
+use crate::materials::{Material};
+
+use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
+
+/**
+ * Grid of heights sampled over the unit square [0, 1]^2 of the xz plane, x varies fastest in `values`.
+ * Images map their width to x and their height to z, from the top row.
+ **/
+#[derive(Clone, Debug)]
+pub struct HeightMap {
+  nx: usize,
+  nz: usize,
+  values: Vec<f64>,
+}
+
+impl HeightMap {
+  pub fn new(nx: usize, nz: usize, values: Vec<f64>) -> Result<Self> {
+    if nx < 2 || nz < 2 || values.len() != nx * nz {
+      return Err(Error::new(
+        ErrorKind::InvalidData,
+        format!("expected at least 2x2 heights, got {} for {}x{}", values.len(), nx, nz),
+      ));
+    }
+    if values.iter().any(|h| !h.is_finite()) {
+      return Err(Error::new(ErrorKind::InvalidData, "heights must be finite"));
+    }
+    Ok(Self { nx, nz, values })
+  }
+
+  /**
+   * Fill the map by evaluating `height` at the grid points (x, z), which must be at least 2x2.
+   **/
+  pub fn from_fn<F: Fn(f64, f64) -> f64>(nx: usize, nz: usize, height: F) -> Result<Self> {
+    if nx < 2 || nz < 2 {
+      return Self::new(nx, nz, Vec::new());
+    }
+    let mut values = Vec::with_capacity(nx * nz);
+    for j in 0..nz {
+      for i in 0..nx {
+        values.push(height(i as f64 / (nx - 1) as f64, j as f64 / (nz - 1) as f64));
+      }
+    }
+    Self::new(nx, nz, values)
+  }
+
+  /**
+   * Heights from the gray levels of an image, x along its width and z along its height.
+   **/
+  pub fn from_image(image: &GrayImage) -> Result<Self> {
+    Self::new(image.width(), image.height(), image.values().to_vec())
+  }
+
+  /**
+   * Load a `.pgm` or `.png` image (see `GrayImage::load`).
+   **/
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+    Self::from_image(&GrayImage::load(path)?)
+  }
+
+  pub fn size(&self) -> (usize, usize) {
+    (self.nx, self.nz)
+  }
+
+  pub fn height(&self, i: usize, j: usize) -> f64 {
+    self.values[j * self.nx + i]
+  }
+
+  /**
+   * Lowest and highest heights.
+   **/
+  pub fn range(&self) -> (f64, f64) {
+    self.values.iter().fold((f64::INFINITY, -f64::INFINITY), |(lo, hi), h| (lo.min(*h), hi.max(*h)))
+  }
+}
+
+/**
+ * Terrain from a height map: the grid points are joined by two triangles per cell, shaded with
+ * normals interpolated from the grid (central differences) so the cells don't show.
+ * `to_world` places the unit square of the map, heights being along y, in the scene.
+ *
+ * Rays walk the cells they cross with a 2D DDA and only test the triangles of those cells,
+ * skipping the ones they pass over or under.
+ * Refer to: http://www.cse.yorku.ca/~amana/research/grid.pdf
+ **/
+pub struct Heightfield<M: Material> {
+  map: HeightMap,
+  to_world: Transform,
+  to_local: Transform,
+  // Per grid point, in the frame of the map
+  normals: Vec<Vec3>,
+  min_height: f64,
+  max_height: f64,
+  material: M,
+}
+
+impl<M: Material> Heightfield<M> {
+  pub fn new(map: HeightMap, to_world: Transform, material: M) -> Self {
+    // Height maps are checked to be at least 2x2 when made
+    let (nx, nz) = (map.nx, map.nz);
+    let (dx, dz) = (1.0 / (nx - 1) as f64, 1.0 / (nz - 1) as f64);
+    let mut normals = Vec::with_capacity(nx * nz);
+    for j in 0..nz {
+      for i in 0..nx {
+        let (i0, i1) = (i.saturating_sub(1), (i + 1).min(nx - 1));
+        let (j0, j1) = (j.saturating_sub(1), (j + 1).min(nz - 1));
+        let slope_x = (map.height(i1, j) - map.height(i0, j)) / ((i1 - i0) as f64 * dx);
+        let slope_z = (map.height(i, j1) - map.height(i, j0)) / ((j1 - j0) as f64 * dz);
+        normals.push(Vec3::new(-slope_x, 1.0, -slope_z));
+      }
+    }
+    // Padded so flat maps don't give flat boxes
+    let (min_height, max_height) = map.range();
+    Heightfield {
+      map,
+      to_local: to_world.inverse(),
+      to_world,
+      normals,
+      min_height: min_height - 1e-4,
+      max_height: max_height + 1e-4,
+      material,
+    }
+  }
+
+  fn vertex(&self, i: usize, j: usize) -> Point3 {
+    Point3::new(
+      i as f64 / (self.map.nx - 1) as f64,
+      self.map.height(i, j),
+      j as f64 / (self.map.nz - 1) as f64,
+    )
+  }
+
+  /**
+   * Where the ray, in the frame of the map, overlaps its bounds within [t_min, t_max].
+   **/
+  fn span(&self, local: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+    let origin = [local.origin.x, local.origin.y, local.origin.z];
+    let direction = [local.direction.x, local.direction.y, local.direction.z];
+    let bounds = [(0.0, 1.0), (self.min_height, self.max_height), (0.0, 1.0)];
+    let (mut t0, mut t1) = (t_min, t_max);
+    for axis in 0..3 {
+      let inv_d = 1.0 / direction[axis];
+      let mut t_near = (bounds[axis].0 - origin[axis]) * inv_d;
+      let mut t_far = (bounds[axis].1 - origin[axis]) * inv_d;
+      if inv_d < 0.0 {
+        std::mem::swap(&mut t_near, &mut t_far);
+      }
+      t0 = t0.max(t_near);
+      t1 = t1.min(t_far);
+      if t1 < t0 {
+        return None;
+      }
+    }
+    Some((t0, t1))
+  }
+
+  /**
+   * Closest hit with the two triangles of cell (i, j), along with the shading normal and the
+   * derivatives of the point along x and z.
+   **/
+  fn cell_hit(&self, local: &Ray, i: usize, j: usize, t_min: f64, t_max: f64) -> Option<(f64, Vec3, Vec3, Vec3)> {
+    let normal = |i: usize, j: usize| self.normals[j * self.map.nx + i];
+    // Both triangles wind so their normal faces up
+    let triangles = [
+      [(i, j), (i, j + 1), (i + 1, j)],
+      [(i + 1, j + 1), (i + 1, j), (i, j + 1)],
+    ];
+    let mut closest: Option<(f64, Vec3, Vec3, Vec3)> = None;
+    for corners in triangles.iter() {
+      let [a, b, c] = *corners;
+      let (p0, p1, p2) = (self.vertex(a.0, a.1), self.vertex(b.0, b.1), self.vertex(c.0, c.1));
+      let t_limit = closest.map_or(t_max, |hit| hit.0);
+      if let Some((t, u, v)) = triangle_hit(local, p0, p1, p2, t_min, t_limit) {
+        let shading = (1.0 - u - v) * normal(a.0, a.1) + u * normal(b.0, b.1) + v * normal(c.0, c.1);
+        // Edges of the triangle along z and x, scaled to unit steps
+        let (along_z, along_x) = if a.0 == i { (p1 - p0, p2 - p0) } else { (p0 - p1, p0 - p2) };
+        let dz = (along_z.z).abs();
+        let dx = (along_x.x).abs();
+        closest = Some((t, shading, along_x / dx, along_z / dz));
+      }
+    }
+    closest
+  }
+}
+
+/**
+ * Moller-Trumbore intersection, returning t and the barycentric coordinates of p1 and p2.
+ **/
+fn triangle_hit(ray: &Ray, p0: Point3, p1: Point3, p2: Point3, t_min: f64, t_max: f64) -> Option<(f64, f64, f64)> {
+  let edge1 = p1 - p0;
+  let edge2 = p2 - p0;
+  let p = Vec3::cross(&ray.direction, &edge2);
+  let det = Vec3::dot(&edge1, &p);
+  if det.abs() < 1e-12 {
+    return None;
+  }
+  let inv_det = 1.0 / det;
+  let s = ray.origin - p0;
+  let u = Vec3::dot(&s, &p) * inv_det;
+  if !(0.0..=1.0).contains(&u) {
+    return None;
+  }
+  let q = Vec3::cross(&s, &edge1);
+  let v = Vec3::dot(&ray.direction, &q) * inv_det;
+  if v < 0.0 || u + v > 1.0 {
+    return None;
+  }
+  let t = Vec3::dot(&edge2, &q) * inv_det;
+  if t <= t_min || t >= t_max {
+    return None;
+  }
+  Some((t, u, v))
+}
+
+impl<M: Material> Hittable for Heightfield<M> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    let local = self.to_local.ray(ray);
+    let (t0, t1) = self.span(&local, t_min, t_max)?;
+
+    let (o, d) = (local.origin, local.direction);
+    let cells = [(self.map.nx - 1) as isize, (self.map.nz - 1) as isize];
+    let start = local.at(t0);
+    let mut cell = [0, 0];
+    let mut step = [0, 0];
+    let mut t_next = [f64::INFINITY, f64::INFINITY];
+    let mut t_delta = [f64::INFINITY, f64::INFINITY];
+    for (axis, (p, o, d)) in [(start.x, o.x, d.x), (start.z, o.z, d.z)].iter().enumerate() {
+      let size = 1.0 / cells[axis] as f64;
+      cell[axis] = ((p / size).floor() as isize).max(0).min(cells[axis] - 1);
+      if *d > 0.0 {
+        step[axis] = 1;
+        t_next[axis] = ((cell[axis] + 1) as f64 * size - o) / d;
+        t_delta[axis] = size / d;
+      } else if *d < 0.0 {
+        step[axis] = -1;
+        t_next[axis] = (cell[axis] as f64 * size - o) / d;
+        t_delta[axis] = -size / d;
+      }
+    }
+
+    let mut t_enter = t0;
+    loop {
+      let t_exit = t_next[0].min(t_next[1]).min(t1);
+      let (i, j) = (cell[0] as usize, cell[1] as usize);
+      // Skip the cell if the ray stays above or below all its corners
+      let corners = [self.map.height(i, j), self.map.height(i + 1, j), self.map.height(i, j + 1), self.map.height(i + 1, j + 1)];
+      let low = corners.iter().cloned().fold(f64::INFINITY, f64::min);
+      let high = corners.iter().cloned().fold(-f64::INFINITY, f64::max);
+      let (y_enter, y_exit) = (o.y + t_enter * d.y, o.y + t_exit * d.y);
+      if y_enter.min(y_exit) <= high && y_enter.max(y_exit) >= low {
+        if let Some((t, shading, dpdx, dpdz)) = self.cell_hit(&local, i, j, t_min, t_max) {
+          let p = local.at(t);
+          let geometric = self.to_world.normal(Vec3::cross(&dpdz, &dpdx)).unit();
+          let mut record = HitRecord::new(ray.at(t), geometric, t, &self.material);
+          record.set_face_normal(ray, geometric);
+          let shading = self.to_world.normal(shading).unit();
+          record.normal = if record.front_face { shading } else { shading.get_reversed() };
+          record.u = p.x;
+          record.v = p.z;
+          record.dpdu = self.to_world.vector(dpdx);
+          record.dpdv = self.to_world.vector(dpdz);
+          return Some(record);
+        }
+      }
+      if t_exit >= t1 {
+        return None;
+      }
+
+      let axis = if t_next[0] < t_next[1] { 0 } else { 1 };
+      cell[axis] += step[axis];
+      if cell[axis] < 0 || cell[axis] >= cells[axis] {
+        return None;
+      }
+      t_enter = t_next[axis];
+      t_next[axis] += t_delta[axis];
+    }
+  }
+
+  fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+    let local = Aabb::new(Point3::new(0.0, self.min_height, 0.0), Point3::new(1.0, self.max_height, 1.0));
+    Some(local.transformed(&self.to_world))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::materials::DefaultMaterial;
+  use crate::geometry::triangle::Triangle;
+  use crate::geometry::hit::HittableList;
+  use crate::utils::utils::random_double;
+
+  #[test]
+  fn test_from_image() {
+    let image = GrayImage::parse_pgm(b"P2\n# ramp\n3 2\n4\n0 1 2\n2 3 4\n").unwrap();
+    let map = HeightMap::from_image(&image).unwrap();
+    assert_eq!(map.size(), (3, 2));
+    assert_eq!(map.height(1, 0), 0.25);
+    assert_eq!(map.range(), (0.0, 1.0));
+    // A single row has no cells
+    assert!(HeightMap::from_image(&GrayImage::parse_pgm(b"P2 2 1 4 0 4").unwrap()).is_err());
+  }
+
+  #[test]
+  fn test_hit() {
+    // A ramp going up along x, stretched to 2x1x2
+    let map = HeightMap::from_fn(5, 5, |x, _| x).unwrap();
+    // A single row or column has no cells, and no size at all nothing to divide
+    assert!(HeightMap::from_fn(1, 5, |x, _| x).is_err());
+    assert!(HeightMap::from_fn(0, 0, |x, _| x).is_err());
+    assert!(HeightMap::from_fn(3, 3, |_, _| f64::NAN).is_err());
+    let field = Heightfield::new(map, Transform::scale(Vec3::new(2.0, 1.0, 2.0)), DefaultMaterial::new());
+    let r = Ray::new(Point3::new(1.0, 2.0, 0.6), Vec3::new(0.0, -1.0, 0.0));
+    let record = field.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 1.5).abs() < 1e-9);
+    assert!(record.front_face);
+    assert!((record.normal - Vec3::new(-1.0, 2.0, 0.0).unit()).length() < 1e-9);
+    assert!((record.u - 0.5).abs() < 1e-9 && (record.v - 0.3).abs() < 1e-9);
+    // From below
+    let r = Ray::new(Point3::new(1.0, -2.0, 0.6), Vec3::new(0.0, 1.0, 0.0));
+    assert!(!field.hit(&r, 0.001, f64::INFINITY).unwrap().front_face);
+    // Beside it
+    let r = Ray::new(Point3::new(2.5, 2.0, 0.6), Vec3::new(0.0, -1.0, 0.0));
+    assert!(field.hit(&r, 0.001, f64::INFINITY).is_none());
+  }
+
+  #[test]
+  fn test_matches_triangles() {
+    // The DDA finds the same hits as testing every triangle of a bumpy map
+    let n = 9;
+    let map = HeightMap::from_fn(n, n, |x, z| 0.3 * (9.0 * x).sin() * (7.0 * z).cos()).unwrap();
+    let field = Heightfield::new(map.clone(), Transform::translate(Vec3::new(-0.5, 0.0, -0.5)), DefaultMaterial::new());
+    let mut triangles = HittableList::new();
+    for j in 0..n - 1 {
+      for i in 0..n - 1 {
+        let vertex = |i: usize, j: usize| Point3::new(
+          i as f64 / (n - 1) as f64 - 0.5, map.height(i, j), j as f64 / (n - 1) as f64 - 0.5,
+        );
+        triangles.add(Triangle::new(vertex(i, j), vertex(i, j + 1), vertex(i + 1, j), DefaultMaterial::new()));
+        triangles.add(Triangle::new(vertex(i + 1, j + 1), vertex(i + 1, j), vertex(i, j + 1), DefaultMaterial::new()));
+      }
+    }
+    for _ in 0..500 {
+      let origin = Point3::new(random_double() * 3.0 - 1.5, random_double() + 0.2, random_double() * 3.0 - 1.5);
+      let target = Point3::new(random_double() - 0.5, random_double() * 0.6 - 0.3, random_double() - 0.5);
+      let r = Ray::new(origin, target - origin);
+      match (field.hit(&r, 0.001, f64::INFINITY), triangles.hit(&r, 0.001, f64::INFINITY)) {
+        (Some(a), Some(b)) => {
+          assert!((a.t - b.t).abs() < 1e-9);
+          assert_eq!(a.front_face, b.front_face);
+        },
+        (None, None) => {},
+        _ => panic!("heightfield and triangles disagree"),
+      }
+    }
+  }
+}
@@ -1,19 +1,29 @@
 use std::fmt::{Debug, Formatter};
 
 use crate::core::{
+  color::Color,
   point3::Point3,
   ray::Ray,
   vec3::Vec3,
 };
 use crate::materials::{Material};
+use super::aabb::Aabb;
 
-// #[derive(Clone, Copy)]
+#[derive(Clone, Copy)]
 pub struct HitRecord<'a> {
   pub point: Point3,
   pub normal: Vec3,
   pub t: f64,
   pub front_face: bool,
   pub material: &'a dyn Material,
+  // Surface coordinates of the hit point, for textures
+  pub u: f64,
+  pub v: f64,
+  // Tangent frame: derivatives of the point along u and v, zero when the surface has no parameterization
+  pub dpdu: Vec3,
+  pub dpdv: Vec3,
+  // Throughput picked up while finding the hit (e.g. by volume tracking), white for plain surfaces
+  pub weight: Color,
 }
 
 impl<'m> HitRecord<'m> {
@@ -24,6 +34,11 @@ impl<'m> HitRecord<'m> {
       t,
       front_face: true,
       material,
+      u: 0.0,
+      v: 0.0,
+      dpdu: Vec3::new(0.0, 0.0, 0.0),
+      dpdv: Vec3::new(0.0, 0.0, 0.0),
+      weight: Color::new(1.0, 1.0, 1.0),
     }
   }
 
@@ -31,10 +46,76 @@ impl<'m> HitRecord<'m> {
     self.front_face = Vec3::dot(&r.direction, &outward_normal) < 0.0;
     self.normal = if self.front_face { outward_normal } else { outward_normal.get_reversed() };
   }
+
+  /**
+   * The normal on the outer side of the surface, whichever side was hit.
+   **/
+  pub fn outward_normal(&self) -> Vec3 {
+    if self.front_face { self.normal } else { self.normal.get_reversed() }
+  }
+}
+
+/**
+ * Span of a ray inside a closed object, between the hit where it enters and the one where it exits.
+ **/
+#[derive(Clone, Copy)]
+pub struct Interval<'a> {
+  pub enter: HitRecord<'a>,
+  pub exit: HitRecord<'a>,
 }
 
 pub trait Hittable: Send + Sync {
-  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
+
+  /**
+   * Box holding the object for rays with a time in [time0, time1], None when it is unbounded.
+   **/
+  fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb>;
+
+  /**
+   * All the spans of the whole line of the ray (negative `t` included) inside the object, sorted
+   * along the ray. Only meaningful for closed objects, found by walking through the hits and
+   * counting how deep inside the ray is, so overlapping objects in a list merge.
+   **/
+  fn intervals(&self, ray: &Ray) -> Vec<Interval<'_>> {
+    let mut intervals = Vec::new();
+    let mut depth = 0;
+    let mut enter = None;
+    let mut t_from = -f64::INFINITY;
+    while let Some(record) = self.hit(ray, t_from, f64::INFINITY) {
+      t_from = record.t;
+      if record.front_face {
+        depth += 1;
+        if depth == 1 {
+          enter = Some(record);
+        }
+      } else if depth > 0 {
+        depth -= 1;
+        if depth == 0 {
+          if let Some(enter) = enter.take() {
+            intervals.push(Interval { enter, exit: record });
+          }
+        }
+      }
+    }
+    intervals
+  }
+
+  /**
+   * Fraction of light getting through the object between `t_min` and `t_max` along a shadow ray.
+   * Any hit blocks the light, unless it is transmissive (e.g. glass) and `skip_transmissive` is set,
+   * which gives caustic-free previews.
+   **/
+  fn shadow_transmittance(&self, ray: &Ray, t_min: f64, t_max: f64, skip_transmissive: bool) -> Color {
+    let mut t_from = t_min;
+    while let Some(record) = self.hit(ray, t_from, t_max) {
+      if !(skip_transmissive && record.material.is_transmissive()) {
+        return Color::new(0.0, 0.0, 0.0);
+      }
+      t_from = record.t;
+    }
+    Color::new(1.0, 1.0, 1.0)
+  }
 }
 
 pub struct HittableList {
@@ -69,12 +150,51 @@ impl HittableList {
   pub fn clear(&mut self) {
     self.objects.clear();
   }
+
+  pub fn len(&self) -> usize {
+    self.objects.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.objects.is_empty()
+  }
+
+  pub fn into_objects(self) -> Vec<Box<dyn Hittable>> {
+    self.objects
+  }
+}
+
+impl Default for HittableList {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 impl Hittable for HittableList {
-  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    let mut closest_so_far = t_max;
+    let mut closest = None;
+    for object in self.objects.iter() {
+      if let Some(record) = object.hit(ray, t_min, closest_so_far) {
+        closest_so_far = record.t;
+        closest = Some(record);
+      }
+    }
+    closest
+  }
+
+  fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+    let mut objects = self.objects.iter();
+    let first = objects.next()?.bounding_box(time0, time1)?;
+    objects.try_fold(first, |aabb, object| {
+      object.bounding_box(time0, time1).map(|other| Aabb::surrounding(&aabb, &other))
+    })
+  }
+
+  fn shadow_transmittance(&self, ray: &Ray, t_min: f64, t_max: f64, skip_transmissive: bool) -> Color {
     self.objects.iter()
-      .filter_map(|object| object.hit(ray, t_min, t_max))
-      .min_by(|rl, rr| rl.t.partial_cmp(&rr.t).unwrap())
+      .fold(Color::new(1.0, 1.0, 1.0), |transmittance, object| {
+        transmittance * object.shadow_transmittance(ray, t_min, t_max, skip_transmissive)
+      })
   }
 }
\ No newline at end of file
@@ -0,0 +1,88 @@
+use crate::core::{
+  PI,
+  point3::Point3,
+  ray::Ray,
+  transform::Transform,
+  vec3::Vec3,
+};
+use crate::materials::{Material};
+
+use super::hit::{HitRecord};
+
+/**
+ * Hit found in the own frame of a shape, before placing it in the scene.
+ * Shapes built around the y axis share the longitude convention of `Sphere` for u.
+ **/
+pub(crate) struct LocalHit {
+  pub t: f64,
+  // Outward, not normalized
+  pub normal: Vec3,
+  pub u: f64,
+  pub v: f64,
+  pub dpdu: Vec3,
+  pub dpdv: Vec3,
+}
+
+impl LocalHit {
+  pub fn into_record<'a>(self, ray: &Ray, to_world: &Transform, material: &'a dyn Material) -> HitRecord<'a> {
+    let outward_normal = to_world.normal(self.normal).unit();
+    let mut record = HitRecord::new(ray.at(self.t), outward_normal, self.t, material);
+    record.set_face_normal(ray, outward_normal);
+    record.u = self.u;
+    record.v = self.v;
+    record.dpdu = to_world.vector(self.dpdu);
+    record.dpdv = to_world.vector(self.dpdv);
+    record
+  }
+}
+
+/**
+ * Longitude around the y axis as u in [0, 1], and the derivative of the point along it.
+ **/
+pub(crate) fn azimuth(p: &Point3) -> (f64, Vec3) {
+  let u = ((-p.z).atan2(p.x) + PI) / (2.0 * PI);
+  (u, 2.0 * PI * Vec3::new(p.z, 0.0, -p.x))
+}
+
+/**
+ * Unit vector away from the y axis, towards the point.
+ **/
+pub(crate) fn radial(p: &Point3) -> Vec3 {
+  let r = Vec3::new(p.x, 0.0, p.z);
+  if r.length_square() > 0.0 { r.unit() } else { Vec3::new(1.0, 0.0, 0.0) }
+}
+
+/**
+ * Disk cap of a shape in the plane y = `y`, facing +y if `up`, -y otherwise.
+ * v goes from the rim to the center so the frame follows the outward normal.
+ **/
+pub(crate) fn disk_hit(ray: &Ray, y: f64, radius: f64, up: bool) -> Option<LocalHit> {
+  if ray.direction.y == 0.0 {
+    return None;
+  }
+  let t = (y - ray.origin.y) / ray.direction.y;
+  let p = ray.at(t);
+  let rho = (p.x * p.x + p.z * p.z).sqrt();
+  if rho > radius {
+    return None;
+  }
+  let (u, dpdu) = azimuth(&p);
+  let sign = if up { 1.0 } else { -1.0 };
+  Some(LocalHit {
+    t,
+    normal: Vec3::new(0.0, sign, 0.0),
+    u,
+    v: if up { 1.0 - rho / radius } else { rho / radius },
+    dpdu,
+    dpdv: radial(&p) * (-sign * radius),
+  })
+}
+
+/**
+ * The closest of the candidate hits within (t_min, t_max).
+ **/
+pub(crate) fn closest(hits: Vec<LocalHit>, t_min: f64, t_max: f64) -> Option<LocalHit> {
+  hits.into_iter()
+    .filter(|hit| hit.t > t_min && hit.t < t_max)
+    .min_by(|a, b| a.t.partial_cmp(&b.t).unwrap())
+}
@@ -1,2 +1,20 @@
 pub mod sphere;
-pub mod hit;
\ No newline at end of file
+pub mod hit;
+pub mod aabb;
+pub mod constant_medium;
+pub mod grid_medium;
+pub mod triangle;
+pub mod alpha_mask;
+pub mod subsurface;
+pub mod csg;
+pub mod local;
+pub mod cylinder;
+pub mod cone;
+pub mod torus;
+pub mod capsule;
+pub mod paraboloid;
+pub mod bvh;
+pub mod sdf;
+pub mod heightfield;
+pub mod curve;
+pub mod animated;
\ No newline at end of file
@@ -0,0 +1,104 @@
+use crate::core::{
+  point3::Point3,
+  polynomial::solve_quadratic,
+  ray::Ray,
+  transform::Transform,
+  vec3::Vec3,
+};
+use crate::materials::{Material};
+
+use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
+use super::local::{LocalHit, azimuth, radial, disk_hit, closest};
+
+/**
+ * Paraboloid of revolution from its vertex up to a disk cap of the given radius, closed by the cap
+ * (reflector dishes, bowls).
+ * u goes around the axis, v from the vertex to the rim on the side and from the rim to the
+ * center on the cap.
+ **/
+pub struct Paraboloid<M: Material> {
+  to_world: Transform,
+  radius: f64,
+  height: f64,
+  material: M,
+}
+
+impl<M: Material> Paraboloid<M> {
+  pub fn new(vertex: Point3, cap_center: Point3, radius: f64, material: M) -> Self {
+    Paraboloid {
+      to_world: Transform::from_axis(vertex, cap_center - vertex),
+      radius,
+      height: (cap_center - vertex).length(),
+      material,
+    }
+  }
+}
+
+impl<M: Material> Hittable for Paraboloid<M> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    let local = self.to_world.inverse().ray(ray);
+    let (o, d) = (local.origin, local.direction);
+    let (r, h) = (self.radius, self.height);
+    let mut hits = Vec::with_capacity(3);
+
+    // Side: x^2 + z^2 = k y for 0 <= y <= h, with k = r^2 / h
+    let k = r * r / h;
+    let a = d.x * d.x + d.z * d.z;
+    let b = 2.0 * (o.x * d.x + o.z * d.z) - k * d.y;
+    let c = o.x * o.x + o.z * o.z - k * o.y;
+    if let Some((t0, t1)) = solve_quadratic(a, b, c) {
+      for t in [t0, t1].iter() {
+        let p = local.at(*t);
+        if p.y >= 0.0 && p.y <= h {
+          let (u, dpdu) = azimuth(&p);
+          let v = p.y / h;
+          // The radius grows as r sqrt(v)
+          let dpdv = radial(&p) * (r / (2.0 * v.max(1e-6).sqrt())) + Vec3::new(0.0, h, 0.0);
+          hits.push(LocalHit {
+            t: *t,
+            normal: Vec3::new(2.0 * p.x, -k, 2.0 * p.z),
+            u,
+            v,
+            dpdu,
+            dpdv,
+          });
+        }
+      }
+    }
+    hits.extend(disk_hit(&local, h, r, true));
+
+    closest(hits, t_min, t_max).map(|hit| hit.into_record(ray, &self.to_world, &self.material))
+  }
+
+  fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+    let r = self.radius;
+    let local = Aabb::new(Point3::new(-r, 0.0, -r), Point3::new(r, self.height, r));
+    Some(local.transformed(&self.to_world))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::materials::DefaultMaterial;
+
+  #[test]
+  fn test_hit() {
+    // y = x^2 + z^2 up to y = 1
+    let dish = Paraboloid::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 1.0, 0.0), 1.0, DefaultMaterial::new());
+
+    let r = Ray::new(Point3::new(0.5, -1.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    let record = dish.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 1.25).abs() < 1e-9);
+    // Slope of 1 at x = 0.5, the normal points out and down
+    assert!((record.normal - Vec3::new(1.0, -1.0, 0.0).unit()).length() < 1e-9);
+    assert!((record.v - 0.25).abs() < 1e-9);
+
+    // Out through the cap
+    let record = dish.hit(&r, 1.251, f64::INFINITY).unwrap();
+    assert!((record.t - 2.0).abs() < 1e-9 && !record.front_face);
+
+    assert!(dish.hit(&Ray::new(Point3::new(1.5, -1.0, 0.0), Vec3::new(0.0, 1.0, 0.0)), 0.001, f64::INFINITY).is_none());
+  }
+}
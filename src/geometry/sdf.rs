@@ -0,0 +1,310 @@
+use crate::core::{
+  point3::Point3,
+  ray::Ray,
+  vec3::Vec3,
+};
+use crate::materials::{Material};
+use crate::utils::utils::clamp;
+
+use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
+
+/**
+ * Signed distance field: distance from a point to the closest surface, negative inside.
+ * It may underestimate the distance (a bound is enough) but never overestimate it.
+ * Closures `Fn(&Point3) -> f64` are fields too.
+ * Refer to: https://iquilezles.org/articles/distfunctions/
+ **/
+pub trait Sdf: Send + Sync {
+  fn distance(&self, p: &Point3) -> f64;
+}
+
+impl<F: Fn(&Point3) -> f64 + Send + Sync> Sdf for F {
+  fn distance(&self, p: &Point3) -> f64 {
+    self(p)
+  }
+}
+
+pub struct SdfSphere {
+  pub center: Point3,
+  pub radius: f64,
+}
+
+impl Sdf for SdfSphere {
+  fn distance(&self, p: &Point3) -> f64 {
+    (*p - self.center).length() - self.radius
+  }
+}
+
+/**
+ * Box of the given half size, its edges rounded by `radius` (which adds to the size).
+ **/
+pub struct RoundBox {
+  pub center: Point3,
+  pub half_size: Vec3,
+  pub radius: f64,
+}
+
+impl Sdf for RoundBox {
+  fn distance(&self, p: &Point3) -> f64 {
+    let d = *p - self.center;
+    let q = Vec3::new(d.x.abs() - self.half_size.x, d.y.abs() - self.half_size.y, d.z.abs() - self.half_size.z);
+    let outside = Vec3::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).length();
+    let inside = q.x.max(q.y).max(q.z).min(0.0);
+    outside + inside - self.radius
+  }
+}
+
+/**
+ * 3D Mandelbrot fractal, from the distance estimator of its escape time iteration.
+ * It fits in a sphere of radius about 1.2 times `scale`.
+ * Refer to: https://en.wikipedia.org/wiki/Mandelbulb
+ **/
+pub struct Mandelbulb {
+  pub center: Point3,
+  pub scale: f64,
+  pub power: f64,
+  pub iterations: u32,
+}
+
+impl Mandelbulb {
+  pub fn new(center: Point3, scale: f64) -> Self {
+    Mandelbulb { center, scale, power: 8.0, iterations: 12 }
+  }
+}
+
+impl Sdf for Mandelbulb {
+  fn distance(&self, p: &Point3) -> f64 {
+    let c = (*p - self.center) / self.scale;
+    let mut z = c;
+    let mut dr = 1.0;
+    let mut r = z.length();
+    for _ in 0..self.iterations {
+      if r > 2.0 || r == 0.0 {
+        break;
+      }
+      // Raise to the power in spherical coordinates
+      let theta = clamp(z.z / r, -1.0, 1.0).acos() * self.power;
+      let phi = z.y.atan2(z.x) * self.power;
+      dr = r.powf(self.power - 1.0) * self.power * dr + 1.0;
+      let zr = r.powf(self.power);
+      z = zr * Vec3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()) + c;
+      r = z.length();
+    }
+    if r <= 0.0 {
+      return 0.0;
+    }
+    0.5 * r.ln() * r / dr * self.scale
+  }
+}
+
+pub struct Union<A: Sdf, B: Sdf>(pub A, pub B);
+
+impl<A: Sdf, B: Sdf> Sdf for Union<A, B> {
+  fn distance(&self, p: &Point3) -> f64 {
+    self.0.distance(p).min(self.1.distance(p))
+  }
+}
+
+pub struct Intersection<A: Sdf, B: Sdf>(pub A, pub B);
+
+impl<A: Sdf, B: Sdf> Sdf for Intersection<A, B> {
+  fn distance(&self, p: &Point3) -> f64 {
+    self.0.distance(p).max(self.1.distance(p))
+  }
+}
+
+/**
+ * The first field with the second one carved out.
+ **/
+pub struct Difference<A: Sdf, B: Sdf>(pub A, pub B);
+
+impl<A: Sdf, B: Sdf> Sdf for Difference<A, B> {
+  fn distance(&self, p: &Point3) -> f64 {
+    self.0.distance(p).max(-self.1.distance(p))
+  }
+}
+
+/**
+ * Union blending the two surfaces over about a distance `k`.
+ **/
+pub struct SmoothUnion<A: Sdf, B: Sdf> {
+  pub a: A,
+  pub b: B,
+  pub k: f64,
+}
+
+impl<A: Sdf, B: Sdf> Sdf for SmoothUnion<A, B> {
+  fn distance(&self, p: &Point3) -> f64 {
+    let (d1, d2) = (self.a.distance(p), self.b.distance(p));
+    let h = clamp(0.5 + 0.5 * (d2 - d1) / self.k, 0.0, 1.0);
+    d2 * (1.0 - h) + d1 * h - self.k * h * (1.0 - h)
+  }
+}
+
+/**
+ * Surface where a signed distance field is zero, found by sphere tracing: stepping along the ray
+ * by the distance to the closest surface, which can't be crossed, until it gets below `epsilon`.
+ * The field is only traced inside `bounds`.
+ * Refer to: https://en.wikipedia.org/wiki/Ray_marching#Sphere_tracing
+ **/
+pub struct SdfShape<S: Sdf, M: Material> {
+  sdf: S,
+  bounds: Aabb,
+  material: M,
+  // Distance to the surface under which it is hit
+  pub epsilon: f64,
+  // Rays still marching after this many steps miss
+  pub max_steps: u32,
+}
+
+impl<S: Sdf, M: Material> SdfShape<S, M> {
+  pub fn new(sdf: S, bounds: Aabb, material: M) -> Self {
+    SdfShape {
+      sdf,
+      bounds,
+      material,
+      epsilon: 1e-4,
+      max_steps: 512,
+    }
+  }
+
+  /**
+   * Gradient of the field by central differences, the outward normal on the surface.
+   **/
+  pub fn gradient(&self, p: &Point3) -> Vec3 {
+    let h = self.epsilon;
+    let dx = Vec3::new(h, 0.0, 0.0);
+    let dy = Vec3::new(0.0, h, 0.0);
+    let dz = Vec3::new(0.0, 0.0, h);
+    Vec3::new(
+      self.sdf.distance(&(*p + dx)) - self.sdf.distance(&(*p - dx)),
+      self.sdf.distance(&(*p + dy)) - self.sdf.distance(&(*p - dy)),
+      self.sdf.distance(&(*p + dz)) - self.sdf.distance(&(*p - dz)),
+    ) / (2.0 * h)
+  }
+
+  /**
+   * Where the ray overlaps the bounds within [t_min, t_max].
+   **/
+  fn span(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+    let (min, max) = (self.bounds.min, self.bounds.max);
+    let (mut t0, mut t1) = (t_min, t_max);
+    for (o, d, lo, hi) in [
+      (ray.origin.x, ray.direction.x, min.x, max.x),
+      (ray.origin.y, ray.direction.y, min.y, max.y),
+      (ray.origin.z, ray.direction.z, min.z, max.z),
+    ].iter() {
+      let inv_d = 1.0 / d;
+      let (mut t_near, mut t_far) = ((lo - o) * inv_d, (hi - o) * inv_d);
+      if inv_d < 0.0 {
+        std::mem::swap(&mut t_near, &mut t_far);
+      }
+      t0 = if t_near > t0 { t_near } else { t0 };
+      t1 = if t_far < t1 { t_far } else { t1 };
+      if t1 < t0 {
+        return None;
+      }
+    }
+    Some((t0, t1))
+  }
+}
+
+impl<S: Sdf, M: Material> Hittable for SdfShape<S, M> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    let (t_start, t_end) = self.span(ray, t_min, t_max)?;
+    let speed = ray.direction.length();
+
+    // March on the side of the surface the ray starts from. A ray leaving the surface (after a
+    // bounce) must get away from it before a hit counts.
+    let start = self.sdf.distance(&ray.at(t_start));
+    let side = if start.abs() >= self.epsilon {
+      start.signum()
+    } else if Vec3::dot(&ray.direction, &self.gradient(&ray.at(t_start))) > 0.0 {
+      1.0
+    } else {
+      -1.0
+    };
+    let mut escaped = start.abs() >= self.epsilon;
+
+    let mut t = t_start;
+    for _ in 0..self.max_steps {
+      let distance = side * self.sdf.distance(&ray.at(t));
+      if distance < self.epsilon {
+        if escaped {
+          let point = ray.at(t);
+          let outward_normal = self.gradient(&point).unit();
+          let mut record = HitRecord::new(point, outward_normal, t, &self.material);
+          record.set_face_normal(ray, outward_normal);
+          return Some(record);
+        }
+      } else {
+        escaped = true;
+      }
+      t += distance.max(self.epsilon) / speed;
+      if t > t_end {
+        return None;
+      }
+    }
+    None
+  }
+
+  fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+    Some(self.bounds)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::materials::DefaultMaterial;
+
+  fn unit_bounds() -> Aabb {
+    Aabb::new(Point3::new(-2.0, -2.0, -2.0), Point3::new(2.0, 2.0, 2.0))
+  }
+
+  #[test]
+  fn test_sphere_tracing() {
+    let shape = SdfShape::new(SdfSphere { center: Point3::new(0.0, 0.0, 0.0), radius: 1.0 }, unit_bounds(), DefaultMaterial::new());
+    let r = Ray::new(Point3::new(0.3, 0.0, 5.0), Vec3::new(0.0, 0.0, -2.0));
+    let record = shape.hit(&r, 0.001, f64::INFINITY).unwrap();
+    let expected_t = (5.0 - (1.0_f64 - 0.09).sqrt()) / 2.0;
+    assert!((record.t - expected_t).abs() < 1e-3);
+    assert!((record.normal - record.point.unit()).length() < 1e-3);
+    assert!(record.front_face);
+
+    // Leaving the surface from inside, the next hit is on the other side
+    let inside = Ray::new(record.point, Vec3::new(0.0, 0.0, -1.0));
+    let exit = shape.hit(&inside, 0.001, f64::INFINITY).unwrap();
+    assert!((exit.point.z + (1.0_f64 - 0.09).sqrt()).abs() < 1e-3);
+    assert!(!exit.front_face);
+
+    // Bouncing off, nothing else to hit
+    let outside = Ray::new(record.point, Vec3::new(0.0, 0.0, 1.0));
+    assert!(shape.hit(&outside, 0.001, f64::INFINITY).is_none());
+    assert!(shape.hit(&Ray::new(Point3::new(1.5, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0)), 0.001, f64::INFINITY).is_none());
+  }
+
+  #[test]
+  fn test_closure_and_nodes() {
+    let shape = SdfShape::new(|p: &Point3| p.y + 0.5, unit_bounds(), DefaultMaterial::new());
+    let record = shape.hit(&Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0)), 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 1.5).abs() < 1e-3);
+
+    let a = SdfSphere { center: Point3::new(-0.5, 0.0, 0.0), radius: 0.6 };
+    let b = SdfSphere { center: Point3::new(0.5, 0.0, 0.0), radius: 0.6 };
+    let p = Point3::new(0.0, 0.7, 0.0);
+    let (da, db) = (a.distance(&p), b.distance(&p));
+    let blend = SmoothUnion { a, b, k: 0.3 };
+    // The blend fills the crease between the two spheres
+    assert!(blend.distance(&p) < da.min(db));
+
+    let rounded = RoundBox { center: Point3::new(0.0, 0.0, 0.0), half_size: Vec3::new(1.0, 1.0, 1.0), radius: 0.1 };
+    assert!((rounded.distance(&Point3::new(2.0, 0.0, 0.0)) - 0.9).abs() < 1e-12);
+    assert!((rounded.distance(&Point3::new(0.5, 0.0, 0.0)) + 0.6).abs() < 1e-12);
+
+    let bulb = Mandelbulb::new(Point3::new(0.0, 0.0, 0.0), 1.0);
+    assert!(bulb.distance(&Point3::new(0.0, 0.0, 0.0)) <= 0.0);
+    assert!(bulb.distance(&Point3::new(3.0, 0.0, 0.0)) > 0.5);
+  }
+}
@@ -1,4 +1,5 @@
 use crate::core::{
+  PI,
   point3::Point3,
   ray::Ray,
   vec3::Vec3,
@@ -6,6 +7,7 @@ use crate::core::{
 use crate::materials::{Material};
 
 use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
 
 pub struct Sphere<M: Material> {
   center: Point3,
@@ -18,10 +20,35 @@ impl<M: Material> Sphere<M> {
     Sphere { center, radius, material }
   }
 
+  /**
+   * Texture coordinates of a point on the unit sphere centered at the origin:
+   * u follows the longitude from -x, v the latitude from the south pole, both in [0, 1].
+   **/
+  pub fn get_uv(p: &Point3) -> (f64, f64) {
+    let theta = (-p.y).acos();
+    let phi = (-p.z).atan2(p.x) + PI;
+    (phi / (2.0 * PI), theta / PI)
+  }
+
+  /**
+   * Texture coordinates and tangent frame of a hit point.
+   **/
+  fn set_uv(&self, record: &mut HitRecord) {
+    let radius = self.radius.abs();
+    let (u, v) = Self::get_uv(&((record.point - self.center) / radius));
+    let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+    let (sin_theta, cos_theta) = (PI * v).sin_cos();
+    record.u = u;
+    record.v = v;
+    record.dpdu = 2.0 * PI * radius * sin_theta * Vec3::new(sin_phi, 0.0, cos_phi);
+    record.dpdv = PI * radius * Vec3::new(-cos_theta * cos_phi, sin_theta, cos_theta * sin_phi);
+  }
+
   /**
    * If the ray hit the area of the sphere.
    * We say the ray cutting the surface of sphere does not hit (able to pass through)
    * */
+  #[allow(unused_parens)]
   pub fn is_hitten(&self, ray: &Ray) -> bool {
     let oc: Vec3 = ray.origin - self.center;
     let a: f64 = ray.direction.length_square();
@@ -50,7 +77,7 @@ impl<M: Material> Sphere<M> {
 }
 
 impl<M: Material> Hittable for Sphere<M> {
-  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
     let oc: Vec3 = ray.origin - self.center;
     let a: f64 = ray.direction.length_square();
     let half_b: f64 = Vec3::dot(&oc, &(ray.direction));
@@ -66,6 +93,7 @@ impl<M: Material> Hittable for Sphere<M> {
         let normal: Vec3 = (point - self.center) / self.radius;
         let mut record = HitRecord::new(point, normal, t, &self.material);
         record.set_face_normal(ray, normal);
+        self.set_uv(&mut record);
         return Some(record);
       }
       let solution_2: f64 = (-half_b + root) / a;
@@ -75,11 +103,18 @@ impl<M: Material> Hittable for Sphere<M> {
         let normal: Vec3 = (point - self.center) / self.radius;
         let mut record = HitRecord::new(point, normal, t, &self.material);
         record.set_face_normal(ray, normal);
+        self.set_uv(&mut record);
         return Some(record);
       }
     } 
     None
   }
+
+  fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+    let r = self.radius.abs();
+    let extent = Vec3::new(r, r, r);
+    Some(Aabb::new(self.center - extent, self.center + extent))
+  }
 }
 
 #[cfg(test)]
@@ -88,9 +123,11 @@ mod tests {
   use super::Vec3;
   use super::Point3;
   use super::Sphere;
+  use super::Hittable;
   use crate::materials::DefaultMaterial;
   
   #[test]
+  #[allow(clippy::bool_assert_comparison)]
   fn test_hit() {
     let s = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, DefaultMaterial::new());
     let r1 = Ray::new(Point3::new(5.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
@@ -103,4 +140,27 @@ mod tests {
     let r3 = Ray::new(Point3::new(0.0, 0.1, -1.0), Vec3::new(0.1, 0.0, 1.0));
     assert_eq!(s.is_hitten(&r3), true);
   }
+
+  #[test]
+  fn test_uv() {
+    let (u, v) = Sphere::<DefaultMaterial>::get_uv(&Point3::new(1.0, 0.0, 0.0));
+    assert!((u - 0.5).abs() < 1e-12 && (v - 0.5).abs() < 1e-12);
+    let (_, v) = Sphere::<DefaultMaterial>::get_uv(&Point3::new(0.0, 1.0, 0.0));
+    assert!((v - 1.0).abs() < 1e-12);
+    let (u, _) = Sphere::<DefaultMaterial>::get_uv(&Point3::new(0.0, 0.0, 1.0));
+    assert!((u - 0.25).abs() < 1e-12);
+  }
+
+  #[test]
+  fn test_tangents() {
+    let s = Sphere::new(Point3::new(0.0, 0.0, 0.0), 2.0, DefaultMaterial::new());
+    let r = Ray::new(Point3::new(5.0, 0.5, 0.3), Vec3::new(-1.0, 0.0, 0.0));
+    let record = s.hit(&r, 0.0, 100.0).unwrap();
+    // The frame follows the parameterization and is oriented like the outward normal
+    let (u, v) = Sphere::<DefaultMaterial>::get_uv(&((record.point + record.dpdu * 1e-6) / 2.0));
+    assert!((u - record.u - 1e-6).abs() < 1e-9 && (v - record.v).abs() < 1e-9);
+    let (u, v) = Sphere::<DefaultMaterial>::get_uv(&((record.point + record.dpdv * 1e-6) / 2.0));
+    assert!((u - record.u).abs() < 1e-9 && (v - record.v - 1e-6).abs() < 1e-9);
+    assert!(Vec3::dot(&Vec3::cross(&record.dpdu, &record.dpdv), &record.normal) > 0.0);
+  }
 }
@@ -0,0 +1,244 @@
+use crate::core::{
+  color::Color,
+  ray::Ray,
+  vec3::Vec3,
+};
+use crate::materials::{
+  Material,
+  ScatterRecord,
+  dielectric::Dielectric,
+};
+use crate::utils::utils::random_double;
+
+use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
+
+// Walks longer than this leave diffusely near the nearest boundary point instead of going on
+const MAX_WALK_STEPS: usize = 4096;
+// Directions probed for the nearest boundary point: the axes both ways
+const EXIT_PROBES: [(f64, f64, f64); 6] = [
+  (1.0, 0.0, 0.0), (-1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, -1.0, 0.0), (0.0, 0.0, 1.0), (0.0, 0.0, -1.0),
+];
+// Offset for the hits of the walk, smaller than the usual one as steps can be tiny
+const WALK_EPSILON: f64 = 1e-5;
+
+/**
+ * Translucent material (skin, wax, marble) filling a closed boundary: light refracts in through
+ * a `Dielectric` interface, scatters in a random walk inside and leaves from another point.
+ * The mean free path (average distance between scattering events) and the single scattering
+ * albedo are per channel. As the mean free path gets to zero light leaves right where it entered,
+ * in a diffuse way, like `Lambertian`.
+ *
+ * The whole walk happens in `scatter`, so it costs a single bounce of the path.
+ * The material of the boundary is not used.
+ * Refer to: https://graphics.pixar.com/library/PathTracedSubsurface/
+ **/
+pub struct Subsurface<H: Hittable> {
+  boundary: H,
+  interface: Dielectric,
+  sigma_t: Color,
+  albedo: Color,
+}
+
+impl<H: Hittable> Subsurface<H> {
+  pub fn new(boundary: H, ior: f64, mean_free_path: Color, albedo: Color) -> Self {
+    let extinction = |mfp: f64| 1.0 / mfp.max(1e-9);
+    Subsurface {
+      boundary,
+      interface: Dielectric::new(ior),
+      sigma_t: Color::new(
+        extinction(mean_free_path.r()),
+        extinction(mean_free_path.g()),
+        extinction(mean_free_path.b()),
+      ),
+      albedo,
+    }
+  }
+
+  fn sigma_t_channel(&self, channel: usize) -> f64 {
+    match channel {
+      0 => self.sigma_t.r(),
+      1 => self.sigma_t.g(),
+      _ => self.sigma_t.b(),
+    }
+  }
+
+  /**
+   * Random walk from a point just inside the boundary. Distances of the whole walk are sampled
+   * for one random channel, each channel being weighted by the average of the pdfs of the
+   * walk for all of them (one-sample MIS over the path), which keeps weights below 3.
+   * Walks too long to follow leave through the interface from right under the nearest boundary
+   * point, in a diffuse direction, as deep in the object light has forgotten where it came from.
+   * That skips the rest of the walk, absorption and the distance to the boundary included, which
+   * biases deep walks towards more light.
+   * Returns the ray leaving the boundary and the weight picked up, None if the light got absorbed.
+   **/
+  fn walk(&self, r_in: &Ray, entry: Ray) -> Option<(Ray, Color)> {
+    let channel = ((random_double() * 3.0) as usize).min(2);
+    let sigma_t = self.sigma_t_channel(channel);
+    let mut albedo = Color::new(1.0, 1.0, 1.0);
+    // Pdf of the walk for each channel, only their ratios matter
+    let mut pdfs = Color::new(1.0, 1.0, 1.0);
+    let mut ray = entry;
+    for _ in 0..MAX_WALK_STEPS {
+      ray.direction = ray.direction.unit();
+      // A point right on the boundary may miss it with the offset
+      let exit = self.boundary.hit(&ray, WALK_EPSILON, f64::INFINITY)
+        .or_else(|| self.boundary.hit(&ray, 0.0, f64::INFINITY))?;
+
+      let distance = -(1.0 - random_double()).ln() / sigma_t;
+      if distance < exit.t {
+        albedo = albedo * self.albedo;
+        pdfs = pdfs * self.sigma_t * (self.sigma_t * -distance).exp();
+        pdfs = pdfs * (1.0 / pdfs.max_component());
+        ray = self.walk_ray(r_in, ray.at(distance), Vec3::random_unit_vec());
+        continue;
+      }
+
+      pdfs = pdfs * (self.sigma_t * -exit.t).exp();
+      pdfs = pdfs * (1.0 / pdfs.max_component());
+      let scattered = self.interface.scatter(&ray, &exit)?;
+      let direction = scattered.scattered.direction;
+      if Vec3::dot(&direction, &exit.outward_normal()) > 0.0 {
+        let mut out = scattered.scattered;
+        out.time = r_in.time;
+        out.wavelength = r_in.wavelength;
+        let weight = albedo * pdfs * (1.0 / pdfs.average());
+        return Some((out, weight * scattered.attenuation));
+      }
+      // Reflected back inside
+      ray = self.walk_ray(r_in, exit.point, direction);
+    }
+
+    let nearest = EXIT_PROBES.iter()
+      .filter_map(|(x, y, z)| self.boundary.hit(&Ray::new(ray.origin, Vec3::new(*x, *y, *z)), 0.0, f64::INFINITY))
+      .min_by(|a, b| a.t.total_cmp(&b.t))?;
+    let weight = albedo * pdfs * (1.0 / pdfs.average());
+    for _ in 0..MAX_WALK_STEPS {
+      let direction = (nearest.outward_normal() + Vec3::random_unit_vec()).unit();
+      let probe = self.walk_ray(r_in, nearest.point - WALK_EPSILON * direction, direction);
+      let exit = self.boundary.hit(&probe, 0.0, f64::INFINITY)?;
+      let scattered = self.interface.scatter(&probe, &exit)?;
+      // Light the interface reflects back inside tries another direction
+      if Vec3::dot(&scattered.scattered.direction, &exit.outward_normal()) > 0.0 {
+        return Some((scattered.scattered, weight * scattered.attenuation));
+      }
+    }
+    None
+  }
+
+  fn walk_ray(&self, r_in: &Ray, origin: Vec3, direction: Vec3) -> Ray {
+    let mut ray = Ray::new_with_time(origin, direction, r_in.time);
+    ray.wavelength = r_in.wavelength;
+    ray
+  }
+}
+
+impl<H: Hittable> Hittable for Subsurface<H> {
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    let record = self.boundary.hit(ray, t_min, t_max)?;
+    Some(HitRecord { material: self, ..record })
+  }
+
+  fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+    self.boundary.bounding_box(time0, time1)
+  }
+}
+
+impl<H: Hittable> Material for Subsurface<H> {
+  fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
+    let mut interface = self.interface.scatter(r_in, record)?;
+    if !record.front_face || Vec3::dot(&interface.scattered.direction, &record.normal) > 0.0 {
+      // Reflected by the surface, or leaving the object for a camera inside it
+      return Some(interface);
+    }
+    interface.scattered.wavelength = r_in.wavelength;
+    let (scattered, weight) = self.walk(r_in, interface.scattered)?;
+    Some(ScatterRecord {
+      attenuation: weight * interface.attenuation,
+      scattered,
+    })
+  }
+
+  fn is_dispersive(&self) -> bool {
+    self.interface.is_dispersive()
+  }
+
+  fn is_transmissive(&self) -> bool {
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::point3::Point3;
+  use crate::geometry::sphere::Sphere;
+  use crate::materials::DefaultMaterial;
+
+  #[test]
+  fn test_short_walk() {
+    // Without absorption all the light comes out, near where it entered when the mean free path is short
+    let boundary = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, DefaultMaterial::new());
+    let subsurface = Subsurface::new(boundary, 1.3, Color::new(0.05, 0.05, 0.05), Color::new(1.0, 1.0, 1.0));
+    let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let record = subsurface.hit(&r, 0.001, f64::INFINITY).unwrap();
+
+    let (mut inside, mut near, mut absorbed) = (0, 0, 0);
+    for _ in 0..200 {
+      let scattered = match subsurface.scatter(&r, &record) {
+        Some(scattered) => scattered,
+        None => {
+          // Only walks missing the boundary numerically
+          absorbed += 1;
+          continue;
+        },
+      };
+      assert!((scattered.attenuation.r() - 1.0).abs() < 1e-9);
+      let out = scattered.scattered;
+      assert!(Vec3::dot(&out.direction, &out.origin) > 0.0);
+      if (out.origin - record.point).length() > 1e-9 {
+        inside += 1;
+        if (out.origin - record.point).length() < 0.3 {
+          near += 1;
+        }
+      }
+    }
+    // Most of the light does not bounce off the surface
+    assert!(inside > 150);
+    assert!(near as f64 > 0.5 * inside as f64);
+    assert!(absorbed < 10);
+  }
+
+  #[test]
+  fn test_energy_conservation() {
+    // Without absorption every walk gets out, the long ones included
+    let boundary = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, DefaultMaterial::new());
+    let subsurface = Subsurface::new(boundary, 1.3, Color::new(0.01, 0.01, 0.01), Color::new(1.0, 1.0, 1.0));
+    let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let record = subsurface.hit(&r, 0.001, f64::INFINITY).unwrap();
+    let n = 1000;
+    let mut energy = 0.0;
+    for _ in 0..n {
+      let scattered = subsurface.scatter(&r, &record).unwrap();
+      assert!(Vec3::dot(&scattered.scattered.direction, &scattered.scattered.origin) > 0.0);
+      energy += scattered.attenuation.average();
+    }
+    assert!((energy / n as f64 - 1.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_absorption() {
+    // Red light goes through a long red walk, blue is absorbed
+    let boundary = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, DefaultMaterial::new());
+    let subsurface = Subsurface::new(boundary, 1.0, Color::new(0.1, 0.1, 0.1), Color::new(1.0, 0.5, 0.0));
+    let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let record = subsurface.hit(&r, 0.001, f64::INFINITY).unwrap();
+    for _ in 0..50 {
+      if let Some(scattered) = subsurface.scatter(&r, &record) {
+        let a = scattered.attenuation;
+        assert!(a.r() >= a.g() && a.g() >= a.b());
+      }
+    }
+  }
+}
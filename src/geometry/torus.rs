@@ -0,0 +1,166 @@
+use crate::core::{
+  PI,
+  point3::Point3,
+  polynomial::{Polynomial, solve_quadratic},
+  ray::Ray,
+  transform::Transform,
+  vec3::Vec3,
+};
+use crate::materials::{Material};
+
+use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
+use super::local::{LocalHit, azimuth, radial};
+
+// Fraction of the outer radius skipped past t_min when the ray is restarted from there
+const RESTART_MARGIN: f64 = 1e-7;
+
+/**
+ * Ring around `axis` through `center`, the tube of radius `minor` following a circle of radius `major`.
+ * u goes around the axis, v around the tube starting from its outer equator.
+ **/
+pub struct Torus<M: Material> {
+  to_world: Transform,
+  major: f64,
+  minor: f64,
+  material: M,
+}
+
+impl<M: Material> Torus<M> {
+  pub fn new(center: Point3, axis: Vec3, major: f64, minor: f64, material: M) -> Self {
+    Torus {
+      to_world: Transform::from_axis(center, axis),
+      major,
+      minor,
+      material,
+    }
+  }
+}
+
+impl<M: Material> Hittable for Torus<M> {
+  /**
+   * Solve the quartic (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + z^2) along the ray.
+   * The ray is first clipped to the bounding sphere and restarted from there, with a unit
+   * direction, which keeps the coefficients well conditioned for distant rays.
+   **/
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    let local = self.to_world.inverse().ray(ray);
+    let speed = local.direction.length();
+    let d = local.direction / speed;
+    let (big_r, small_r) = (self.major, self.minor);
+
+    let bound = big_r + small_r;
+    let (s0, s1) = solve_quadratic(1.0, 2.0 * Vec3::dot(&local.origin, &d), local.origin.length_square() - bound * bound)?;
+    let start = s0.max(t_min * speed);
+    let end = s1.min(t_max * speed);
+    if start >= end {
+      return None;
+    }
+
+    let o = local.origin + d * start;
+    let b = Vec3::dot(&o, &d);
+    let k = o.length_square() + big_r * big_r - small_r * small_r;
+    let four_r2 = 4.0 * big_r * big_r;
+    let quartic = Polynomial::new(vec![
+      k * k - four_r2 * (o.x * o.x + o.z * o.z),
+      4.0 * b * k - 2.0 * four_r2 * (o.x * d.x + o.z * d.z),
+      4.0 * b * b + 2.0 * k - four_r2 * (d.x * d.x + d.z * d.z),
+      4.0 * b,
+      1.0,
+    ]);
+    // Restarted from t_min, the root there comes back up to rounding past it (more for grazing
+    // rays): skip a thin margin so rays walking the hits move on
+    let margin = if start > s0 { RESTART_MARGIN * bound } else { 0.0 };
+    let t = quartic.roots_in(margin, end - start).into_iter()
+      .map(|s| (s + start) / speed)
+      .find(|t| *t > t_min)?;
+
+    let p = local.at(t);
+    let outward = radial(&p);
+    let rho = (p.x * p.x + p.z * p.z).sqrt();
+    let (u, dpdu) = azimuth(&p);
+    let mut theta = p.y.atan2(rho - big_r);
+    if theta < 0.0 {
+      theta += 2.0 * PI;
+    }
+    let hit = LocalHit {
+      t,
+      normal: p - outward * big_r,
+      u,
+      v: theta / (2.0 * PI),
+      dpdu,
+      dpdv: 2.0 * PI * (outward * -p.y + Vec3::new(0.0, rho - big_r, 0.0)),
+    };
+    Some(hit.into_record(ray, &self.to_world, &self.material))
+  }
+
+  fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+    let (big_r, small_r) = (self.major, self.minor);
+    let local = Aabb::new(
+      Point3::new(-big_r - small_r, -small_r, -big_r - small_r),
+      Point3::new(big_r + small_r, small_r, big_r + small_r),
+    );
+    Some(local.transformed(&self.to_world))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::color::Color;
+  use crate::materials::{DefaultMaterial, dielectric::Dielectric};
+  use crate::utils::utils::random_double_in_range;
+
+  #[test]
+  fn test_hit() {
+    let torus = Torus::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 2.0, 0.5, DefaultMaterial::new());
+
+    // Through all four walls
+    let r = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0));
+    let mut t = 0.001;
+    for (expected, front_face) in [(1.25, true), (1.75, false), (3.25, true), (3.75, false)].iter() {
+      let record = torus.hit(&r, t, f64::INFINITY).unwrap();
+      assert!((record.t - expected).abs() < 1e-9, "{} != {}", record.t, expected);
+      assert_eq!(record.front_face, *front_face);
+      t = record.t + 0.001;
+    }
+    assert!(torus.hit(&r, t, f64::INFINITY).is_none());
+
+    // On top of the tube
+    let r = Ray::new(Point3::new(2.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let record = torus.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - 4.5).abs() < 1e-9);
+    assert!((record.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+    assert!((record.v - 0.25).abs() < 1e-9);
+    assert!(Vec3::dot(&Vec3::cross(&record.dpdu, &record.dpdv), &record.normal) > 0.0);
+
+    // Through the hole, and from far away
+    assert!(torus.hit(&Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0)), 0.001, f64::INFINITY).is_none());
+    let r = Ray::new(Point3::new(-1e4, 0.1, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    let record = torus.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!((record.t - (1e4 - 2.0 - (0.25_f64 - 0.01).sqrt())).abs() < 1e-6);
+  }
+
+  #[test]
+  fn test_restart_from_hit() {
+    let torus = Torus::new(Point3::new(0.3, -0.2, 0.1), Vec3::new(1.0, 2.0, 0.5), 2.0, 0.5, DefaultMaterial::new());
+    let glass = Torus::new(Point3::new(0.3, -0.2, 0.1), Vec3::new(1.0, 2.0, 0.5), 2.0, 0.5, Dielectric::new(1.5));
+    // Walking the hits from the previous one, as shadow rays and CSG do, moves forward and stops:
+    // a line crosses a torus at most 4 times
+    for _ in 0..2000 {
+      let origin = Point3::new(random_double_in_range(-6.0, 6.0), random_double_in_range(-6.0, 6.0), 6.0);
+      let target = Point3::new(random_double_in_range(-2.5, 2.5), random_double_in_range(-2.5, 2.5), random_double_in_range(-1.0, 1.0));
+      let r = Ray::new(origin, (target - origin) * random_double_in_range(0.1, 3.0));
+      let mut t = 0.001;
+      let mut hits = 0;
+      while let Some(record) = torus.hit(&r, t, f64::INFINITY) {
+        assert!(record.t > t, "{} after {}", record.t, t);
+        t = record.t;
+        hits += 1;
+        assert!(hits <= 4, "stuck at t = {}", t);
+      }
+      // Shadow rays skipping the glass walk its hits the same way
+      assert_eq!(glass.shadow_transmittance(&r, 0.001, f64::INFINITY, true), Color::new(1.0, 1.0, 1.0));
+    }
+  }
+}
@@ -0,0 +1,107 @@
+use crate::core::{
+  point3::Point3,
+  ray::Ray,
+  vec3::Vec3,
+};
+use crate::materials::{Material};
+
+use super::hit::{HitRecord, Hittable};
+use super::aabb::Aabb;
+
+/**
+ * A flat triangle, the outward normal follows the counter-clockwise order of the vertices.
+ **/
+pub struct Triangle<M: Material> {
+  v0: Point3,
+  v1: Point3,
+  v2: Point3,
+  material: M,
+}
+
+impl<M: Material> Triangle<M> {
+  pub fn new(v0: Point3, v1: Point3, v2: Point3, material: M) -> Self {
+    Triangle { v0, v1, v2, material }
+  }
+}
+
+impl<M: Material> Hittable for Triangle<M> {
+  /**
+   * Moller-Trumbore ray-triangle intersection.
+   * Refer to: https://en.wikipedia.org/wiki/M%C3%B6ller%E2%80%93Trumbore_intersection_algorithm
+   **/
+  fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+    let edge1: Vec3 = self.v1 - self.v0;
+    let edge2: Vec3 = self.v2 - self.v0;
+    let p: Vec3 = Vec3::cross(&ray.direction, &edge2);
+    let det: f64 = Vec3::dot(&edge1, &p);
+    if det.abs() < 1e-12 {
+      // Parallel to the triangle
+      return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let s: Vec3 = ray.origin - self.v0;
+    let u = Vec3::dot(&s, &p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+      return None;
+    }
+    let q: Vec3 = Vec3::cross(&s, &edge1);
+    let v = Vec3::dot(&ray.direction, &q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+      return None;
+    }
+
+    let t = Vec3::dot(&edge2, &q) * inv_det;
+    if t <= t_min || t >= t_max {
+      return None;
+    }
+    let outward_normal = Vec3::cross(&edge1, &edge2).unit();
+    let mut record = HitRecord::new(ray.at(t), outward_normal, t, &self.material);
+    record.set_face_normal(ray, outward_normal);
+    // Barycentric coordinates of the hit point
+    record.u = u;
+    record.v = v;
+    record.dpdu = edge1;
+    record.dpdv = edge2;
+    Some(record)
+  }
+
+  fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+    // Padded so axis-aligned triangles don't give flat boxes
+    let aabb = Aabb::from_points(&[self.v0, self.v1, self.v2]);
+    let padding = Vec3::new(1e-4, 1e-4, 1e-4);
+    Some(Aabb::new(aabb.min - padding, aabb.max + padding))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::materials::DefaultMaterial;
+
+  #[test]
+  fn test_hit() {
+    let triangle = Triangle::new(
+      Point3::new(0.0, 0.0, 0.0),
+      Point3::new(1.0, 0.0, 0.0),
+      Point3::new(0.0, 1.0, 0.0),
+      DefaultMaterial::new(),
+    );
+    let r = Ray::new(Point3::new(0.25, 0.25, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    let record = triangle.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert_eq!(record.t, 1.0);
+    assert_eq!(record.point, Point3::new(0.25, 0.25, 0.0));
+    assert!(record.front_face);
+    assert_eq!(record.normal, Vec3::new(0.0, 0.0, 1.0));
+
+    // From the back side
+    let r = Ray::new(Point3::new(0.25, 0.25, -1.0), Vec3::new(0.0, 0.0, 1.0));
+    let record = triangle.hit(&r, 0.001, f64::INFINITY).unwrap();
+    assert!(!record.front_face);
+    assert_eq!(record.normal, Vec3::new(0.0, 0.0, -1.0));
+
+    // Outside the edges
+    let r = Ray::new(Point3::new(0.75, 0.75, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(triangle.hit(&r, 0.001, f64::INFINITY).is_none());
+  }
+}
@@ -1,6 +1,9 @@
 pub mod camera;
 pub mod core;
 pub mod geometry;
+pub mod lights;
 pub mod materials;
 pub mod misc;
+pub mod scene;
+pub mod textures;
 pub mod utils;
\ No newline at end of file
@@ -0,0 +1,45 @@
+use crate::core::{
+  vec3::Vec3,
+};
+use super::{Color, Light, LightSample, Point3};
+
+/**
+ * Light at infinity arriving from a single direction, like the sun.
+ * `direction` is the direction the light travels in.
+ **/
+#[derive(Clone, Copy, Debug)]
+pub struct DirectionalLight {
+  pub direction: Vec3,
+  pub radiance: Color,
+}
+
+impl DirectionalLight {
+  pub fn new(direction: Vec3, radiance: Color) -> Self {
+    Self { direction: direction.unit(), radiance }
+  }
+}
+
+impl Light for DirectionalLight {
+  fn illuminate(&self, _point: &Point3) -> Option<LightSample> {
+    Some(LightSample {
+      direction: -self.direction,
+      distance: f64::INFINITY,
+      radiance: self.radiance,
+    })
+  }
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_sun() {
+    let sun = DirectionalLight::new(Vec3::new(0.0, -2.0, 0.0), Color::new(3.0, 3.0, 3.0));
+    let sample = sun.illuminate(&Point3::new(100.0, 5.0, -7.0)).unwrap();
+    assert_eq!(sample.direction, Vec3::new(0.0, 1.0, 0.0));
+    assert_eq!(sample.distance, f64::INFINITY);
+    assert_eq!(sample.radiance, Color::new(3.0, 3.0, 3.0));
+  }
+}
@@ -0,0 +1,30 @@
+pub mod point;
+pub mod spot;
+pub mod directional;
+
+pub use crate::core::{
+  vec3::Vec3,
+  point3::Point3,
+  color::Color,
+};
+
+/**
+ * What a light delivers to a shaded point.
+ * `direction` is the unit vector from the point towards the light, `distance` is how far a
+ * shadow ray has to travel (INFINITY for lights at infinity) and `radiance` is the incident
+ * light arriving along `direction`.
+ **/
+#[derive(Clone, Copy, Debug)]
+pub struct LightSample {
+  pub direction: Vec3,
+  pub distance: f64,
+  pub radiance: Color,
+}
+
+/**
+ * An analytic light that can be sampled directly from any point in the scene.
+ * Returns None if the point receives nothing from the light (e.g. outside a spot cone).
+ **/
+pub trait Light: Send + Sync {
+  fn illuminate(&self, point: &Point3) -> Option<LightSample>;
+}
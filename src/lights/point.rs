@@ -0,0 +1,47 @@
+use super::{Color, Light, LightSample, Point3};
+
+/**
+ * Isotropic point light, radiance falls off with the squared distance.
+ **/
+#[derive(Clone, Copy, Debug)]
+pub struct PointLight {
+  pub position: Point3,
+  pub intensity: Color,
+}
+
+impl PointLight {
+  pub fn new(position: Point3, intensity: Color) -> Self {
+    Self { position, intensity }
+  }
+}
+
+impl Light for PointLight {
+  fn illuminate(&self, point: &Point3) -> Option<LightSample> {
+    let to_light = self.position - *point;
+    let distance_square = to_light.length_square();
+    if distance_square == 0.0 || distance_square == f64::INFINITY {
+      return None;
+    }
+
+    Some(LightSample {
+      direction: to_light.unit(),
+      distance: distance_square.sqrt(),
+      radiance: self.intensity * (1.0 / distance_square),
+    })
+  }
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_inverse_square_falloff() {
+    let light = PointLight::new(Point3::new(0.0, 2.0, 0.0), Color::new(4.0, 4.0, 4.0));
+    let sample = light.illuminate(&Point3::new(0.0, 0.0, 0.0)).unwrap();
+    assert_eq!(sample.direction, Point3::new(0.0, 1.0, 0.0));
+    assert_eq!(sample.distance, 2.0);
+    assert_eq!(sample.radiance, Color::new(1.0, 1.0, 1.0));
+  }
+}
@@ -0,0 +1,100 @@
+use crate::core::{
+  vec3::Vec3,
+  degrees_to_radians,
+};
+use super::{Color, Light, LightSample, Point3};
+
+/**
+ * Point light restricted to a cone.
+ * The intensity is full inside `falloff_start` degrees from the axis and fades smoothly to zero
+ * at `total_width` degrees.
+ **/
+#[derive(Clone, Copy, Debug)]
+pub struct SpotLight {
+  pub position: Point3,
+  pub direction: Vec3,
+  pub intensity: Color,
+  cos_total_width: f64,
+  cos_falloff_start: f64,
+}
+
+impl SpotLight {
+  pub fn new(
+    position: Point3,
+    lookat: Point3,
+    intensity: Color,
+    total_width: f64,
+    falloff_start: f64,
+  ) -> Self {
+    let falloff_start = falloff_start.min(total_width);
+    Self {
+      position,
+      direction: (lookat - position).unit(),
+      intensity,
+      cos_total_width: degrees_to_radians(total_width).cos(),
+      cos_falloff_start: degrees_to_radians(falloff_start).cos(),
+    }
+  }
+
+  /**
+   * Angular attenuation for a direction leaving the light, smoothstep between the two cones.
+   **/
+  pub fn falloff(&self, w: &Vec3) -> f64 {
+    let cos_theta = Vec3::dot(w, &self.direction);
+    if cos_theta < self.cos_total_width {
+      return 0.0;
+    }
+    if cos_theta >= self.cos_falloff_start {
+      return 1.0;
+    }
+    let x = (cos_theta - self.cos_total_width) / (self.cos_falloff_start - self.cos_total_width);
+    x * x * (3.0 - 2.0 * x)
+  }
+}
+
+impl Light for SpotLight {
+  fn illuminate(&self, point: &Point3) -> Option<LightSample> {
+    let to_light = self.position - *point;
+    let distance_square = to_light.length_square();
+    if distance_square == 0.0 {
+      return None;
+    }
+    let direction = to_light.unit();
+    let falloff = self.falloff(&-direction);
+    if falloff <= 0.0 {
+      return None;
+    }
+
+    Some(LightSample {
+      direction,
+      distance: distance_square.sqrt(),
+      radiance: self.intensity * (falloff / distance_square),
+    })
+  }
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_cone() {
+    let light = SpotLight::new(
+      Point3::new(0.0, 1.0, 0.0),
+      Point3::new(0.0, 0.0, 0.0),
+      Color::new(1.0, 1.0, 1.0),
+      30.0,
+      20.0,
+    );
+    // On the axis
+    let center = light.illuminate(&Point3::new(0.0, 0.0, 0.0)).unwrap();
+    assert_eq!(center.radiance, Color::new(1.0, 1.0, 1.0));
+    // 45 degrees off the axis, outside the cone
+    assert!(light.illuminate(&Point3::new(1.0, 0.0, 0.0)).is_none());
+    // Inside the falloff band
+    let edge = degrees_to_radians(25.0).tan();
+    let falloff = light.falloff(&Vec3::new(edge, -1.0, 0.0).unit());
+    assert!(falloff > 0.0 && falloff < 1.0);
+  }
+}
@@ -26,6 +26,75 @@ fn main() {
   // Chapter 11: Move camera
   // samples::ray_to_scene_camera()
 
+  // Analytic point, spot and directional lights
+  // samples::lights_scene();
+
+  // Constant density volumes: smoke and fog
+  // samples::volume_scene();
+
+  // Heterogeneous volumes from density grids
+  // samples::grid_volume_scene();
+
+  // Spectral rendering with dispersion
+  // samples::spectral_scene();
+
+  // Colored glass with Beer-Lambert absorption
+  // samples::colored_glass_scene();
+
+  // Soap bubbles and thin-film coatings
+  // samples::thin_film_scene();
+
+  // Mixed and clearcoated materials
+  // samples::layered_scene();
+
+  // Subsurface scattering
+  // samples::subsurface_scene();
+
+  // Alpha masked cutouts
+  // samples::cutout_scene();
+
+  // Normal and bump mapping
+  // samples::bump_scene();
+
+  // Constructive solid geometry
+  // samples::csg_scene();
+
+  // Analytic shapes in a bounding volume hierarchy
+  // samples::shapes_scene();
+
+  // Sphere traced distance fields: fractal, smooth blend and a closure
+  // samples::sdf_scene();
+
+  // Procedural terrain from a height map
+  // samples::heightfield_scene();
+
+  // Fur and grass from Bezier curves
+  // samples::hair_scene();
+
+  // Perspective, orthographic, fisheye and equirectangular cameras
+  // samples::projections_scene();
+
+  // Multi-element lens: focus, bokeh and vignetting from the optics
+  // samples::realistic_lens_scene();
+
+  // Hexagonal and heart shaped bokeh, cat's eye vignetting
+  // samples::bokeh_scene();
+
+  // Keyframed turntable with motion blur, written as numbered frames
+  // samples::turntable_scene();
+
+  // Keyframed objects blurred by a shutter open over their motion
+  // samples::motion_blur_scene();
+
+  // Side by side stereo pair, and a top/bottom ODS panorama written to ods.png
+  // samples::stereo_scene();
+
+  // Equivalent exposures at f/2 and f/16, then f/8 at the shutter speed of f/16
+  // samples::exposure_scene();
+
+  // Noisy preview with a detail rendered again over it, and alone in crop.png
+  // samples::crop_scene();
+
   // Chapter 12: Final scene
   samples::final_scene()
 }
\ No newline at end of file
@@ -0,0 +1,130 @@
+use crate::core::{
+  color::Color,
+  vec3::Vec3,
+  ray::Ray,
+};
+use crate::geometry::hit::{HitRecord};
+use super::{Material, ScatterRecord};
+use crate::utils::utils::{random_double, Fresnel};
+
+/**
+ * Clear dielectric coat (varnish, car paint) over any base material.
+ * Light is reflected by the coat following the Fresnel reflectance, otherwise it goes through
+ * to the base, and what the base scatters back out is attenuated by the coat transmittance.
+ * The coat is thin: it doesn't bend rays nor absorb light. Over a transparent base (e.g.
+ * `Dielectric`) the coat reflection stands for the base one, light it lets through enters the
+ * base without being reflected again (see `Material::refract`).
+ **/
+pub struct CoatedMaterial<M: Material> {
+  pub base: M,
+  pub ior: f64,
+  pub fresnel: Fresnel,
+}
+
+impl<M: Material> CoatedMaterial<M> {
+  pub fn new(base: M, ior: f64) -> Self {
+    CoatedMaterial {
+      base,
+      ior,
+      fresnel: Fresnel::Schlick,
+    }
+  }
+
+  /**
+   * Fraction of light going through the coat along a direction, given its cosine to the normal.
+   **/
+  pub fn transmittance(&self, cosine: f64) -> f64 {
+    1.0 - self.fresnel.reflectance(cosine.abs().min(1.0), 1.0 / self.ior)
+  }
+}
+
+impl<M: Material> Material for CoatedMaterial<M> {
+  fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
+    if !record.front_face {
+      // The coat is on the outside
+      return self.base.scatter(r_in, record);
+    }
+    let unit_direction: Vec3 = r_in.direction.unit();
+    let cos_theta = Vec3::dot(&(-unit_direction), &record.normal);
+
+    if random_double() >= self.transmittance(cos_theta) {
+      return Some(ScatterRecord {
+        attenuation: Color::new(1.0, 1.0, 1.0),
+        scattered: r_in.scattered(record.point, Vec3::reflect(unit_direction, record.normal)),
+      });
+    }
+    let transmitted = self.base.refract(r_in, record).or_else(|| self.base.scatter(r_in, record));
+    transmitted.map(|scattered| {
+      let cos_out = Vec3::dot(&scattered.scattered.direction.unit(), &record.normal);
+      // Rays refracted into the base don't cross the coat again
+      let weight = if cos_out > 0.0 { self.transmittance(cos_out) } else { 1.0 };
+      ScatterRecord {
+        attenuation: scattered.attenuation * weight,
+        scattered: scattered.scattered,
+      }
+    })
+  }
+
+  fn eval(&self, r_in: &Ray, record: &HitRecord, direction: &Vec3) -> Option<Color> {
+    let f = self.base.eval(r_in, record, direction)?;
+    if !record.front_face {
+      return Some(f);
+    }
+    let cos_in = Vec3::dot(&(-r_in.direction.unit()), &record.normal);
+    let cos_out = Vec3::dot(direction, &record.normal);
+    Some(f * (self.transmittance(cos_in) * self.transmittance(cos_out)))
+  }
+
+  fn emitted(&self, r_in: &Ray, record: &HitRecord) -> Color {
+    self.base.emitted(r_in, record)
+  }
+
+  fn is_dispersive(&self) -> bool {
+    self.base.is_dispersive()
+  }
+
+  fn is_transmissive(&self) -> bool {
+    self.base.is_transmissive()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::materials::lambertian::Lambertian;
+
+  #[test]
+  fn test_eval() {
+    let base = Lambertian { albedo: Color::new(1.0, 1.0, 1.0) };
+    let coated = CoatedMaterial::new(Lambertian { albedo: Color::new(1.0, 1.0, 1.0) }, 1.5);
+    let material = crate::materials::DefaultMaterial::new();
+    let record = HitRecord::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 1.0, &material);
+    let r = Ray::new(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+
+    // 4% reflected by the coat on the way in and on the way out at normal incidence
+    let direction = Vec3::new(0.0, 0.0, 1.0);
+    let ratio = coated.eval(&r, &record, &direction).unwrap().r() / base.eval(&r, &record, &direction).unwrap().r();
+    assert!((ratio - 0.96 * 0.96).abs() < 1e-12);
+
+    // Grazing light is mostly reflected by the coat
+    let grazing = Vec3::new(0.999, 0.0, (1.0 - 0.999_f64 * 0.999).sqrt());
+    let ratio = coated.eval(&r, &record, &grazing).unwrap().r() / base.eval(&r, &record, &grazing).unwrap().r();
+    assert!(ratio < 0.96 * 0.8);
+  }
+
+  #[test]
+  fn test_dielectric_base() {
+    use crate::materials::dielectric::Dielectric;
+
+    // A coat over glass of the same index reflects 4% at normal incidence, not 4% + 96% * 4%
+    let coated = CoatedMaterial::new(Dielectric::new(1.5), 1.5);
+    let material = crate::materials::DefaultMaterial::new();
+    let record = HitRecord::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 1.0, &material);
+    let r = Ray::new(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    let n = 40000;
+    let reflected = (0..n)
+      .filter(|_| coated.scatter(&r, &record).unwrap().scattered.direction.z > 0.0)
+      .count();
+    assert!((reflected as f64 / n as f64 - 0.04).abs() < 0.006);
+  }
+}
@@ -7,46 +7,237 @@ use crate::geometry::hit::{HitRecord};
 use super::{Material, ScatterRecord};
 use crate::utils::utils::{random_double, schlick};
 
+// Fraunhofer d line, where the refractive index of glasses is usually quoted
+const LAMBDA_D: f64 = 587.56;
+
+/**
+ * How the refractive index varies with the wavelength.
+ * Wavelengths are given in micrometers in the coefficients, as in optical glass catalogs.
+ **/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dispersion {
+  None,
+  // n = a + b / lambda^2
+  Cauchy { a: f64, b: f64 },
+  // n^2 = 1 + sum(b_i * lambda^2 / (lambda^2 - c_i))
+  Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+  /**
+   * Refractive index at a wavelength in nm, None without dispersion.
+   **/
+  pub fn ref_idx(&self, wavelength: f64) -> Option<f64> {
+    let l = wavelength / 1000.0;
+    let l2 = l * l;
+    match self {
+      Dispersion::None => None,
+      Dispersion::Cauchy { a, b } => Some(a + b / l2),
+      Dispersion::Sellmeier { b, c } => {
+        let n2 = 1.0 + (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum::<f64>();
+        Some(n2.sqrt())
+      },
+    }
+  }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Dielectric {
+  // Refractive index used when rendering in RGB
   pub ref_idx: f64,
+  pub dispersion: Dispersion,
+  // Absorption coefficient per unit length traveled inside, black for clear glass
+  pub absorption: Color,
 }
 
 impl Dielectric {
   pub fn new(ri: f64) -> Self {
     Self {
       ref_idx: ri,
+      dispersion: Dispersion::None,
+      absorption: Color::new(0.0, 0.0, 0.0),
     }
   }
+
+  /**
+   * Colored glass: light is absorbed following the Beer-Lambert law, exp(-absorption * distance),
+   * so thick parts get darker than thin ones.
+   **/
+  pub fn new_with_absorption(ri: f64, absorption: Color) -> Self {
+    Self {
+      absorption,
+      ..Self::new(ri)
+    }
+  }
+
+  pub fn new_with_dispersion(dispersion: Dispersion) -> Self {
+    Self {
+      ref_idx: dispersion.ref_idx(LAMBDA_D).unwrap_or(1.0),
+      dispersion,
+      absorption: Color::new(0.0, 0.0, 0.0),
+    }
+  }
+
+  pub fn new_cauchy(a: f64, b: f64) -> Self {
+    Self::new_with_dispersion(Dispersion::Cauchy { a, b })
+  }
+
+  pub fn new_sellmeier(b: [f64; 3], c: [f64; 3]) -> Self {
+    Self::new_with_dispersion(Dispersion::Sellmeier { b, c })
+  }
+
+  /**
+   * Schott N-BK7 crown glass.
+   **/
+  pub fn bk7() -> Self {
+    Self::new_sellmeier(
+      [1.03961212, 0.231792344, 1.01046945],
+      [0.00600069867, 0.0200179144, 103.560653],
+    )
+  }
+
+  /**
+   * Diamond, strongly dispersive ("fire").
+   **/
+  pub fn diamond() -> Self {
+    Self::new_sellmeier([0.3306, 4.3356, 0.0], [0.1750 * 0.1750, 0.1060 * 0.1060, 0.0])
+  }
+
+  /**
+   * Refractive index seen by a ray, following its wavelength when rendering spectrally.
+   **/
+  pub fn ref_idx_for(&self, r_in: &Ray) -> f64 {
+    r_in.wavelength
+      .and_then(|wavelength| self.dispersion.ref_idx(wavelength))
+      .unwrap_or(self.ref_idx)
+  }
+
+  /**
+   * Beer-Lambert attenuation of a ray reaching the hit point. Only a ray hitting the surface from
+   * the inside (back face) has traveled through the medium, from its origin on the boundary.
+   **/
+  pub fn transmittance(&self, r_in: &Ray, record: &HitRecord) -> Color {
+    if record.front_face || self.absorption == Color::new(0.0, 0.0, 0.0) {
+      return Color::new(1.0, 1.0, 1.0);
+    }
+    let distance = record.t * r_in.direction.length();
+    (self.absorption * -distance).exp()
+  }
 }
 
 impl Material for Dielectric {
   fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
-    let etai_over_etat: f64 = if record.front_face { 1.0 / self.ref_idx } else { self.ref_idx };
+    let ref_idx = self.ref_idx_for(r_in);
+    let etai_over_etat: f64 = if record.front_face { 1.0 / ref_idx } else { ref_idx };
     let unit_direction: Vec3 = r_in.direction.unit();
 
     let cos_theta: f64 = Vec3::dot(&(-unit_direction), &record.normal).min(1.0);
     let sin_theta: f64 = (1.0 - cos_theta * cos_theta).sqrt();
-    
+
     let reflect_prob: f64 = schlick(cos_theta, etai_over_etat);
     let random_factor: f64 = random_double();
+    let attenuation = self.transmittance(r_in, record);
 
     if etai_over_etat * sin_theta > 1.0 || random_factor < reflect_prob {
       // Reflection
       let reflected: Vec3 = Vec3::reflect(unit_direction, record.normal);
       Some(ScatterRecord{
-        attenuation: Color::new(1.0, 1.0, 1.0),
-        scattered: Ray::new(record.point, reflected),
+        attenuation,
+        scattered: r_in.scattered(record.point, reflected),
       })
     } else {
       // Refraction
       let refracted: Vec3 = Vec3::refract(unit_direction, record.normal, etai_over_etat);
       Some(ScatterRecord {
-        attenuation: Color::new(1.0, 1.0, 1.0),
-        scattered: Ray::new(record.point, refracted),
+        attenuation,
+        scattered: r_in.scattered(record.point, refracted),
       })
     }
+  }
+
+  fn refract(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
+    let ref_idx = self.ref_idx_for(r_in);
+    let etai_over_etat: f64 = if record.front_face { 1.0 / ref_idx } else { ref_idx };
+    let unit_direction: Vec3 = r_in.direction.unit();
+    let cos_theta: f64 = Vec3::dot(&(-unit_direction), &record.normal).min(1.0);
+    let sin_theta: f64 = (1.0 - cos_theta * cos_theta).sqrt();
+    // Total internal reflection is all that is left when light can't get out
+    let direction = if etai_over_etat * sin_theta > 1.0 {
+      Vec3::reflect(unit_direction, record.normal)
+    } else {
+      Vec3::refract(unit_direction, record.normal, etai_over_etat)
+    };
+    Some(ScatterRecord {
+      attenuation: self.transmittance(r_in, record),
+      scattered: r_in.scattered(record.point, direction),
+    })
+  }
+
+  fn is_dispersive(&self) -> bool {
+    self.dispersion != Dispersion::None
+  }
+
+  fn is_transmissive(&self) -> bool {
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_dispersion() {
+    let bk7 = Dielectric::bk7();
+    assert!((bk7.ref_idx - 1.5168).abs() < 1e-3);
+    let diamond = Dielectric::diamond();
+    assert!((diamond.ref_idx - 2.417).abs() < 5e-3);
+
+    // Blue bends more than red
+    let blue = diamond.dispersion.ref_idx(450.0).unwrap();
+    let red = diamond.dispersion.ref_idx(650.0).unwrap();
+    assert!(blue > red);
 
-    
+    let cauchy = Dielectric::new_cauchy(1.5046, 0.00420);
+    assert!((cauchy.dispersion.ref_idx(500.0).unwrap() - (1.5046 + 0.00420 / 0.25)).abs() < 1e-12);
+    assert_eq!(Dispersion::None.ref_idx(500.0), None);
+
+    let mut r = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    assert_eq!(diamond.ref_idx_for(&r), diamond.ref_idx);
+    r.wavelength = Some(450.0);
+    assert_eq!(diamond.ref_idx_for(&r), blue);
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn test_absorption() {
+    let glass = Dielectric::new_with_absorption(1.5, Color::new(0.0, 0.5, 1.0));
+    let r = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0));
+    let material = crate::materials::DefaultMaterial::new();
+
+    // Leaving the glass after 4 units inside
+    let mut record = HitRecord::new(Vec3::new(4.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 2.0, &material);
+    record.set_face_normal(&r, Vec3::new(1.0, 0.0, 0.0));
+    assert_eq!(glass.transmittance(&r, &record), Color::new(1.0, (-2.0_f64).exp(), (-4.0_f64).exp()));
+
+    // Entering it, nothing traveled inside yet
+    record.set_face_normal(&r, Vec3::new(-1.0, 0.0, 0.0));
+    assert_eq!(glass.transmittance(&r, &record), Color::new(1.0, 1.0, 1.0));
+  }
+
+  #[test]
+  fn test_scattered_time() {
+    // Rays reflected or refracted at the surface stay at the time and wavelength they came with
+    let glass = Dielectric::new(1.5);
+    let material = crate::materials::DefaultMaterial::new();
+    let mut r = Ray::new_with_time(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.7);
+    r.wavelength = Some(550.0);
+    let record = HitRecord::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 1.0, &material);
+    let (mut reflected, mut refracted) = (0, 0);
+    while reflected == 0 || refracted == 0 {
+      let scattered = glass.scatter(&r, &record).unwrap().scattered;
+      assert_eq!(scattered.time, 0.7);
+      assert_eq!(scattered.wavelength, Some(550.0));
+      if scattered.direction.z > 0.0 { reflected += 1 } else { refracted += 1 }
+    }
+  }
+}
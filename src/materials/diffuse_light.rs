@@ -0,0 +1,30 @@
+use crate::core::{
+  color::Color,
+  ray::Ray,
+};
+use crate::geometry::hit::{HitRecord};
+use super::{Material, ScatterRecord};
+
+/**
+ * Emits light and absorbs everything arriving at it.
+ **/
+#[derive(Clone, Copy, Debug)]
+pub struct DiffuseLight {
+  pub emit: Color,
+}
+
+impl DiffuseLight {
+  pub fn new(emit: Color) -> Self {
+    Self { emit }
+  }
+}
+
+impl Material for DiffuseLight {
+  fn scatter(&self, _r_in: &Ray, _record: &HitRecord) -> Option<ScatterRecord> {
+    None
+  }
+
+  fn emitted(&self, _r_in: &Ray, _record: &HitRecord) -> Color {
+    self.emit
+  }
+}
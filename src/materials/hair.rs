@@ -0,0 +1,187 @@
+use crate::core::{
+  PI,
+  color::Color,
+  vec3::Vec3,
+  ray::Ray,
+};
+use crate::geometry::hit::{HitRecord};
+use super::{Material, ScatterRecord};
+use crate::utils::utils::{random_double, random_double_in_range, clamp};
+
+// Resolution of the table normalizing the specular lobe
+const NORMALIZATION_STEPS: usize = 64;
+
+/**
+ * Kajiya-Kay model for hair and fur, lit along the tangent of the fiber (`dpdu` of the hit,
+ * as given by `Curve`) rather than a normal: a diffuse term following the sine to the tangent,
+ * and a specular highlight on the cone mirroring the view direction around the tangent.
+ * Both lobes are normalized over the sphere, so `diffuse` and `specular` are the fractions of the
+ * light each one scatters; they should sum to at most 1 per channel.
+ * Refer to: https://www.cs.drexel.edu/~deb39/Classes/Papers/p271-kajiya.pdf
+ **/
+pub struct KajiyaKay {
+  pub diffuse: Color,
+  pub specular: Color,
+  exponent: f64,
+  // Integral of the specular lobe over the sphere, by the angle of its cone to the tangent
+  normalization: [f64; NORMALIZATION_STEPS + 1],
+}
+
+impl KajiyaKay {
+  pub fn new(diffuse: Color, specular: Color, exponent: f64) -> Self {
+    let mut normalization = [0.0; NORMALIZATION_STEPS + 1];
+    let steps = 512;
+    for (i, value) in normalization.iter_mut().enumerate() {
+      let cone = PI * i as f64 / NORMALIZATION_STEPS as f64;
+      let d_theta = PI / steps as f64;
+      *value = (0..steps).map(|j| {
+        let theta = (j as f64 + 0.5) * d_theta;
+        (theta - cone).cos().max(0.0).powf(exponent) * theta.sin() * d_theta
+      }).sum::<f64>() * 2.0 * PI;
+    }
+    KajiyaKay { diffuse, specular, exponent, normalization }
+  }
+
+  /**
+   * Half the angular width of the specular cone sampled in `scatter`.
+   **/
+  fn lobe_width(&self) -> f64 {
+    0.001_f64.powf(1.0 / self.exponent).acos().min(PI / 2.0)
+  }
+
+  fn specular_normalization(&self, cone: f64) -> f64 {
+    let x = clamp(cone / PI, 0.0, 1.0) * NORMALIZATION_STEPS as f64;
+    let i = (x as usize).min(NORMALIZATION_STEPS - 1);
+    let f = x - i as f64;
+    self.normalization[i] * (1.0 - f) + self.normalization[i + 1] * f
+  }
+
+  /**
+   * Probability of sampling the specular cone rather than the whole sphere.
+   **/
+  fn cone_probability(&self) -> f64 {
+    let (d, s) = (self.diffuse.average(), self.specular.average());
+    if d + s > 0.0 { s / (d + s) } else { 0.0 }
+  }
+
+  /**
+   * Density of the directions sampled by `scatter` (over the solid angle).
+   **/
+  fn pdf(&self, tangent: &Vec3, cone: f64, direction: &Vec3) -> f64 {
+    let uniform = 1.0 / (4.0 * PI);
+    let theta = clamp(Vec3::dot(tangent, direction), -1.0, 1.0).acos();
+    let width = self.lobe_width();
+    let sin_theta = theta.sin();
+    let on_cone = if (theta - cone).abs() <= width && sin_theta > 0.0 {
+      1.0 / (2.0 * width * 2.0 * PI * sin_theta)
+    } else {
+      0.0
+    };
+    let p = self.cone_probability();
+    (1.0 - p) * uniform + p * on_cone
+  }
+}
+
+/**
+ * Unit tangent of the fiber, or any direction on the surface for shapes without one.
+ **/
+fn fiber_tangent(record: &HitRecord) -> Vec3 {
+  if record.dpdu.length_square() > 0.0 {
+    return record.dpdu.unit();
+  }
+  let helper = if record.normal.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+  Vec3::cross(&record.normal, &helper).unit()
+}
+
+/**
+ * Angle to the tangent of the specular cone for light seen from `r_in`.
+ **/
+fn mirror_cone(r_in: &Ray, tangent: &Vec3) -> f64 {
+  let cos_out = clamp(Vec3::dot(tangent, &(-r_in.direction.unit())), -1.0, 1.0);
+  PI - cos_out.acos()
+}
+
+impl Material for KajiyaKay {
+  fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
+    let tangent = fiber_tangent(record);
+    let cone = mirror_cone(r_in, &tangent);
+    let direction = if random_double() < self.cone_probability() {
+      let theta = cone + random_double_in_range(-self.lobe_width(), self.lobe_width());
+      if !(0.0..=PI).contains(&theta) {
+        // Off the sphere, a wasted sample
+        return None;
+      }
+      let phi = random_double_in_range(0.0, 2.0 * PI);
+      let helper = if tangent.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+      let b1 = Vec3::cross(&tangent, &helper).unit();
+      let b2 = Vec3::cross(&tangent, &b1);
+      theta.cos() * tangent + theta.sin() * (phi.cos() * b1 + phi.sin() * b2)
+    } else {
+      Vec3::random_unit_vec()
+    };
+
+    let f = self.eval(r_in, record, &direction)?;
+    let pdf = self.pdf(&tangent, cone, &direction);
+    if pdf <= 0.0 {
+      return None;
+    }
+    Some(ScatterRecord {
+      attenuation: f * (1.0 / pdf),
+      scattered: r_in.scattered(record.point, direction),
+    })
+  }
+
+  fn eval(&self, r_in: &Ray, record: &HitRecord, direction: &Vec3) -> Option<Color> {
+    let tangent = fiber_tangent(record);
+    let cone = mirror_cone(r_in, &tangent);
+    let theta = clamp(Vec3::dot(&tangent, direction), -1.0, 1.0).acos();
+    // The sine to the tangent integrates to PI^2 over the sphere
+    let diffuse = self.diffuse * (theta.sin() / (PI * PI));
+    let lobe = (theta - cone).cos().max(0.0).powf(self.exponent);
+    let specular = self.specular * (lobe / self.specular_normalization(cone));
+    Some(diffuse + specular)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::point3::Point3;
+  use crate::materials::DefaultMaterial;
+
+  fn mean_weight(hair: &KajiyaKay, n: usize) -> Color {
+    let material = DefaultMaterial::new();
+    let mut record = HitRecord::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 1.0, &material);
+    record.dpdu = Vec3::new(1.0, 0.0, 0.0);
+    let r_in = Ray::new(Point3::new(-1.0, 0.0, 2.0), Vec3::new(1.0, 0.0, -2.0));
+    let mut sum = Color::new(0.0, 0.0, 0.0);
+    for _ in 0..n {
+      if let Some(scattered) = hair.scatter(&r_in, &record) {
+        sum += scattered.attenuation;
+      }
+    }
+    sum * (1.0 / n as f64)
+  }
+
+  #[test]
+  fn test_energy() {
+    // Each lobe scatters the fraction of light it's given
+    let diffuse = KajiyaKay::new(Color::new(0.5, 0.5, 0.5), Color::new(0.0, 0.0, 0.0), 20.0);
+    assert!((mean_weight(&diffuse, 20000).r() - 0.5).abs() < 0.02);
+    let specular = KajiyaKay::new(Color::new(0.0, 0.0, 0.0), Color::new(0.8, 0.8, 0.8), 50.0);
+    assert!((mean_weight(&specular, 20000).r() - 0.8).abs() < 0.05);
+  }
+
+  #[test]
+  fn test_highlight() {
+    // The highlight is on the cone mirroring the view around the tangent
+    let hair = KajiyaKay::new(Color::new(0.1, 0.1, 0.1), Color::new(0.5, 0.5, 0.5), 50.0);
+    let material = DefaultMaterial::new();
+    let mut record = HitRecord::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 1.0, &material);
+    record.dpdu = Vec3::new(1.0, 0.0, 0.0);
+    let r_in = Ray::new(Point3::new(-1.0, 0.0, 1.0), Vec3::new(1.0, 0.0, -1.0));
+    let mirror = hair.eval(&r_in, &record, &Vec3::new(1.0, 1.0, 0.0).unit()).unwrap();
+    let off = hair.eval(&r_in, &record, &Vec3::new(-1.0, 1.0, 0.0).unit()).unwrap();
+    assert!(mirror.r() > 10.0 * off.r());
+  }
+}
@@ -0,0 +1,35 @@
+use crate::core::{
+  PI,
+  color::Color,
+  vec3::Vec3,
+  ray::Ray,
+};
+use crate::geometry::hit::{HitRecord};
+use super::{Material, ScatterRecord};
+
+/**
+ * Phase function of a participating medium scattering equally in all directions.
+ **/
+#[derive(Clone, Copy, Debug)]
+pub struct Isotropic {
+  pub albedo: Color,
+}
+
+impl Isotropic {
+  pub fn new(albedo: Color) -> Self {
+    Self { albedo }
+  }
+}
+
+impl Material for Isotropic {
+  fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
+    Some(ScatterRecord {
+      attenuation: self.albedo,
+      scattered: r_in.scattered(record.point, Vec3::random_unit_vec()),
+    })
+  }
+
+  fn eval(&self, _r_in: &Ray, _record: &HitRecord, _direction: &Vec3) -> Option<Color> {
+    Some(self.albedo * (1.0 / (4.0 * PI)))
+  }
+}
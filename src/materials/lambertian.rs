@@ -1,4 +1,5 @@
 use crate::core::{
+  PI,
   color::Color,
   vec3::Vec3,
   ray:: Ray,
@@ -11,12 +12,17 @@ pub struct Lambertian {
 }
 
 impl Material for Lambertian {
-  fn scatter(&self, _r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
+  fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
     let scattered_direction = record.normal + Vec3::random_unit_vec();
     
     Some(ScatterRecord {
       attenuation: self.albedo,
-      scattered: Ray::new(record.point, scattered_direction),
+      scattered: r_in.scattered(record.point, scattered_direction),
     })
   }
+
+  fn eval(&self, _r_in: &Ray, record: &HitRecord, direction: &Vec3) -> Option<Color> {
+    let cos_theta = Vec3::dot(&record.normal, direction).max(0.0);
+    Some(self.albedo * (cos_theta / PI))
+  }
 }
\ No newline at end of file
@@ -25,7 +25,7 @@ impl Metal {
 impl Material for Metal {
   fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
     let reflected = Vec3::reflect(r_in.direction.unit(), record.normal);
-    let scattered = Ray::new(record.point, reflected + self.fuzz * Vec3::random_in_unit_sphere());
+    let scattered = r_in.scattered(record.point, reflected + self.fuzz * Vec3::random_in_unit_sphere());
     if Vec3::dot(&scattered.direction, &record.normal) > 0.0 {
       Some(ScatterRecord{
         attenuation: self.albedo,
@@ -0,0 +1,101 @@
+use crate::core::{
+  color::Color,
+  vec3::Vec3,
+  ray::Ray,
+};
+use crate::geometry::hit::{HitRecord};
+use crate::textures::{Texture, SolidColor};
+use super::{Material, ScatterRecord};
+use crate::utils::utils::{random_double, clamp};
+
+/**
+ * Blend of two materials: each hit scatters with `second` with a probability given by the mask
+ * (its gray level, 0 is all `first`, 1 all `second`), and with `first` otherwise.
+ * Choosing one at random keeps the `ScatterRecord` of the picked material as is.
+ **/
+pub struct MixMaterial<A: Material, B: Material, T: Texture = SolidColor> {
+  pub first: A,
+  pub second: B,
+  pub mask: T,
+}
+
+impl<A: Material, B: Material> MixMaterial<A, B> {
+  pub fn new(first: A, second: B, weight: f64) -> Self {
+    Self::new_with_mask(first, second, SolidColor::new_gray(weight))
+  }
+}
+
+impl<A: Material, B: Material, T: Texture> MixMaterial<A, B, T> {
+  pub fn new_with_mask(first: A, second: B, mask: T) -> Self {
+    MixMaterial { first, second, mask }
+  }
+
+  /**
+   * Weight of the second material at the hit point.
+   **/
+  pub fn weight(&self, record: &HitRecord) -> f64 {
+    clamp(self.mask.value(record.u, record.v, &record.point).average(), 0.0, 1.0)
+  }
+}
+
+impl<A: Material, B: Material, T: Texture> Material for MixMaterial<A, B, T> {
+  fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
+    if random_double() < self.weight(record) {
+      self.second.scatter(r_in, record)
+    } else {
+      self.first.scatter(r_in, record)
+    }
+  }
+
+  fn eval(&self, r_in: &Ray, record: &HitRecord, direction: &Vec3) -> Option<Color> {
+    let w = self.weight(record);
+    match (self.first.eval(r_in, record, direction), self.second.eval(r_in, record, direction)) {
+      (None, None) => None,
+      (first, second) => {
+        let black = Color::new(0.0, 0.0, 0.0);
+        Some(first.unwrap_or(black) * (1.0 - w) + second.unwrap_or(black) * w)
+      },
+    }
+  }
+
+  fn emitted(&self, r_in: &Ray, record: &HitRecord) -> Color {
+    let w = self.weight(record);
+    self.first.emitted(r_in, record) * (1.0 - w) + self.second.emitted(r_in, record) * w
+  }
+
+  fn is_dispersive(&self) -> bool {
+    self.first.is_dispersive() || self.second.is_dispersive()
+  }
+
+  fn is_transmissive(&self) -> bool {
+    self.first.is_transmissive() || self.second.is_transmissive()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::materials::{lambertian::Lambertian, metal::Metal};
+
+  #[test]
+  fn test_eval() {
+    let red = Lambertian { albedo: Color::new(1.0, 0.0, 0.0) };
+    let blue = Lambertian { albedo: Color::new(0.0, 0.0, 1.0) };
+    let mix = MixMaterial::new(red, blue, 0.25);
+    let material = crate::materials::DefaultMaterial::new();
+    let record = HitRecord::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 1.0, &material);
+    let r = Ray::new(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    let direction = Vec3::new(0.0, 0.0, 1.0);
+
+    let f = mix.eval(&r, &record, &direction).unwrap();
+    let expected = Color::new(0.75, 0.0, 0.25) * (1.0 / crate::core::PI);
+    assert!((f.r() - expected.r()).abs() < 1e-12 && f.g() == 0.0 && (f.b() - expected.b()).abs() < 1e-12);
+
+    // A specular material has no lobe to evaluate, only the diffuse part remains
+    let half_metal = MixMaterial::new(Lambertian { albedo: Color::new(1.0, 1.0, 1.0) }, Metal::new(Color::new(1.0, 1.0, 1.0), 0.0), 0.5);
+    let f = half_metal.eval(&r, &record, &direction).unwrap();
+    assert!((f.r() - 0.5 / crate::core::PI).abs() < 1e-12);
+    let all_metal = MixMaterial::new(Metal::new(Color::new(1.0, 1.0, 1.0), 0.0), Metal::new(Color::new(1.0, 1.0, 1.0), 0.0), 0.5);
+    assert!(all_metal.eval(&r, &record, &direction).is_none());
+  }
+}
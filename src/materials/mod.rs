@@ -1,6 +1,13 @@
 pub mod lambertian;
 pub mod metal;
 pub mod dielectric;
+pub mod isotropic;
+pub mod diffuse_light;
+pub mod thin_film;
+pub mod mix;
+pub mod coated;
+pub mod normal_map;
+pub mod hair;
 
 pub use crate::core::{
   vec3::Vec3,
@@ -13,6 +20,45 @@ pub use crate::geometry::{
 
 pub trait Material: Send + Sync {
   fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord>;
+
+  /**
+   * Evaluate the scattering function (BRDF times the cosine term, or the phase function in a medium)
+   * for light arriving from `direction`, a unit vector pointing away from the hit point.
+   * Only materials with a non-specular lobe return a value, so analytic lights can be sampled directly.
+   **/
+  fn eval(&self, _r_in: &Ray, _record: &HitRecord, _direction: &Vec3) -> Option<Color> {
+    None
+  }
+
+  /**
+   * Light emitted from the hit point towards the incoming ray.
+   **/
+  fn emitted(&self, _r_in: &Ray, _record: &HitRecord) -> Color {
+    Color::new(0.0, 0.0, 0.0)
+  }
+
+  /**
+   * Light going through the surface with its reflection left out, for coatings over the material
+   * that already account for it. None for materials light doesn't pass through.
+   **/
+  fn refract(&self, _r_in: &Ray, _record: &HitRecord) -> Option<ScatterRecord> {
+    None
+  }
+
+  /**
+   * If the scattering depends on the wavelength of the ray (e.g. dispersion in glass),
+   * so spectral rendering can only follow the hero wavelength afterwards.
+   **/
+  fn is_dispersive(&self) -> bool {
+    false
+  }
+
+  /**
+   * If light passes through the material, e.g. glass.
+   **/
+  fn is_transmissive(&self) -> bool {
+    false
+  }
 }
 
 pub struct ScatterRecord {
@@ -26,6 +72,11 @@ impl DefaultMaterial {
     Self { }
   }
 }
+impl Default for DefaultMaterial {
+  fn default() -> Self {
+    Self::new()
+  }
+}
 impl Material for DefaultMaterial {
   fn scatter(&self, _r_in: &Ray, _record: &HitRecord) -> Option<ScatterRecord> {
     None
@@ -0,0 +1,255 @@
+use crate::core::{
+  color::Color,
+  vec3::Vec3,
+  ray::Ray,
+};
+use crate::geometry::hit::{HitRecord};
+use crate::textures::{Texture};
+use super::{Material, ScatterRecord};
+
+/**
+ * Orthonormal tangent and bitangent around a unit normal, following `dpdu` when the surface
+ * has a parameterization.
+ **/
+fn tangent_frame(record: &HitRecord, normal: &Vec3) -> (Vec3, Vec3) {
+  let mut tangent = record.dpdu - *normal * Vec3::dot(normal, &record.dpdu);
+  if tangent.length_square() < 1e-16 {
+    // Degenerate (poles, no parameterization): any direction perpendicular to the normal
+    let axis = if normal.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    tangent = Vec3::cross(&axis, normal);
+  }
+  let tangent = tangent.unit();
+  (tangent, Vec3::cross(normal, &tangent))
+}
+
+/**
+ * Copy of the hit record with the given outward shading normal, turned to face the incoming ray
+ * like the geometric one. The shading normal is bent if needed so the ray still arrives from above.
+ **/
+fn shading_record<'a>(r_in: &Ray, record: &HitRecord<'a>, outward_shading: Vec3) -> HitRecord<'a> {
+  let mut normal = if record.front_face { outward_shading } else { outward_shading.get_reversed() };
+  let wo = -r_in.direction.unit();
+  let cos_o = Vec3::dot(&normal, &wo);
+  if cos_o < 0.01 {
+    normal = (normal + wo * (0.01 - cos_o)).unit();
+  }
+  HitRecord { normal, ..*record }
+}
+
+/**
+ * A direction must be on the same side of the geometric and the shading surfaces,
+ * otherwise light would leak through the surface.
+ **/
+fn same_side(geometric: &Vec3, shading: &Vec3, direction: &Vec3) -> bool {
+  Vec3::dot(geometric, direction) * Vec3::dot(shading, direction) > 0.0
+}
+
+fn scatter_shaded<M: Material>(base: &M, r_in: &Ray, record: &HitRecord, shaded: &HitRecord) -> Option<ScatterRecord> {
+  base.scatter(r_in, shaded)
+    .filter(|scattered| same_side(&record.normal, &shaded.normal, &scattered.scattered.direction))
+}
+
+fn eval_shaded<M: Material>(base: &M, r_in: &Ray, record: &HitRecord, shaded: &HitRecord, direction: &Vec3) -> Option<Color> {
+  let f = base.eval(r_in, shaded, direction)?;
+  if same_side(&record.normal, &shaded.normal, direction) {
+    Some(f)
+  } else {
+    Some(Color::new(0.0, 0.0, 0.0))
+  }
+}
+
+/**
+ * Perturb the shading normal of a material from a tangent-space normal map:
+ * the red, green and blue channels map from [0, 1] to the x (along u), y (along v)
+ * and z (along the normal) coordinates, flat being (0.5, 0.5, 1).
+ **/
+pub struct NormalMapped<M: Material, T: Texture> {
+  pub base: M,
+  pub map: T,
+  // Scales the tilt of the normals, 1 for the map as is
+  pub strength: f64,
+}
+
+impl<M: Material, T: Texture> NormalMapped<M, T> {
+  pub fn new(base: M, map: T) -> Self {
+    NormalMapped { base, map, strength: 1.0 }
+  }
+
+  pub fn shading_normal(&self, record: &HitRecord) -> Vec3 {
+    let normal = record.outward_normal();
+    let (tangent, bitangent) = tangent_frame(record, &normal);
+    let c = self.map.value(record.u, record.v, &record.point);
+    let x = (2.0 * c.r() - 1.0) * self.strength;
+    let y = (2.0 * c.g() - 1.0) * self.strength;
+    let z = (2.0 * c.b() - 1.0).max(0.0);
+    let perturbed = tangent * x + bitangent * y + normal * z;
+    if perturbed.length_square() < 1e-16 { normal } else { perturbed.unit() }
+  }
+}
+
+impl<M: Material, T: Texture> Material for NormalMapped<M, T> {
+  fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
+    let shaded = shading_record(r_in, record, self.shading_normal(record));
+    scatter_shaded(&self.base, r_in, record, &shaded)
+  }
+
+  fn eval(&self, r_in: &Ray, record: &HitRecord, direction: &Vec3) -> Option<Color> {
+    let shaded = shading_record(r_in, record, self.shading_normal(record));
+    eval_shaded(&self.base, r_in, record, &shaded, direction)
+  }
+
+  fn emitted(&self, r_in: &Ray, record: &HitRecord) -> Color {
+    self.base.emitted(r_in, record)
+  }
+
+  fn is_dispersive(&self) -> bool {
+    self.base.is_dispersive()
+  }
+
+  fn is_transmissive(&self) -> bool {
+    self.base.is_transmissive()
+  }
+}
+
+/**
+ * Perturb the shading normal of a material as if the surface was displaced along its normal
+ * by a height texture (its gray level times `scale`), without moving the geometry.
+ * Refer to: https://en.wikipedia.org/wiki/Bump_mapping
+ **/
+pub struct BumpMapped<M: Material, T: Texture> {
+  pub base: M,
+  pub height: T,
+  pub scale: f64,
+}
+
+impl<M: Material, T: Texture> BumpMapped<M, T> {
+  pub fn new(base: M, height: T, scale: f64) -> Self {
+    BumpMapped { base, height, scale }
+  }
+
+  pub fn shading_normal(&self, record: &HitRecord) -> Vec3 {
+    let normal = record.outward_normal();
+    let (dpdu, dpdv) = if record.dpdu.length_square() > 0.0 && record.dpdv.length_square() > 0.0 {
+      (record.dpdu, record.dpdv)
+    } else {
+      tangent_frame(record, &normal)
+    };
+
+    // Finite differences of the height along u and v
+    let delta = 1e-4;
+    let height = |du: f64, dv: f64| {
+      let p = record.point + dpdu * du + dpdv * dv;
+      self.height.value(record.u + du, record.v + dv, &p).average() * self.scale
+    };
+    let h = height(0.0, 0.0);
+    let dhdu = (height(delta, 0.0) - h) / delta;
+    let dhdv = (height(0.0, delta) - h) / delta;
+
+    let perturbed = Vec3::cross(&(dpdu + normal * dhdu), &(dpdv + normal * dhdv));
+    if perturbed.length_square() < 1e-16 {
+      return normal;
+    }
+    let perturbed = perturbed.unit();
+    if Vec3::dot(&perturbed, &normal) < 0.0 { perturbed.get_reversed() } else { perturbed }
+  }
+}
+
+impl<M: Material, T: Texture> Material for BumpMapped<M, T> {
+  fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
+    let shaded = shading_record(r_in, record, self.shading_normal(record));
+    scatter_shaded(&self.base, r_in, record, &shaded)
+  }
+
+  fn eval(&self, r_in: &Ray, record: &HitRecord, direction: &Vec3) -> Option<Color> {
+    let shaded = shading_record(r_in, record, self.shading_normal(record));
+    eval_shaded(&self.base, r_in, record, &shaded, direction)
+  }
+
+  fn emitted(&self, r_in: &Ray, record: &HitRecord) -> Color {
+    self.base.emitted(r_in, record)
+  }
+
+  fn is_dispersive(&self) -> bool {
+    self.base.is_dispersive()
+  }
+
+  fn is_transmissive(&self) -> bool {
+    self.base.is_transmissive()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::point3::Point3;
+  use crate::materials::{DefaultMaterial, lambertian::Lambertian, metal::Metal};
+  use crate::textures::SolidColor;
+
+  struct Ramp;
+
+  impl Texture for Ramp {
+    fn value(&self, u: f64, _v: f64, _p: &Point3) -> Color {
+      Color::new(u, u, u)
+    }
+  }
+
+  fn plane_record<'a>(material: &'a DefaultMaterial, r: &Ray) -> HitRecord<'a> {
+    let mut record = HitRecord::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 1.0, material);
+    record.set_face_normal(r, Vec3::new(0.0, 0.0, 1.0));
+    record.dpdu = Vec3::new(1.0, 0.0, 0.0);
+    record.dpdv = Vec3::new(0.0, 1.0, 0.0);
+    record
+  }
+
+  #[test]
+  fn test_flat_maps() {
+    let material = DefaultMaterial::new();
+    let r = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    let record = plane_record(&material, &r);
+
+    let flat = NormalMapped::new(DefaultMaterial::new(), SolidColor::new(Color::new(0.5, 0.5, 1.0)));
+    assert!((flat.shading_normal(&record) - record.normal).length() < 1e-12);
+    let bump = BumpMapped::new(DefaultMaterial::new(), SolidColor::new_gray(0.7), 2.0);
+    assert!((bump.shading_normal(&record) - record.normal).length() < 1e-12);
+  }
+
+  #[test]
+  fn test_tilted_maps() {
+    let material = DefaultMaterial::new();
+    let r = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    let record = plane_record(&material, &r);
+
+    // Tilted towards +u
+    let tilted = NormalMapped::new(DefaultMaterial::new(), SolidColor::new(Color::new(1.0, 0.5, 1.0)));
+    let n = tilted.shading_normal(&record);
+    assert!((n - Vec3::new(1.0, 0.0, 1.0).unit()).length() < 1e-12);
+
+    // Height growing along u: the slope faces -u
+    let ramp = BumpMapped::new(DefaultMaterial::new(), Ramp, 1.0);
+    let n = ramp.shading_normal(&record);
+    assert!((n - Vec3::new(-1.0, 0.0, 1.0).unit()).length() < 1e-6);
+  }
+
+  #[test]
+  fn test_no_leak() {
+    let material = DefaultMaterial::new();
+    // Grazing ray on a surface tilted away from it
+    let r = Ray::new(Point3::new(-1.0, 0.0, 0.1), Vec3::new(1.0, 0.0, -0.1));
+    let record = plane_record(&material, &r);
+    let tilted = NormalMapped::new(Lambertian { albedo: Color::new(1.0, 1.0, 1.0) }, SolidColor::new(Color::new(1.0, 0.5, 0.6)));
+    let shaded = shading_record(&r, &record, tilted.shading_normal(&record));
+    assert!(Vec3::dot(&shaded.normal, &(-r.direction)) > 0.0);
+
+    let mirror = NormalMapped::new(Metal::new(Color::new(1.0, 1.0, 1.0), 0.0), SolidColor::new(Color::new(1.0, 0.5, 0.6)));
+    for _ in 0..100 {
+      if let Some(scattered) = mirror.scatter(&r, &record) {
+        assert!(scattered.scattered.direction.z > 0.0);
+      }
+      if let Some(scattered) = tilted.scatter(&r, &record) {
+        assert!(scattered.scattered.direction.z > 0.0);
+      }
+    }
+    let below = Vec3::new(0.0, 0.0, -1.0);
+    assert_eq!(tilted.eval(&r, &record, &below), Some(Color::new(0.0, 0.0, 0.0)));
+  }
+}
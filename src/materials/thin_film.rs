@@ -0,0 +1,278 @@
+use crate::core::{
+  PI,
+  color::Color,
+  vec3::Vec3,
+  ray::Ray,
+};
+use crate::geometry::hit::{HitRecord};
+use super::{Material, ScatterRecord};
+use crate::utils::utils::{random_double, schlick, fresnel_amplitudes, Fresnel};
+
+// Wavelengths (nm) standing for the red, green and blue channels when rendering in RGB
+const RGB_WAVELENGTHS: [f64; 3] = [630.0, 532.0, 465.0];
+
+/**
+ * Thin transparent film (soap, oil, lens coating) whose reflectance depends on the wavelength,
+ * due to the interference between the light reflected at its two interfaces.
+ * The film is in air, over a substrate of index `substrate_ior` (1.0 for a free film like a bubble).
+ *
+ * Used alone as a material it is a soap bubble: light is either reflected or passes straight
+ * through. Use `ThinFilmCoating` to put it over another material.
+ * Refer to: https://en.wikipedia.org/wiki/Thin-film_interference
+ **/
+#[derive(Clone, Copy, Debug)]
+pub struct ThinFilm {
+  // Thickness in nm
+  pub thickness: f64,
+  pub ior: f64,
+  pub substrate_ior: f64,
+  pub fresnel: Fresnel,
+}
+
+impl ThinFilm {
+  pub fn new(thickness: f64, ior: f64) -> Self {
+    Self::new_on_substrate(thickness, ior, 1.0)
+  }
+
+  pub fn new_on_substrate(thickness: f64, ior: f64, substrate_ior: f64) -> Self {
+    Self {
+      thickness,
+      ior,
+      substrate_ior,
+      fresnel: Fresnel::Schlick,
+    }
+  }
+
+  /**
+   * If the reflectance depends on the wavelength: interference needs a film of some thickness,
+   * with an index different from the media on both sides.
+   **/
+  pub fn varies_with_wavelength(&self) -> bool {
+    self.thickness > 0.0 && self.ior != 1.0 && self.ior != self.substrate_ior
+  }
+
+  /**
+   * Reflectance for light arriving from air with the given cosine to the normal, at a wavelength (nm).
+   **/
+  pub fn reflectance(&self, cosine: f64, wavelength: f64) -> f64 {
+    let (n0, nf, ns) = (1.0, self.ior, self.substrate_ior);
+    let cos_0 = cosine.abs().min(1.0);
+    let sin_0 = (1.0 - cos_0 * cos_0).sqrt();
+    let sin_f = sin_0 * n0 / nf;
+    if sin_f >= 1.0 {
+      return 1.0;
+    }
+    let cos_f = (1.0 - sin_f * sin_f).sqrt();
+    // Phase difference between the two reflected waves
+    let delta = 2.0 * PI / wavelength * 2.0 * nf * self.thickness * cos_f;
+
+    match self.fresnel {
+      Fresnel::Exact => {
+        let (rs_01, rp_01) = fresnel_amplitudes(cos_0, n0 / nf);
+        let (rs_12, rp_12) = fresnel_amplitudes(cos_f, nf / ns);
+        0.5 * (airy(rs_01, rs_12, delta) + airy(rp_01, rp_12, delta))
+      },
+      Fresnel::Schlick => {
+        // Schlick only gives the intensity, recover the amplitude with the sign of the phase shift.
+        // Each interface is evaluated with the cosine on its less dense side.
+        let sin_s = sin_0 * n0 / ns;
+        let cos_s = (1.0 - (sin_s * sin_s).min(1.0)).sqrt();
+        let r_01 = -schlick(cos_0, n0 / nf).sqrt();
+        let cos_12 = if ns < nf { cos_s } else { cos_f };
+        let r_12 = (nf - ns).signum() * schlick(cos_12, nf / ns).sqrt();
+        airy(r_01, r_12, delta)
+      },
+    }
+  }
+
+  /**
+   * Reflectance seen by a ray: at its wavelength when rendering spectrally, per channel otherwise.
+   **/
+  pub fn reflectance_for(&self, r_in: &Ray, cosine: f64) -> Color {
+    match r_in.wavelength {
+      Some(wavelength) => {
+        let r = self.reflectance(cosine, wavelength);
+        Color::new(r, r, r)
+      },
+      None => Color::new(
+        self.reflectance(cosine, RGB_WAVELENGTHS[0]),
+        self.reflectance(cosine, RGB_WAVELENGTHS[1]),
+        self.reflectance(cosine, RGB_WAVELENGTHS[2]),
+      ),
+    }
+  }
+}
+
+/**
+ * Reflectance of a film from the amplitude coefficients of its two interfaces,
+ * summing all the internal reflections (Airy formula).
+ **/
+fn airy(r_01: f64, r_12: f64, delta: f64) -> f64 {
+  let cross = 2.0 * r_01 * r_12 * delta.cos();
+  (r_01 * r_01 + r_12 * r_12 + cross) / (1.0 + r_01 * r_01 * r_12 * r_12 + cross)
+}
+
+/**
+ * Pick between the film reflection and the rest by the average reflectance,
+ * returns if reflected and the weight to apply.
+ **/
+fn choose_reflection(reflectance: Color) -> (bool, Color) {
+  let p = reflectance.average();
+  if random_double() < p {
+    (true, reflectance * (1.0 / p))
+  } else {
+    (false, (Color::new(1.0, 1.0, 1.0) - reflectance) * (1.0 / (1.0 - p)))
+  }
+}
+
+impl Material for ThinFilm {
+  fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
+    let unit_direction: Vec3 = r_in.direction.unit();
+    let cos_theta = Vec3::dot(&(-unit_direction), &record.normal);
+    let (reflected, weight) = choose_reflection(self.reflectance_for(r_in, cos_theta));
+
+    let direction = if reflected { Vec3::reflect(unit_direction, record.normal) } else { unit_direction };
+    Some(ScatterRecord {
+      attenuation: weight,
+      scattered: r_in.scattered(record.point, direction),
+    })
+  }
+
+  fn is_dispersive(&self) -> bool {
+    self.varies_with_wavelength()
+  }
+
+  fn is_transmissive(&self) -> bool {
+    true
+  }
+}
+
+/**
+ * Iridescent coating over another material, e.g. `Metal` or `Dielectric`.
+ * The film should use the index of the base as its substrate: its reflectance already includes
+ * the interface with the base, so light it lets through enters a transparent base without being
+ * reflected by it again (see `Material::refract`), and is scattered by an opaque base.
+ **/
+pub struct ThinFilmCoating<M: Material> {
+  pub film: ThinFilm,
+  pub base: M,
+}
+
+impl<M: Material> ThinFilmCoating<M> {
+  pub fn new(film: ThinFilm, base: M) -> Self {
+    Self { film, base }
+  }
+}
+
+impl<M: Material> Material for ThinFilmCoating<M> {
+  fn scatter(&self, r_in: &Ray, record: &HitRecord) -> Option<ScatterRecord> {
+    if !record.front_face {
+      // The coating is on the outside
+      return self.base.scatter(r_in, record);
+    }
+    let unit_direction: Vec3 = r_in.direction.unit();
+    let cos_theta = Vec3::dot(&(-unit_direction), &record.normal);
+    let (reflected, weight) = choose_reflection(self.film.reflectance_for(r_in, cos_theta));
+
+    if reflected {
+      Some(ScatterRecord {
+        attenuation: weight,
+        scattered: r_in.scattered(record.point, Vec3::reflect(unit_direction, record.normal)),
+      })
+    } else {
+      let transmitted = self.base.refract(r_in, record).or_else(|| self.base.scatter(r_in, record));
+      transmitted.map(|scattered| ScatterRecord {
+        attenuation: scattered.attenuation * weight,
+        scattered: scattered.scattered,
+      })
+    }
+  }
+
+  fn eval(&self, r_in: &Ray, record: &HitRecord, direction: &Vec3) -> Option<Color> {
+    let f = self.base.eval(r_in, record, direction)?;
+    if !record.front_face {
+      return Some(f);
+    }
+    let cos_theta = Vec3::dot(&(-r_in.direction.unit()), &record.normal);
+    Some(f * (Color::new(1.0, 1.0, 1.0) - self.film.reflectance_for(r_in, cos_theta)))
+  }
+
+  fn emitted(&self, r_in: &Ray, record: &HitRecord) -> Color {
+    self.base.emitted(r_in, record)
+  }
+
+  fn is_dispersive(&self) -> bool {
+    self.film.varies_with_wavelength() || self.base.is_dispersive()
+  }
+
+  fn is_transmissive(&self) -> bool {
+    self.base.is_transmissive()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::utils::utils::fresnel_dielectric;
+
+  #[test]
+  fn test_vanishing_film() {
+    // A film of zero thickness in air does not reflect at all
+    for fresnel in [Fresnel::Schlick, Fresnel::Exact].iter() {
+      let mut film = ThinFilm::new(0.0, 1.33);
+      film.fresnel = *fresnel;
+      for cosine in [1.0, 0.7, 0.2].iter() {
+        assert!(film.reflectance(*cosine, 500.0).abs() < 1e-9);
+      }
+    }
+  }
+
+  #[test]
+  fn test_single_interface() {
+    // A film of the same index as its substrate is just the substrate
+    let mut film = ThinFilm::new_on_substrate(300.0, 1.5, 1.5);
+    film.fresnel = Fresnel::Exact;
+    for cosine in [1.0, 0.5, 0.1].iter() {
+      let expected = fresnel_dielectric(*cosine, 1.0 / 1.5);
+      assert!((film.reflectance(*cosine, 550.0) - expected).abs() < 1e-9);
+    }
+  }
+
+  #[test]
+  fn test_anti_reflection_coating() {
+    // Quarter-wave layer of index sqrt(n) cancels the reflection at its design wavelength
+    let ior = 1.5_f64.sqrt();
+    let mut film = ThinFilm::new_on_substrate(550.0 / (4.0 * ior), ior, 1.5);
+    film.fresnel = Fresnel::Exact;
+    assert!(film.reflectance(1.0, 550.0) < 1e-9);
+    assert!(film.reflectance(1.0, 400.0) > 0.001);
+  }
+
+  #[test]
+  fn test_coating_over_glass() {
+    use crate::core::point3::Point3;
+    use crate::materials::{DefaultMaterial, dielectric::Dielectric};
+
+    // A film like its substrate leaves plain glass: reflected once, at its Fresnel reflectance
+    let film = ThinFilm::new_on_substrate(300.0, 1.5, 1.5);
+    let coated = ThinFilmCoating::new(film, Dielectric::new(1.5));
+    assert!(!coated.is_dispersive());
+    let material = DefaultMaterial::new();
+    let r = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    let mut record = HitRecord::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 1.0, &material);
+    record.set_face_normal(&r, Vec3::new(0.0, 0.0, 1.0));
+    let n = 20000;
+    let mut reflected = 0.0;
+    for _ in 0..n {
+      let scattered = coated.scatter(&r, &record).unwrap();
+      if scattered.scattered.direction.z > 0.0 {
+        reflected += scattered.attenuation.average();
+      }
+    }
+    // 0.04 at normal incidence, twice that when reflected by both the film and the glass
+    assert!((reflected / n as f64 - schlick(1.0, 1.0 / 1.5)).abs() < 0.01);
+
+    assert!(ThinFilmCoating::new(ThinFilm::new_on_substrate(300.0, 1.33, 1.5), Dielectric::new(1.5)).is_dispersive());
+    assert!(!ThinFilm::new(0.0, 1.33).is_dispersive());
+  }
+}
@@ -1,16 +1,39 @@
+use std::path::Path;
 use std::sync::Arc;
 use std::sync::mpsc;
 use std::thread;
 use std::time;
 use crate::core::{
+  PI,
+  quaternion::Quaternion,
   vec3::Vec3,
   point3::Point3,
   ray::Ray,
   color::Color,
-  PI,
+  image::Image,
+  spectrum::SampledWavelengths,
+  track::{Interpolation, Track},
+  transform::Transform,
 };
 use crate::geometry::{
   sphere::Sphere,
+  constant_medium::ConstantMedium,
+  grid_medium::{DensityGrid, GridMedium},
+  triangle::Triangle,
+  alpha_mask::AlphaMasked,
+  subsurface::Subsurface,
+  csg::Csg,
+  cylinder::Cylinder,
+  cone::Cone,
+  torus::Torus,
+  capsule::Capsule,
+  paraboloid::Paraboloid,
+  bvh::BvhNode,
+  aabb::Aabb,
+  sdf::{SdfShape, SdfSphere, RoundBox, SmoothUnion, Mandelbulb},
+  heightfield::{HeightMap, Heightfield},
+  curve::{Curve, CurveKind},
+  animated::Animated,
   hit::{HittableList}
 };
 use crate::materials::{
@@ -18,9 +41,26 @@ use crate::materials::{
   lambertian::Lambertian,
   metal::Metal,
   dielectric::Dielectric,
+  diffuse_light::DiffuseLight,
+  thin_film::{ThinFilm, ThinFilmCoating},
+  mix::MixMaterial,
+  coated::CoatedMaterial,
+  normal_map::{NormalMapped, BumpMapped},
+  hair::KajiyaKay,
+};
+use crate::textures::{
+  Texture,
+  SolidColor,
+  checker::Checker,
+};
+use crate::lights::{
+  point::PointLight,
+  spot::SpotLight,
+  directional::DirectionalLight,
 };
+use crate::scene::{Background, Scene};
 use crate::utils::utils;
-use crate::camera::Camera;
+use crate::camera::{AutoFocus, AnimatedCamera, Sequence, OdsCamera, StereoCamera, StereoLayout, Aperture, ApertureMask, Camera, PerspectiveCamera, OrthographicCamera, FisheyeCamera, EquirectangularCamera, Exposure, Lens, RealisticCamera, Region, render_image, render_into, render_region};
 
 const IMAGE_WIDTH: u32 =256;
 const IMAGE_HEIGHT: u32 =256;
@@ -39,6 +79,7 @@ pub enum AdvanceSceneCase {
   RefractionScene,
 }
 
+#[allow(clippy::unused_unit, clippy::print_with_newline)]
 pub fn output_image() -> () {
   // PPM meta
   println!("P3\n{0} {1}\n255", IMAGE_WIDTH, IMAGE_HEIGHT);
@@ -103,7 +144,7 @@ pub fn ray_to_scene_advance(scene: AdvanceSceneCase) {
   let image_width = 384;
   let image_height = (image_width as f64 / aspect_ratio) as u32;
   let samples_per_pixel = 100;
-  let camera = Camera::new();
+  let camera = PerspectiveCamera::new();
 
   println!("P3\n{0} {1}\n255\n", image_width, image_height);
 
@@ -120,7 +161,7 @@ pub fn ray_to_scene_advance(scene: AdvanceSceneCase) {
       for _s in 0..samples_per_pixel {
         let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
         let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
-        let r: Ray = camera.get_ray(u, v);
+        let (r, weight) = camera.get_ray(u, v);
         let depth: u32 = 50;
 
         let mut world = HittableList::new();
@@ -150,7 +191,7 @@ pub fn ray_to_scene_advance(scene: AdvanceSceneCase) {
           world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, DefaultMaterial::new()));        
         }
 
-        pixel_color += match scene {
+        pixel_color += weight * match scene {
           AdvanceSceneCase::AntialiasingScene => utils::world_ray_color(&r, &world),
           AdvanceSceneCase::MaterialScene => utils::material_ray_color(&r, &world, depth),
           AdvanceSceneCase::MetalScene => utils::metal_ray_color(&r, &world, depth),
@@ -169,15 +210,15 @@ pub fn ray_to_scene_camera() {
   let image_height = (image_width as f64 / aspect_ratio) as u32;
   let samples_per_pixel = 100;
 
-  let radius = (PI / 4.0).cos();
   let mut world = HittableList::new();
 
   // Simple scene
+  // let radius = (PI / 4.0).cos();
   // let material_left  = Lambertian { albedo: Color::new(0.0,0.0,1.0) };
   // let material_right = Lambertian { albedo: Color::new(1.0,0.0,0.0) };
   // world.add(Sphere::new(Point3::new(-radius, 0.0, -1.0), radius, material_left));
   // world.add(Sphere::new(Point3::new(radius, 0.0, -1.0), radius, material_right));
-  // let camera = Camera::new_param(90.0, aspect_ratio);
+  // let camera = PerspectiveCamera::new_param(90.0, aspect_ratio);
 
   // Free camera scene
   let material_ground = Lambertian { albedo: Color::new(0.8, 0.8, 0.0) };
@@ -195,15 +236,140 @@ pub fn ray_to_scene_camera() {
   let lookfrom = Point3::new(3.0, 3.0, 2.0);  // Camera poition for defocus cases
   let lookat = Point3::new(0.0, 0.0, -1.0);
   
-  let camera = Camera::new_with_lens(
-    lookfrom, 
-    lookat, 
-    Vec3::new(0.0, 1.0, 0.0), 
-    // 90.0,  // Wide angle lens
-    20.0,  // Long focal lens
+  // Focused on the front of the sphere in the middle of the frame, not on its center
+  let camera = PerspectiveCamera::builder()
+    .look_from(lookfrom)
+    .look_at(lookat)
+    .vup(Vec3::new(0.0, 1.0, 0.0))
+    // .vfov(90.0)  // Wide angle lens
+    .vfov(20.0)  // Long focal lens
+    .aspect(aspect_ratio)
+    .aperture(2.0)
+    .autofocus(&world, &AutoFocus::default())
+    .and_then(|builder| builder.build())
+    .expect("invalid camera");
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        let depth: u32 = 50;
+        
+        pixel_color += weight * utils::metal_ray_color(&r, &world, depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+// Analytic lights: point, spot and sun with shadow rays
+pub fn lights_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 50;
+  let max_depth = 20;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.8, 0.8, 0.8) };
+  let material_center = Lambertian { albedo: Color::new(0.7, 0.3, 0.3) };
+  let material_left = Dielectric::new(1.5);
+  let material_right = Metal::new(Color::new(0.8, 0.6, 0.2), 0.1);
+
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, material_center));
+  world.add(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, material_left));
+  world.add(Sphere::new(Point3::new( 1.0, 0.0, -1.0), 0.5, material_right));
+
+  let mut scene = Scene::new(world);
+  scene.background = Background::Solid(Color::new(0.02, 0.02, 0.03));
+  // Shadows of the glass ball are skipped, there are no caustics to fill them anyway
+  scene.ignore_transmissive_shadows = true;
+  scene.add_light(PointLight::new(Point3::new(-2.0, 2.0, 0.5), Color::new(6.0, 5.0, 4.0)));
+  scene.add_light(SpotLight::new(
+    Point3::new(1.5, 3.0, 0.0),
+    Point3::new(0.0, -0.5, -1.0),
+    Color::new(12.0, 12.0, 16.0),
+    25.0,
+    15.0,
+  ));
+  scene.add_light(DirectionalLight::new(Vec3::new(1.0, -1.0, -0.5), Color::new(0.3, 0.25, 0.2)));
+
+  let lookfrom = Point3::new(0.0, 1.5, 3.0);
+  let lookat = Point3::new(0.0, 0.0, -1.0);
+  let camera = PerspectiveCamera::new_with_lens(
+    lookfrom,
+    lookat,
+    Vec3::new(0.0, 1.0, 0.0),
+    40.0,
     aspect_ratio,
+    0.0,
+    (lookfrom - lookat).length(),
+  );
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::lit_ray_color(&r, &scene, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+// Participating media: a smoke ball, a blob of thick white fog and thin mist over everything
+pub fn volume_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.8, 0.8, 0.0) };
+  let material_right = Metal::new(Color::new(0.8, 0.6, 0.2), 0.0);
+
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(ConstantMedium::new(
+    Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, DefaultMaterial::new()),
     2.0,
-    (lookfrom-lookat).length(),
+    Color::new(0.1, 0.1, 0.1),
+  ));
+  world.add(ConstantMedium::new(
+    Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, DefaultMaterial::new()),
+    20.0,
+    Color::new(0.9, 0.9, 0.9),
+  ));
+  world.add(Sphere::new(Point3::new( 1.0, 0.0, -1.0), 0.5, material_right));
+  world.add(ConstantMedium::new(
+    Sphere::new(Point3::new(0.0, 0.0, 0.0), 50.0, DefaultMaterial::new()),
+    0.02,
+    Color::new(1.0, 1.0, 1.0),
+  ));
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 0.5, 2.0),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    40.0,
+    aspect_ratio,
   );
 
   println!("P3\n{0} {1}\n255\n", image_width, image_height);
@@ -216,11 +382,8 @@ pub fn ray_to_scene_camera() {
       for _s in 0..samples_per_pixel {
         let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
         let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
-        // let r: Ray = camera.get_ray(u, v);  // No defocus blur 
-        let r: Ray = camera.get_ray_for_lens(u, v);  // With defocus blur
-        let depth: u32 = 50;
-        
-        pixel_color += utils::metal_ray_color(&r, &world, depth);
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
       }
       pixel_color.write_color_gamma_corrected(samples_per_pixel);
     }
@@ -228,31 +391,1221 @@ pub fn ray_to_scene_camera() {
 }
 
 
-// Final scene
-pub fn final_scene() {
-  const N_THREAD: usize = 20;  // Concurrent thread amount
-  let aspect_ratio = 3.0 / 2.0;
-  let image_width = 1200;
+// Heterogeneous volumes: a cloud made of puffs and a glowing explosion with a hot core
+pub fn grid_volume_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
   let image_height = (image_width as f64 / aspect_ratio) as u32;
-  let samples_per_pixel = 500;
+  let samples_per_pixel = 100;
   let max_depth = 50;
 
-  let world = Arc::new(utils::random_scene());
+  // A few random puffs with soft edges
+  let puffs: Vec<(Point3, f64)> = (0..12)
+    .map(|_| (
+      Point3::new(
+        utils::random_double_in_range(0.3, 0.7),
+        utils::random_double_in_range(0.3, 0.5),
+        utils::random_double_in_range(0.3, 0.7),
+      ),
+      utils::random_double_in_range(0.12, 0.25),
+    ))
+    .collect();
+  let cloud = DensityGrid::from_fn(48, 48, 48, |p| {
+    puffs.iter()
+      .map(|(center, radius)| (1.0 - (p - *center).length() / radius).max(0.0))
+      .fold(0.0, f64::max)
+  });
+  let explosion = DensityGrid::from_fn(32, 32, 32, |p| {
+    let r = (p - Point3::new(0.5, 0.5, 0.5)).length();
+    (1.0 - 2.0 * r).max(0.0)
+  });
 
-  let lookfrom = Point3::new(5.0, 2.0, 1.0);  // Camera poition for defocus cases
-  let lookat = Point3::new(-2.0, 0.0, 0.0);
-  
-  let camera = Camera::new_with_lens(
-    lookfrom, 
-    lookat, 
-    Vec3::new(0.0, 1.0, 0.0), 
-    // 90.0,  // Wide angle lens
-    20.0,  // Long focal lens
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.5, 0.5, 0.5) };
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(GridMedium::new(
+    cloud,
+    Transform::translate(Vec3::new(-0.5, -0.5, -0.5))
+      .then(&Transform::scale(Vec3::new(2.0, 1.5, 2.0)))
+      .then(&Transform::translate(Vec3::new(-0.8, 0.4, -1.5))),
+    Color::new(0.05, 0.05, 0.05),
+    Color::new(12.0, 12.0, 12.0),
+    Color::new(0.0, 0.0, 0.0),
+  ));
+  world.add(GridMedium::new(
+    explosion,
+    Transform::translate(Vec3::new(-0.5, -0.5, -0.5))
+      .then(&Transform::rotate(Vec3::new(0.0, 1.0, 0.0), 30.0))
+      .then(&Transform::translate(Vec3::new(1.0, 0.0, -1.0))),
+    Color::new(2.0, 4.0, 8.0),
+    Color::new(4.0, 4.0, 4.0),
+    Color::new(4.0, 1.5, 0.3),
+  ));
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 0.5, 2.0),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    50.0,
+    aspect_ratio,
+  );
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::volume_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+/**
+ * Add a triangular prism lying along the x axis, apex up, built from triangles.
+ **/
+fn add_prism(world: &mut HittableList, center: Point3, length: f64, side: f64, material: Dielectric) {
+  let height = side * 3.0_f64.sqrt() / 2.0;
+  let corners = |x: f64| [
+    center + Vec3::new(x, 2.0 * height / 3.0, 0.0),
+    center + Vec3::new(x, -height / 3.0, side / 2.0),
+    center + Vec3::new(x, -height / 3.0, -side / 2.0),
+  ];
+  let (left, right) = (corners(-length / 2.0), corners(length / 2.0));
+
+  let mut faces = vec![[left[0], left[1], left[2]], [right[0], right[1], right[2]]];
+  for i in 0..3 {
+    let j = (i + 1) % 3;
+    faces.push([left[i], left[j], right[j]]);
+    faces.push([left[i], right[j], right[i]]);
+  }
+  for [a, b, c] in faces {
+    // Order the vertices so the normal points away from the center
+    let outward = Vec3::dot(&Vec3::cross(&(b - a), &(c - a)), &(a - center)) > 0.0;
+    if outward {
+      world.add(Triangle::new(a, b, c, material));
+    } else {
+      world.add(Triangle::new(a, c, b, material));
+    }
+  }
+}
+
+// Spectral rendering: dispersion in a flint glass prism and a diamond
+pub fn spectral_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 200;
+  let max_depth = 50;
+
+  // Schott SF11 dense flint glass
+  let flint = Dielectric::new_sellmeier(
+    [1.73759695, 0.313747346, 1.89878101],
+    [0.013188707, 0.0623068142, 155.23629],
+  );
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.7, 0.7, 0.7) };
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  add_prism(&mut world, Point3::new(-0.7, -0.1, -1.0), 1.2, 0.7, flint);
+  world.add(Sphere::new(Point3::new(0.8, 0.0, -1.0), 0.5, Dielectric::diamond()));
+  // A bright light to split, the edges of the horizon get rainbow fringes as well
+  world.add(Sphere::new(Point3::new(0.2, 1.5, -4.0), 0.2, DiffuseLight::new(Color::new(20.0, 20.0, 20.0))));
+
+  let scene = Scene::new(world);
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 0.4, 2.0),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    45.0,
+    aspect_ratio,
+  );
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        // Stratify the hero wavelengths over the pixel, dispersion leaves a single one per path
+        let stratum = (s as f64 + utils::random_double()) / samples_per_pixel as f64;
+        let mut wavelengths = SampledWavelengths::sample_visible(stratum);
+        let (mut r, weight) = camera.get_ray(u, v);
+        r.wavelength = Some(wavelengths.hero());
+        let radiance = utils::spectral_ray_color(&r, &scene, max_depth, &mut wavelengths);
+        pixel_color += weight * radiance.to_color(&wavelengths);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+// Colored glass: a solid ball gets darker than the thin shell of the hollow one
+pub fn colored_glass_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.8, 0.8, 0.8) };
+  let material_left = Dielectric::new_with_absorption(1.5, Color::new(4.0, 0.4, 4.0));
+  let material_center = Dielectric::new_with_absorption(1.5, Color::new(0.2, 1.2, 2.0));
+  let material_right = Dielectric::new_with_absorption(1.5, Color::new(4.0, 0.4, 4.0));
+
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, material_left));
+  world.add(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, material_center));
+  // The hollow sphere: an inverted inner sphere turns the ball into a thin shell
+  world.add(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, material_right));
+  world.add(Sphere::new(Point3::new(1.0, 0.0, -1.0), -0.45, material_right));
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 0.5, 2.0),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    40.0,
+    aspect_ratio,
+  );
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+// Soap bubbles and an iridescent coating
+pub fn thin_film_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.5, 0.5, 0.5) };
+  let bubble_thin = ThinFilm::new(350.0, 1.33);
+  let bubble_thick = ThinFilm::new(600.0, 1.33);
+  let coated_metal = ThinFilmCoating::new(
+    ThinFilm::new_on_substrate(400.0, 1.45, 2.5),
+    Metal::new(Color::new(0.2, 0.2, 0.25), 0.0),
+  );
+
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, bubble_thin));
+  world.add(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, coated_metal));
+  world.add(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, bubble_thick));
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 0.5, 2.0),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    40.0,
+    aspect_ratio,
+  );
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+// Materials blended by a checker mask and clearcoated
+pub fn layered_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  // Ground alternating between diffuse and polished tiles
+  let material_ground = MixMaterial::new_with_mask(
+    Lambertian { albedo: Color::new(0.5, 0.5, 0.5) },
+    Metal::new(Color::new(0.7, 0.7, 0.7), 0.1),
+    Checker::new(SolidColor::new_gray(0.0), SolidColor::new_gray(1.0), 2.0),
+  );
+  let material_left = MixMaterial::new(
+    Lambertian { albedo: Color::new(0.8, 0.3, 0.1) },
+    Metal::new(Color::new(0.8, 0.6, 0.2), 0.3),
+    0.5,
+  );
+  let material_center = CoatedMaterial::new(Lambertian { albedo: Color::new(0.7, 0.05, 0.05) }, 1.5);
+  let material_right = CoatedMaterial::new(Metal::new(Color::new(0.2, 0.4, 0.8), 0.6), 1.5);
+
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, material_left));
+  world.add(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, material_center));
+  world.add(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, material_right));
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 0.5, 2.0),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    40.0,
+    aspect_ratio,
+  );
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+// Translucent wax, skin and marble balls with subsurface scattering
+pub fn subsurface_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.5, 0.5, 0.5) };
+  let ball = |x: f64| Sphere::new(Point3::new(x, 0.0, -1.0), 0.5, DefaultMaterial::new());
+
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  // Wax: long mean free path, light glows through
+  world.add(Subsurface::new(ball(-1.0), 1.4, Color::new(0.3, 0.2, 0.1), Color::new(0.99, 0.95, 0.8)));
+  // Skin: red travels further than blue
+  world.add(Subsurface::new(ball(0.0), 1.4, Color::new(0.12, 0.04, 0.02), Color::new(0.98, 0.85, 0.75)));
+  // Marble: short mean free path, close to a diffuse surface
+  world.add(Subsurface::new(ball(1.0), 1.5, Color::new(0.01, 0.01, 0.01), Color::new(0.99, 0.99, 0.99)));
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 0.5, 2.0),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    40.0,
     aspect_ratio,
-    0.1,
-    10.0,
   );
 
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+// A checkered fence cut out by an alpha mask, in front of a half transparent ball
+pub fn cutout_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.5, 0.5, 0.5) };
+  let material_fence = || Lambertian { albedo: Color::new(0.6, 0.4, 0.2) };
+  let fence_mask = || Checker::new(SolidColor::new_gray(1.0), SolidColor::new_gray(0.0), 8.0);
+  let (a, b, c, d) = (
+    Point3::new(-2.0, -0.5, -0.3),
+    Point3::new(2.0, -0.5, -0.3),
+    Point3::new(2.0, 0.6, -0.3),
+    Point3::new(-2.0, 0.6, -0.3),
+  );
+
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(AlphaMasked::new(Triangle::new(a, b, c, material_fence()), fence_mask(), 0.5));
+  world.add(AlphaMasked::new(Triangle::new(a, c, d, material_fence()), fence_mask(), 0.5));
+  world.add(Sphere::new(Point3::new(-1.0, 0.0, -1.2), 0.5, Metal::new(Color::new(0.8, 0.8, 0.8), 0.0)));
+  world.add(AlphaMasked::new_stochastic(
+    Sphere::new(Point3::new(1.0, 0.0, -1.2), 0.5, Lambertian { albedo: Color::new(0.1, 0.5, 0.1) }),
+    SolidColor::new_gray(0.5),
+  ));
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 0.5, 2.0),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    40.0,
+    aspect_ratio,
+  );
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+// Normal and bump mapped spheres
+pub fn bump_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = BumpMapped::new(
+    Lambertian { albedo: Color::new(0.5, 0.5, 0.5) },
+    Ripples { frequency: 20.0 },
+    0.05,
+  );
+  // Facets tilting alternately left and right
+  let material_left = NormalMapped::new(
+    Metal::new(Color::new(0.8, 0.6, 0.2), 0.1),
+    Checker::new(SolidColor::new(Color::new(0.3, 0.5, 1.0)), SolidColor::new(Color::new(0.7, 0.5, 1.0)), 8.0),
+  );
+  let material_center = BumpMapped::new(Lambertian { albedo: Color::new(0.7, 0.1, 0.1) }, Ripples { frequency: 40.0 }, 0.05);
+  let material_right = BumpMapped::new(Dielectric::new(1.5), Ripples { frequency: 30.0 }, 0.02);
+
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, material_left));
+  world.add(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, material_center));
+  world.add(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, material_right));
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 0.5, 2.0),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    40.0,
+    aspect_ratio,
+  );
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+/**
+ * Height field of concentric-looking waves, for bump mapping.
+ **/
+struct Ripples {
+  frequency: f64,
+}
+
+impl Texture for Ripples {
+  fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+    let h = 0.5 + 0.5 * (self.frequency * p.x).sin() * (self.frequency * p.z).sin();
+    Color::new(h, h, h)
+  }
+}
+
+
+// Constructive solid geometry: a biconvex lens, a bitten ball and a hollow shell
+pub fn csg_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.5, 0.5, 0.5) };
+  let glass = Dielectric::new(1.5);
+  let steel = || Metal::new(Color::new(0.7, 0.7, 0.75), 0.2);
+  let paint = Lambertian { albedo: Color::new(0.7, 0.2, 0.1) };
+
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  // Lens: overlap of two large spheres
+  world.add(Csg::intersection(
+    Sphere::new(Point3::new(-1.0, 0.0, -2.2), 1.3, glass),
+    Sphere::new(Point3::new(-1.0, 0.0, 0.2), 1.3, glass),
+  ));
+  // Machined part: a steel ball with a spherical cut, holding a painted ball
+  world.add(Csg::union(
+    Csg::difference(
+      Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, steel()),
+      Sphere::new(Point3::new(0.3, 0.3, -0.7), 0.4, steel()),
+    ),
+    Sphere::new(Point3::new(0.3, 0.3, -0.7), 0.2, paint),
+  ));
+  world.add(Csg::difference(
+    Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, glass),
+    Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.45, glass),
+  ));
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 0.5, 2.0),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    40.0,
+    aspect_ratio,
+  );
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+// Analytic shapes: cylinder, cone, torus, capsule and paraboloid, in a bounding volume hierarchy
+pub fn shapes_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let mut objects = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.5, 0.5, 0.5) };
+  let steel = || Metal::new(Color::new(0.7, 0.7, 0.75), 0.1);
+  let paint = |r: f64, g: f64, b: f64| Lambertian { albedo: Color::new(r, g, b) };
+
+  objects.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  objects.add(Cylinder::new(Point3::new(-1.6, -0.5, -1.4), Point3::new(-1.6, 0.3, -1.4), 0.3, paint(0.2, 0.3, 0.7)));
+  objects.add(Cone::new(Point3::new(-0.8, -0.5, -1.0), Point3::new(-0.8, 0.4, -1.0), 0.35, paint(0.8, 0.5, 0.1)));
+  objects.add(Torus::new(Point3::new(0.0, -0.1, -1.0), Vec3::new(0.0, 1.0, 1.0), 0.3, 0.1, steel()));
+  objects.add(Capsule::new(Point3::new(0.6, -0.35, -0.8), Point3::new(1.0, 0.2, -1.2), 0.15, paint(0.1, 0.6, 0.2)));
+  objects.add(Paraboloid::new(Point3::new(1.6, -0.5, -1.4), Point3::new(1.6, 0.2, -1.4), 0.4, steel()));
+  // A single cylinder lying on its side
+  objects.add(Cylinder::new(Point3::new(-0.6, -0.4, -0.2), Point3::new(0.2, -0.4, 0.0), 0.1, Dielectric::new(1.5)));
+  world.add(BvhNode::new(objects, 0.0, 1.0));
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 1.0, 2.5),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    40.0,
+    aspect_ratio,
+  );
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+pub fn sdf_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.5, 0.5, 0.5) };
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+
+  // Rounded box melting into a ball
+  let blob = SmoothUnion {
+    a: RoundBox { center: Point3::new(-1.0, -0.3, -1.0), half_size: Vec3::new(0.25, 0.15, 0.25), radius: 0.05 },
+    b: SdfSphere { center: Point3::new(-1.0, 0.05, -1.0), radius: 0.2 },
+    k: 0.15,
+  };
+  let bounds = |center: Point3, half: f64| Aabb::new(center - Vec3::new(half, half, half), center + Vec3::new(half, half, half));
+  world.add(SdfShape::new(blob, bounds(Point3::new(-1.0, -0.15, -1.0), 0.45), Lambertian { albedo: Color::new(0.8, 0.3, 0.3) }));
+  let bulb = Mandelbulb::new(Point3::new(0.0, 0.0, -1.0), 0.4);
+  world.add(SdfShape::new(bulb, bounds(Point3::new(0.0, 0.0, -1.0), 0.5), Lambertian { albedo: Color::new(0.8, 0.7, 0.4) }));
+  // Ball with a hole drilled through, from a closure
+  let drilled = |p: &Point3| {
+    let d = *p - Point3::new(1.0, 0.0, -1.0);
+    let hole = (d.x * d.x + d.y * d.y).sqrt() - 0.2;
+    (d.length() - 0.45).max(-hole)
+  };
+  world.add(SdfShape::new(drilled, bounds(Point3::new(1.0, 0.0, -1.0), 0.5), Metal::new(Color::new(0.7, 0.7, 0.75), 0.1)));
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 0.5, 2.0),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    40.0,
+    aspect_ratio,
+  );
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+// A valley between procedural hills, with a height map traversed cell by cell
+pub fn heightfield_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  // Flat along the middle, rising into rolling hills on both sides; `HeightMap::load` reads the
+  // same from a PGM or PNG image
+  let terrain = HeightMap::from_fn(128, 128, |x, z| {
+    let side = utils::clamp(((6.0 * x - 3.0).abs() - 0.9) / 1.5, 0.0, 1.0);
+    let hills = 0.6 + 0.3 * (11.0 * z).sin() * (7.0 * x).cos() + 0.1 * (29.0 * x + 23.0 * z).sin();
+    side * side * (3.0 - 2.0 * side) * hills
+  }).expect("invalid terrain");
+  let to_world = Transform::scale(Vec3::new(6.0, 1.2, 6.0))
+    .then(&Transform::translate(Vec3::new(-3.0, -0.5, -4.5)));
+  world.add(Heightfield::new(terrain, to_world, Lambertian { albedo: Color::new(0.4, 0.5, 0.3) }));
+  world.add(Sphere::new(Point3::new(-0.4, -0.25, -1.2), 0.25, Metal::new(Color::new(0.8, 0.6, 0.2), 0.0)));
+  world.add(Sphere::new(Point3::new(0.4, -0.25, -1.8), 0.25, Dielectric::new(1.5)));
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 0.5, 2.0),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    40.0,
+    aspect_ratio,
+  );
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+pub fn hair_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let mut objects = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.4, 0.35, 0.25) };
+  objects.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+
+  // Fur ball: tubes growing out of a core, drooping a little
+  let center = Point3::new(0.0, -0.05, -1.0);
+  let fur = || KajiyaKay::new(Color::new(0.45, 0.25, 0.1), Color::new(0.3, 0.3, 0.3), 40.0);
+  objects.add(Sphere::new(center, 0.28, Lambertian { albedo: Color::new(0.2, 0.1, 0.05) }));
+  let down = Vec3::new(0.0, -1.0, 0.0);
+  for _ in 0..3000 {
+    let n = Vec3::random_unit_vec();
+    let root = center + 0.27 * n;
+    let length = utils::random_double_in_range(0.12, 0.18);
+    let cp = [
+      root,
+      root + length / 3.0 * n,
+      root + 2.0 * length / 3.0 * n + 0.2 * length * down,
+      root + length * n + 0.4 * length * down,
+    ];
+    objects.add(Curve::new(cp, 0.008, 0.002, CurveKind::Cylinder, fur()));
+  }
+
+  // Grass blades: ribbons narrowing to a tip, bent by the wind
+  let wind = Vec3::new(1.0, 0.0, 0.3);
+  for _ in 0..3000 {
+    let root = Point3::new(utils::random_double_in_range(-2.0, 2.0), -0.5, utils::random_double_in_range(-2.5, 0.0));
+    if (root - Point3::new(center.x, -0.5, center.z)).length() < 0.3 {
+      continue;
+    }
+    let height = utils::random_double_in_range(0.1, 0.3);
+    let bend = utils::random_double_in_range(0.0, 0.4) * height;
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let cp = [
+      root,
+      root + height / 3.0 * up,
+      root + 2.0 * height / 3.0 * up + 0.3 * bend * wind,
+      root + height * up + bend * wind,
+    ];
+    let green = Color::new(0.1, utils::random_double_in_range(0.3, 0.5), 0.05);
+    objects.add(Curve::new(cp, 0.02, 0.0, CurveKind::Ribbon, Lambertian { albedo: green }));
+  }
+  world.add(BvhNode::new(objects, 0.0, 1.0));
+
+  let camera = PerspectiveCamera::new_free_camera(
+    Point3::new(0.0, 0.5, 2.0),
+    Point3::new(0.0, 0.0, -1.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    40.0,
+    aspect_ratio,
+  );
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+pub fn projections_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.8, 0.8, 0.0) };
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, Dielectric::new(1.5)));
+  world.add(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, Lambertian { albedo: Color::new(0.1, 0.2, 0.5) }));
+  world.add(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, Metal::new(Color::new(0.8, 0.6, 0.2), 0.0)));
+
+  // Same view through each projection, one per quarter of the image
+  let lookfrom = Point3::new(0.0, 0.5, 2.0);
+  let lookat = Point3::new(0.0, 0.0, -1.0);
+  let vup = Vec3::new(0.0, 1.0, 0.0);
+  let cameras: Vec<Box<dyn Camera>> = vec![
+    Box::new(PerspectiveCamera::new_free_camera(lookfrom, lookat, vup, 60.0, aspect_ratio)),
+    Box::new(OrthographicCamera::new(lookfrom, lookat, vup, 2.0, aspect_ratio)),
+    Box::new(FisheyeCamera::new(lookfrom, lookat, vup, 180.0, aspect_ratio)),
+    Box::new(EquirectangularCamera::new(lookfrom, lookat, vup)),
+  ];
+  let (half_width, half_height) = (image_width / 2, image_height / 2);
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let camera = &cameras[(i / half_width) as usize + 2 * (1 - j / half_height) as usize];
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = ((i % half_width) as f64 + utils::random_double()) / (half_width - 1) as f64;
+        let v = ((j % half_height) as f64 + utils::random_double()) / (half_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+pub fn realistic_lens_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.8, 0.8, 0.0) };
+  world.add(Sphere::new(Point3::new(0.0, -1000.5, -1.0), 1000.0, material_ground));
+  // A row of spheres going away from the camera, the focus is on the nearest one
+  for i in 0..8 {
+    let z = 1.0 - 1.5 * i as f64;
+    let x = if i % 2 == 0 { -0.6 } else { 0.6 };
+    let albedo = Color::new(0.1 + 0.1 * i as f64, 0.2, 0.5 - 0.05 * i as f64);
+    if i % 3 == 1 {
+      world.add(Sphere::new(Point3::new(x, 0.0, z), 0.5, Metal::new(Color::new(0.8, 0.6, 0.2), 0.0)));
+    } else {
+      world.add(Sphere::new(Point3::new(x, 0.0, z), 0.5, Lambertian { albedo }));
+    }
+  }
+  // Small bright spheres far behind, blurred into the shape of the aperture
+  for i in 0..12 {
+    let light = DiffuseLight::new(Color::new(8.0, 7.0, 5.0));
+    world.add(Sphere::new(Point3::new(-3.3 + 0.6 * i as f64, 0.6 + (i % 3) as f64 * 0.2, -12.0), 0.05, light));
+  }
+
+  // Double Gauss 50mm lens wide open on a 35mm film diagonal
+  let lens = Lens::parse(include_str!("../../lenses/dgauss.50mm.dat")).expect("invalid lens");
+  let lookfrom = Point3::new(0.0, 0.5, 3.0);
+  let lookat = Point3::new(0.0, 0.0, -1.0);
+  let focus_dist = (Point3::new(-0.6, 0.0, 1.0) - lookfrom).length();
+  let camera = RealisticCamera::new(lookfrom, lookat, Vec3::new(0.0, 1.0, 0.0), lens, focus_dist, 35.0, aspect_ratio)
+    .expect("invalid camera");
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.generate_weighted_ray((u, v), (utils::random_double(), utils::random_double()));
+        if weight > 0.0 {
+          pixel_color += weight * utils::volume_ray_color(&r, &world, max_depth);
+        }
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+pub fn bokeh_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.8, 0.8, 0.0) };
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, Lambertian { albedo: Color::new(0.1, 0.2, 0.5) }));
+  // Out of focus lights far behind
+  for i in 0..9 {
+    for j in 0..5 {
+      let light = DiffuseLight::new(Color::new(12.0, 9.0, 5.0));
+      world.add(Sphere::new(Point3::new(-8.0 + 2.0 * i as f64, 1.0 + 1.5 * j as f64, -20.0), 0.15, light));
+    }
+  }
+
+  let mut scene = Scene::new(world);
+  scene.background = Background::Solid(Color::new(0.01, 0.01, 0.02));
+  scene.add_light(PointLight::new(Point3::new(-2.0, 2.0, 1.0), Color::new(18.0, 15.0, 12.0)));
+
+  // Same view, left half through a hexagonal aperture with cat's eye vignetting, right half
+  // through a heart shaped mask
+  let heart = ApertureMask::from_fn(64, 64, |x, y| {
+    let (x, y) = (1.2 * x, 1.2 * y + 0.25);
+    let a = x * x + y * y - 1.0;
+    if a * a * a - x * x * y * y * y <= 0.0 { 1.0 } else { 0.0 }
+  }).expect("invalid aperture mask");
+  let half_aspect = aspect_ratio / 2.0;
+  let builder = PerspectiveCamera::builder()
+    .look_from(Point3::new(0.0, 0.5, 2.0))
+    .look_at(Point3::new(0.0, 0.0, -1.0))
+    .vup(Vec3::new(0.0, 1.0, 0.0))
+    .vfov(40.0)
+    .aspect(half_aspect)
+    .aperture(0.3);
+  let cameras = [
+    builder.clone().aperture_shape(Aperture::polygon(6, 90.0)).cat_eye(0.4).build().expect("invalid camera"),
+    builder.aperture_shape(Aperture::mask(heart)).build().expect("invalid camera"),
+  ];
+  let half_width = image_width / 2;
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let camera = &cameras[(i / half_width) as usize];
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = ((i % half_width) as f64 + utils::random_double()) / (half_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.generate_weighted_ray((u, v), (utils::random_double(), utils::random_double()));
+        if weight > 0.0 {
+          pixel_color += weight * utils::lit_ray_color(&r, &scene, max_depth);
+        }
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+pub fn turntable_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let max_depth = 50;
+  // Two seconds at 24 frames per second, rendered to frames/frame_0001.png...
+  let mut sequence = Sequence::new(192, 108, 24.0, 48);
+  sequence.samples_per_pixel = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.8, 0.8, 0.0) };
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, Dielectric::new(1.5)));
+  world.add(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, Lambertian { albedo: Color::new(0.1, 0.2, 0.5) }));
+  world.add(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, Metal::new(Color::new(0.8, 0.6, 0.2), 0.0)));
+
+  // One turn around the spheres through 8 keys, zooming in on the way
+  let center = Point3::new(0.0, 0.0, -1.0);
+  let mut orbit = Track::new(0.0, center + Vec3::new(0.0, 0.8, 3.0)).interpolation(Interpolation::CatmullRom);
+  for k in 1..=8 {
+    let angle = PI / 4.0 * k as f64;
+    orbit = orbit.key(0.25 * k as f64, center + Vec3::new(3.0 * angle.sin(), 0.8, 3.0 * angle.cos()));
+  }
+  let camera = AnimatedCamera::new_with_lens(
+    orbit,
+    Track::new(0.0, center),
+    Vec3::new(0.0, 1.0, 0.0),
+    Track::new(0.0, 50.0).key(2.0, 35.0).interpolation(Interpolation::CatmullRom),
+    aspect_ratio,
+    0.05,
+  );
+
+  let frames = sequence.render(&camera, Path::new("frames"), |r| utils::metal_ray_color(r, &world, max_depth));
+  if let Err(e) = frames {
+    eprintln!("Could not render the sequence: {}", e);
+  }
+}
+
+
+pub fn motion_blur_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.8, 0.8, 0.0) };
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+
+  let mut objects = HittableList::new();
+  // Bouncing ball: up and down again during the exposure
+  let ball = Sphere::new(Point3::new(0.0, 0.0, 0.0), 0.3, Lambertian { albedo: Color::new(0.7, 0.2, 0.2) });
+  objects.add(Animated::new(ball).translation(
+    Track::new(0.0, Vec3::new(-1.2, -0.2, -1.0))
+      .key(0.5, Vec3::new(-0.9, 0.6, -1.0))
+      .key(1.0, Vec3::new(-0.6, -0.2, -1.0))
+      .interpolation(Interpolation::CatmullRom),
+  ));
+  // Spinning bar, a quarter turn around the view direction
+  let bar = Capsule::new(Point3::new(-0.6, 0.0, 0.0), Point3::new(0.6, 0.0, 0.0), 0.1, Metal::new(Color::new(0.8, 0.8, 0.9), 0.1));
+  let z = Vec3::new(0.0, 0.0, 1.0);
+  objects.add(Animated::new(bar)
+    .rotation(Track::new(0.0, Quaternion::identity()).key(1.0, Quaternion::from_axis_angle(z, 90.0)))
+    .translation(Track::new(0.0, Vec3::new(0.3, 0.3, -1.5))));
+  // Growing ball, and a still one for reference
+  let growing = Sphere::new(Point3::new(0.0, 0.0, 0.0), 0.3, Lambertian { albedo: Color::new(0.1, 0.2, 0.5) });
+  objects.add(Animated::new(growing)
+    .scale(Track::new(0.0, Vec3::new(0.5, 0.5, 0.5)).key(1.0, Vec3::new(1.0, 1.0, 1.0)))
+    .translation(Track::new(0.0, Vec3::new(1.2, -0.2, -1.0))));
+  objects.add(Sphere::new(Point3::new(1.2, -0.3, -2.2), 0.2, Lambertian { albedo: Color::new(0.2, 0.6, 0.2) }));
+  world.add(BvhNode::new(objects, 0.0, 1.0));
+
+  let camera = PerspectiveCamera::builder()
+    .look_from(Point3::new(0.0, 0.5, 2.0))
+    .look_at(Point3::new(0.0, 0.0, -1.0))
+    .vup(Vec3::new(0.0, 1.0, 0.0))
+    .vfov(40.0)
+    .aspect(aspect_ratio)
+    .shutter(0.0, 1.0)
+    .build()
+    .expect("invalid camera");
+
+  println!("P3\n{0} {1}\n255\n", image_width, image_height);
+
+  for j in (0..image_height).rev() {
+    eprintln!("\rScanlines remaining: {0} ", j);
+
+    for i in 0..image_width {
+      let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+      for _s in 0..samples_per_pixel {
+        let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
+        let v = (j as f64 + utils::random_double()) / (image_height - 1) as f64;
+        let (r, weight) = camera.get_ray(u, v);
+        pixel_color += weight * utils::metal_ray_color(&r, &world, max_depth);
+      }
+      pixel_color.write_color_gamma_corrected(samples_per_pixel);
+    }
+  }
+}
+
+
+pub fn stereo_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let eye_width = 192;
+  let eye_height = (eye_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.8, 0.8, 0.0) };
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, Dielectric::new(1.5)));
+  world.add(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, Lambertian { albedo: Color::new(0.1, 0.2, 0.5) }));
+  world.add(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, Metal::new(Color::new(0.8, 0.6, 0.2), 0.0)));
+  world.add(Sphere::new(Point3::new(0.3, -0.3, 0.6), 0.2, Lambertian { albedo: Color::new(0.7, 0.2, 0.2) }));
+  let ray_color = |r: &Ray| utils::metal_ray_color(r, &world, max_depth);
+
+  // Side by side pair converging on the middle sphere, for cross-eyed viewing swap the halves
+  let builder = PerspectiveCamera::builder()
+    .look_from(Point3::new(0.0, 0.5, 2.0))
+    .look_at(Point3::new(0.0, 0.0, -1.0))
+    .vup(Vec3::new(0.0, 1.0, 0.0))
+    .vfov(40.0)
+    .aspect(aspect_ratio);
+  let (left, right) = StereoCamera::pair(&builder, 0.1, 3.0).expect("invalid camera");
+  let image = StereoLayout::SideBySide.render((&left, &right), eye_width, eye_height, samples_per_pixel, &ray_color);
+
+  println!("P3\n{0} {1}\n255\n", image.width(), image.height());
+  for j in (0..image.height()).rev() {
+    for i in 0..image.width() {
+      image.get(i, j).write_color_gamma_corrected(1);
+    }
+  }
+
+  // Omni-directional stereo panorama for headsets, left eye on top
+  let (left, right) = OdsCamera::pair(Point3::new(0.0, 0.2, 0.0), Point3::new(0.0, 0.2, -1.0), Vec3::new(0.0, 1.0, 0.0), 0.064)
+    .expect("invalid camera");
+  let panorama = StereoLayout::TopBottom.render((&left, &right), 2 * eye_width, eye_width, samples_per_pixel, &ray_color);
+  if let Err(e) = panorama.write_png("ods.png") {
+    eprintln!("Could not write the panorama: {}", e);
+  }
+}
+
+
+pub fn exposure_scene() {
+  let aspect_ratio = 2.0 / 3.0;
+  let panel_width = 128;
+  let panel_height = (panel_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 100;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.8, 0.8, 0.0) };
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(Sphere::new(Point3::new(-0.6, 0.0, -2.5), 0.5, Metal::new(Color::new(0.8, 0.6, 0.2), 0.0)));
+  world.add(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, Lambertian { albedo: Color::new(0.1, 0.2, 0.5) }));
+  world.add(Sphere::new(Point3::new(0.4, -0.3, 0.6), 0.2, Lambertian { albedo: Color::new(0.7, 0.2, 0.2) }));
+  let ray_color = |r: &Ray| utils::metal_ray_color(r, &world, max_depth);
+
+  // A scene in decimeters shot with a full frame camera
+  let builder = PerspectiveCamera::builder()
+    .look_from(Point3::new(0.0, 0.5, 2.0))
+    .look_at(Point3::new(0.0, 0.0, -1.0))
+    .vup(Vec3::new(0.0, 1.0, 0.0))
+    .vfov(40.0)
+    .aspect(aspect_ratio)
+    .film_height(0.24)
+    .autofocus(&world, &AutoFocus::default())
+    .expect("invalid camera");
+  // Left to right: wide open with a fast shutter and stopped down with a slow one expose the same,
+  // opening up two stops at the same shutter speed is four times brighter
+  let settings = [
+    Exposure::new(2.0, 1.0 / 6400.0, 100.0),
+    Exposure::new(16.0, 1.0 / 100.0, 100.0),
+    Exposure::new(8.0, 1.0 / 100.0, 100.0),
+  ];
+
+  let mut image = Image::new(settings.len() as u32 * panel_width, panel_height);
+  for (k, exposure) in settings.iter().enumerate() {
+    let camera = builder.clone().exposure(*exposure).build().expect("invalid camera");
+    let panel = render_image(&camera, panel_width, panel_height, samples_per_pixel, &ray_color);
+    for j in 0..panel_height {
+      for i in 0..panel_width {
+        image.set(k as u32 * panel_width + i, j, panel.get(i, j));
+      }
+    }
+  }
+
+  println!("P3\n{0} {1}\n255\n", image.width(), image.height());
+  for j in (0..image.height()).rev() {
+    for i in 0..image.width() {
+      image.get(i, j).write_color_gamma_corrected(1);
+    }
+  }
+}
+
+
+pub fn crop_scene() {
+  let aspect_ratio = 16.0 / 9.0;
+  let image_width = 384;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let max_depth = 50;
+
+  let mut world = HittableList::new();
+  let material_ground = Lambertian { albedo: Color::new(0.8, 0.8, 0.0) };
+  world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material_ground));
+  world.add(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, Dielectric::new(1.5)));
+  world.add(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, Lambertian { albedo: Color::new(0.1, 0.2, 0.5) }));
+  world.add(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, Metal::new(Color::new(0.8, 0.6, 0.2), 0.0)));
+  let ray_color = |r: &Ray| utils::metal_ray_color(r, &world, max_depth);
+
+  let camera = PerspectiveCamera::builder()
+    .look_from(Point3::new(0.0, 0.5, 2.0))
+    .look_at(Point3::new(0.0, 0.0, -1.0))
+    .vup(Vec3::new(0.0, 1.0, 0.0))
+    .vfov(40.0)
+    .aspect(aspect_ratio)
+    .aperture(0.1)
+    .build()
+    .expect("invalid camera");
+
+  // Quick preview of the whole frame, then the glass sphere again with many more samples
+  let full = Region::full(image_width, image_height);
+  let mut image = render_region(&camera, image_width, image_height, &full, 4, 1, &ray_color);
+  let detail = Region::crop(image_width, image_height, (0.1, 0.2), (0.45, 0.8));
+  render_into(&camera, &mut image, &detail, 200, 2, &ray_color);
+  // The detail alone, cropped at the geometry of the full frame: the same pixels as in the image
+  let patch = render_region(&camera, image_width, image_height, &detail, 200, 2, &ray_color);
+  if let Err(e) = patch.write_png("crop.png") {
+    eprintln!("Could not write the crop: {}", e);
+  }
+
+  println!("P3\n{0} {1}\n255\n", image.width(), image.height());
+  for j in (0..image.height()).rev() {
+    for i in 0..image.width() {
+      image.get(i, j).write_color_gamma_corrected(1);
+    }
+  }
+}
+
+
+// Final scene
+pub fn final_scene() {
+  const N_THREAD: usize = 20;  // Concurrent thread amount
+  let aspect_ratio = 3.0 / 2.0;
+  let image_width = 1200;
+  let image_height = (image_width as f64 / aspect_ratio) as u32;
+  let samples_per_pixel = 500;
+  let max_depth = 50;
+
+  let world = Arc::new(utils::random_scene());
+
+  let lookfrom = Point3::new(5.0, 2.0, 1.0);  // Camera poition for defocus cases
+  let lookat = Point3::new(-2.0, 0.0, 0.0);
+  
+  let camera = PerspectiveCamera::builder()
+    .look_from(lookfrom)
+    .look_at(lookat)
+    .vup(Vec3::new(0.0, 1.0, 0.0))
+    // .vfov(90.0)  // Wide angle lens
+    .vfov(20.0)  // Long focal lens
+    .aspect(aspect_ratio)
+    .aperture(0.1)
+    // Nearest subject around the middle of the frame
+    .autofocus(world.as_ref(), &AutoFocus::default().cone(0.02, 16))
+    .and_then(|builder| builder.build())
+    .expect("invalid camera");
+
   let timer = time::SystemTime::now();
   println!("P3\n{0} {1}\n255\n", image_width, image_height);
 
@@ -264,6 +1617,7 @@ pub fn final_scene() {
   for t in 0..N_THREAD {
     let thread_tx = tx.clone();
     let thread_world = world.clone();
+    let camera = camera.clone();
     // Each thread do partial sampling value
     children.push(thread::spawn(move || {
       let mut colors = vec![vec![]];
@@ -280,9 +1634,8 @@ pub fn final_scene() {
           for _s in 0..samples_per_pixel  {
             let u = (i as f64 + utils::random_double()) / (image_width - 1) as f64;
             let v = (thread_j as f64 + utils::random_double()) / (image_height - 1) as f64;
-            // let r: Ray = camera.get_ray(u, v);  // No defocus blur 
-            let r: Ray = camera.get_ray_for_lens(u, v);  // With defocus blur
-            pixel_color += utils::metal_ray_color(&r, &thread_world, max_depth);
+            let (r, weight) = camera.get_ray(u, v);
+            pixel_color += weight * utils::metal_ray_color(&r, &thread_world, max_depth);
           }
           row_colors.push(pixel_color);
         }
@@ -0,0 +1,134 @@
+use crate::core::{
+  color::Color,
+  ray::Ray,
+  vec3::Vec3,
+};
+use crate::geometry::hit::{HitRecord, Hittable, HittableList};
+use crate::lights::Light;
+
+/**
+ * What a ray sees when it leaves the scene.
+ **/
+#[derive(Clone, Copy, Debug)]
+pub enum Background {
+  // The white-to-blue gradient of the book
+  Sky,
+  Solid(Color),
+}
+
+impl Background {
+  pub fn color(&self, r: &Ray) -> Color {
+    match self {
+      Background::Sky => {
+        let unit_direction: Vec3 = r.direction.unit();
+        let t = 0.5 * (unit_direction.y + 1.0);
+        (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0)
+      },
+      Background::Solid(color) => *color,
+    }
+  }
+}
+
+/**
+ * The objects to render together with the analytic lights illuminating them.
+ **/
+pub struct Scene {
+  pub world: HittableList,
+  pub lights: Vec<Box<dyn Light>>,
+  pub background: Background,
+  // Let shadow rays pass through transmissive occluders (glass), for caustic-free previews
+  pub ignore_transmissive_shadows: bool,
+}
+
+impl Scene {
+  pub fn new(world: HittableList) -> Self {
+    Scene {
+      world,
+      lights: Vec::new(),
+      background: Background::Sky,
+      ignore_transmissive_shadows: false,
+    }
+  }
+
+  pub fn add_light<L: Light + 'static>(&mut self, light: L) {
+    self.lights.push(Box::new(light));
+  }
+
+  /**
+   * Light reaching the hit point directly from the analytic lights, tested with shadow rays.
+   **/
+  pub fn direct_lighting(&self, r_in: &Ray, record: &HitRecord) -> Color {
+    let mut color = Color::new(0.0, 0.0, 0.0);
+    for light in self.lights.iter() {
+      if let Some(sample) = light.illuminate(&record.point) {
+        if let Some(f) = record.material.eval(r_in, record, &sample.direction) {
+          if f == Color::new(0.0, 0.0, 0.0) {
+            continue;
+          }
+          let shadow_ray = Ray::new_with_time(record.point, sample.direction, r_in.time);
+          let transmittance = self.world.shadow_transmittance(
+            &shadow_ray,
+            0.001,
+            sample.distance,
+            self.ignore_transmissive_shadows,
+          );
+          color += f * sample.radiance * transmittance;
+        }
+      }
+    }
+    color
+  }
+}
+
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::core::point3::Point3;
+  use crate::geometry::sphere::Sphere;
+  use crate::lights::point::PointLight;
+  use crate::materials::{
+    lambertian::Lambertian,
+    dielectric::Dielectric,
+  };
+
+  fn lit_from_above(occluder_is_glass: bool) -> Scene {
+    let mut world = HittableList::new();
+    world.add(Sphere::new(Point3::new(0.0, -100.0, 0.0), 100.0, Lambertian { albedo: Color::new(1.0, 1.0, 1.0) }));
+    if occluder_is_glass {
+      world.add(Sphere::new(Point3::new(0.0, 2.0, 0.0), 0.5, Dielectric::new(1.5)));
+    } else {
+      world.add(Sphere::new(Point3::new(0.0, 2.0, 0.0), 0.5, Lambertian { albedo: Color::new(0.5, 0.5, 0.5) }));
+    }
+    let mut scene = Scene::new(world);
+    scene.add_light(PointLight::new(Point3::new(0.0, 4.0, 0.0), Color::new(16.0, 16.0, 16.0)));
+    scene
+  }
+
+  fn direct_at_origin(scene: &Scene, from: Point3) -> Color {
+    let r = Ray::new(from, Point3::new(0.0, 0.0, 0.0) - from);
+    let record = scene.world.hit(&r, 0.001, f64::INFINITY).unwrap();
+    scene.direct_lighting(&r, &record)
+  }
+
+  #[test]
+  fn test_shadow() {
+    let scene = lit_from_above(false);
+    assert_eq!(direct_at_origin(&scene, Point3::new(3.0, 1.0, 0.0)), Color::new(0.0, 0.0, 0.0));
+
+    // Move the light aside, the ground gets lit
+    let mut scene = lit_from_above(false);
+    scene.lights.clear();
+    scene.add_light(PointLight::new(Point3::new(0.0, 4.0, 4.0), Color::new(32.0, 32.0, 32.0)));
+    assert!(direct_at_origin(&scene, Point3::new(3.0, 1.0, 0.0)) != Color::new(0.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn test_ignore_transmissive_shadows() {
+    let mut scene = lit_from_above(true);
+    assert_eq!(direct_at_origin(&scene, Point3::new(3.0, 1.0, 0.0)), Color::new(0.0, 0.0, 0.0));
+
+    scene.ignore_transmissive_shadows = true;
+    assert!(direct_at_origin(&scene, Point3::new(3.0, 1.0, 0.0)) != Color::new(0.0, 0.0, 0.0));
+  }
+}
@@ -0,0 +1,49 @@
+use crate::core::{
+  color::Color,
+  point3::Point3,
+};
+use super::Texture;
+
+/**
+ * 3D checker pattern alternating between two textures, `scale` being the number of
+ * cells per unit length.
+ **/
+pub struct Checker<E: Texture, O: Texture> {
+  pub even: E,
+  pub odd: O,
+  pub scale: f64,
+}
+
+impl<E: Texture, O: Texture> Checker<E, O> {
+  pub fn new(even: E, odd: O, scale: f64) -> Self {
+    Checker { even, odd, scale }
+  }
+}
+
+impl<E: Texture, O: Texture> Texture for Checker<E, O> {
+  fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+    let cell = (self.scale * p.x).floor() + (self.scale * p.y).floor() + (self.scale * p.z).floor();
+    if cell as i64 % 2 == 0 {
+      self.even.value(u, v, p)
+    } else {
+      self.odd.value(u, v, p)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::textures::SolidColor;
+
+  #[test]
+  fn test_checker() {
+    let black = Color::new(0.0, 0.0, 0.0);
+    let white = Color::new(1.0, 1.0, 1.0);
+    let checker = Checker::new(SolidColor::new(white), SolidColor::new(black), 2.0);
+    assert_eq!(checker.value(0.0, 0.0, &Point3::new(0.1, 0.1, 0.1)), white);
+    assert_eq!(checker.value(0.0, 0.0, &Point3::new(0.6, 0.1, 0.1)), black);
+    assert_eq!(checker.value(0.0, 0.0, &Point3::new(-0.1, 0.1, 0.1)), black);
+    assert_eq!(checker.value(0.0, 0.0, &Point3::new(-0.6, 0.1, 0.1)), white);
+  }
+}
@@ -0,0 +1,37 @@
+pub mod checker;
+
+use crate::core::{
+  color::Color,
+  point3::Point3,
+};
+
+/**
+ * Color varying over a surface, looked up from the texture coordinates or the hit point.
+ **/
+pub trait Texture: Send + Sync {
+  fn value(&self, u: f64, v: f64, p: &Point3) -> Color;
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SolidColor {
+  pub color: Color,
+}
+
+impl SolidColor {
+  pub fn new(color: Color) -> Self {
+    Self { color }
+  }
+
+  /**
+   * Gray level, handy for masks and weights.
+   **/
+  pub fn new_gray(value: f64) -> Self {
+    Self::new(Color::new(value, value, value))
+  }
+}
+
+impl Texture for SolidColor {
+  fn value(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
+    self.color
+  }
+}
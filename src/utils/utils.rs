@@ -1,13 +1,17 @@
 use rand::prelude::*;
+#[allow(clippy::useless_attribute, clippy::legacy_numeric_constants)]
 use crate::core::{
   ray::Ray,
   vec3::Vec3,
   point3::Point3,
   color::Color,
+  spectrum::{SampledSpectrum, SampledWavelengths},
+  transform::Transform,
   INFINITY,
 };
 use crate::geometry::{
   sphere::Sphere,
+  heightfield::{HeightMap, Heightfield},
   hit::{Hittable, HittableList}
 };
 use crate::materials::{
@@ -16,7 +20,7 @@ use crate::materials::{
   metal::Metal,
   dielectric::Dielectric,
 };
-
+use crate::scene::Scene;
 
 pub fn random_double() -> f64 {
   let mut rng = rand::thread_rng();
@@ -54,6 +58,7 @@ pub fn shading_ray_color(r: &Ray) -> Color {
   }
 }
 
+#[allow(clippy::legacy_numeric_constants)]
 pub fn world_ray_color(r: &Ray, world: &HittableList) -> Color {
   if let Some(record) = world.hit(r, 0.0, INFINITY) {
     0.5 * (Color::new(1.0, 1.0, 1.0) + record.normal)
@@ -65,6 +70,7 @@ pub fn world_ray_color(r: &Ray, world: &HittableList) -> Color {
   
 }
 
+#[allow(clippy::absurd_extreme_comparisons, clippy::legacy_numeric_constants)]
 pub fn material_ray_color(r: &Ray, world: &HittableList, depth: u32) -> Color {  
   if depth <= 0 {
     return Color::new(0.0, 0.0, 0.0);
@@ -80,6 +86,7 @@ pub fn material_ray_color(r: &Ray, world: &HittableList, depth: u32) -> Color {
   }
 }
 
+#[allow(clippy::absurd_extreme_comparisons, clippy::legacy_numeric_constants, clippy::op_ref)]
 pub fn metal_ray_color(r: &Ray, world: &HittableList, depth: u32) -> Color {  
   if depth <= 0 {
     return Color::new(0.0, 0.0, 0.0);
@@ -98,6 +105,84 @@ pub fn metal_ray_color(r: &Ray, world: &HittableList, depth: u32) -> Color {
   }
 }
 
+/**
+ * `metal_ray_color` with the light emitted by what is hit (lights, glowing volumes) and the
+ * weights hits pick up while being found, like the chromatic weights of volume tracking.
+ **/
+pub fn volume_ray_color(r: &Ray, world: &HittableList, depth: u32) -> Color {
+  if depth == 0 {
+    return Color::new(0.0, 0.0, 0.0);
+  }
+
+  if let Some(record) = world.hit(r, 0.001, f64::INFINITY) {
+    let emitted = record.material.emitted(r, &record);
+    if let Some(scattered_record) = record.material.scatter(r, &record) {
+      return record.weight * (emitted + scattered_record.attenuation * volume_ray_color(&scattered_record.scattered, world, depth-1))
+    }
+
+    record.weight * emitted
+  } else {
+    let unit_direction: Vec3 = r.direction.unit();
+    let t = 0.5 * (unit_direction.y + 1.0);
+    (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0)
+  }
+}
+
+/**
+ * Path tracing with direct lighting from the analytic lights of the scene (shadow rays at each bounce).
+ **/
+pub fn lit_ray_color(r: &Ray, scene: &Scene, depth: u32) -> Color {
+  if depth == 0 {
+    return Color::new(0.0, 0.0, 0.0);
+  }
+
+  if let Some(record) = scene.world.hit(r, 0.001, f64::INFINITY) {
+    let emitted = record.material.emitted(r, &record);
+    let direct = scene.direct_lighting(r, &record);
+    if let Some(scattered_record) = record.material.scatter(r, &record) {
+      return record.weight * (emitted + direct + scattered_record.attenuation * lit_ray_color(&scattered_record.scattered, scene, depth-1))
+    }
+
+    record.weight * (emitted + direct)
+  } else {
+    scene.background.color(r)
+  }
+}
+
+/**
+ * Spectral counterpart of `lit_ray_color`: the ray carries the hero wavelength, colors of
+ * materials and lights are upsampled to spectra at the sampled wavelengths.
+ **/
+pub fn spectral_ray_color(
+  r: &Ray,
+  scene: &Scene,
+  depth: u32,
+  wavelengths: &mut SampledWavelengths,
+) -> SampledSpectrum {
+  if depth == 0 {
+    return SampledSpectrum::new(0.0);
+  }
+
+  if let Some(record) = scene.world.hit(r, 0.001, f64::INFINITY) {
+    if record.material.is_dispersive() {
+      wavelengths.terminate_secondary();
+    }
+    let emitted = record.material.emitted(r, &record) + scene.direct_lighting(r, &record);
+    let emitted = SampledSpectrum::from_rgb(emitted, wavelengths);
+    let weight = SampledSpectrum::from_rgb(record.weight, wavelengths);
+    if let Some(scattered_record) = record.material.scatter(r, &record) {
+      let mut scattered = scattered_record.scattered;
+      scattered.wavelength = r.wavelength;
+      let attenuation = SampledSpectrum::from_rgb(scattered_record.attenuation, wavelengths);
+      return weight * (emitted + attenuation * spectral_ray_color(&scattered, scene, depth-1, wavelengths))
+    }
+
+    weight * emitted
+  } else {
+    SampledSpectrum::from_rgb(scene.background.color(r), wavelengths)
+  }
+}
+
 pub fn clamp(x: f64, min: f64, max: f64) -> f64 {
   if x < min {
     min
@@ -114,14 +199,65 @@ pub fn schlick(cosine: f64, ref_idx: f64) -> f64 {
   r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
 }
 
+/**
+ * Amplitude reflection coefficients (s and p polarized) of a dielectric interface, from the
+ * Fresnel equations. Both are 1 on total internal reflection.
+ * Refer to: https://en.wikipedia.org/wiki/Fresnel_equations
+ **/
+pub fn fresnel_amplitudes(cosine: f64, etai_over_etat: f64) -> (f64, f64) {
+  let cos_i = clamp(cosine, 0.0, 1.0);
+  let sin_t = etai_over_etat * (1.0 - cos_i * cos_i).sqrt();
+  if sin_t >= 1.0 {
+    return (1.0, 1.0);
+  }
+  let cos_t = (1.0 - sin_t * sin_t).sqrt();
+  let r_s = (etai_over_etat * cos_i - cos_t) / (etai_over_etat * cos_i + cos_t);
+  let r_p = (cos_i - etai_over_etat * cos_t) / (cos_i + etai_over_etat * cos_t);
+  (r_s, r_p)
+}
+
+/**
+ * Exact reflectance of a dielectric interface for unpolarized light.
+ **/
+pub fn fresnel_dielectric(cosine: f64, etai_over_etat: f64) -> f64 {
+  let (r_s, r_p) = fresnel_amplitudes(cosine, etai_over_etat);
+  0.5 * (r_s * r_s + r_p * r_p)
+}
+
+/**
+ * Which approximation of the Fresnel reflectance to use.
+ **/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fresnel {
+  Schlick,
+  Exact,
+}
+
+impl Fresnel {
+  pub fn reflectance(&self, cosine: f64, etai_over_etat: f64) -> f64 {
+    match self {
+      Fresnel::Schlick => schlick(cosine, etai_over_etat),
+      Fresnel::Exact => fresnel_dielectric(cosine, etai_over_etat),
+    }
+  }
+}
+
 /**
  * Generate a random scene to be rendered
  * */
 pub fn random_scene() -> HittableList {
   let mut world = HittableList::new();
-  // Add ground
+  // Add ground: flat under the spheres, rising into hills around them
   let material_ground = Lambertian { albedo: Color::new(0.5, 0.5, 0.5) };
-  world.add(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, material_ground));
+  let terrain = HeightMap::from_fn(256, 256, |x, z| {
+    let (x, z) = (100.0 * x - 50.0, 100.0 * z - 50.0);
+    let rise = clamp(((x * x + z * z).sqrt() - 12.0) / 18.0, 0.0, 1.0);
+    let hills = 0.6 + 0.25 * (0.3 * x).sin() * (0.25 * z).cos() + 0.15 * (0.11 * x + 0.17 * z).sin();
+    rise * rise * (3.0 - 2.0 * rise) * hills
+  }).expect("invalid terrain");
+  let to_world = Transform::scale(Vec3::new(100.0, 8.0, 100.0))
+    .then(&Transform::translate(Vec3::new(-50.0, 0.0, -50.0)));
+  world.add(Heightfield::new(terrain, to_world, material_ground));
   
   // Randomly add some spheres
   for a in -8..8 {
@@ -180,6 +316,8 @@ mod test {
     random_double,
     random_double_in_range,
     clamp,
+    schlick,
+    fresnel_dielectric,
   };
 
 
@@ -214,4 +352,15 @@ mod test {
     assert_eq!(clamp(5.0, 7.0, 10.0), 7.0);
     assert_eq!(clamp(5.0, -1.0, 3.5), 3.5);
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn test_fresnel() {
+    // Normal incidence on glass: ((n - 1) / (n + 1))^2 for both approximations
+    assert!((fresnel_dielectric(1.0, 1.0 / 1.5) - 0.04).abs() < 1e-12);
+    assert!((schlick(1.0, 1.0 / 1.5) - 0.04).abs() < 1e-12);
+    // Grazing angles reflect everything
+    assert!((fresnel_dielectric(0.0, 1.0 / 1.5) - 1.0).abs() < 1e-12);
+    // Total internal reflection
+    assert_eq!(fresnel_dielectric(0.2, 1.5), 1.0);
+  }
+}